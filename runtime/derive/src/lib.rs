@@ -0,0 +1,157 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Fields, Ident, Meta, Path, Token};
+
+/**
+ * Implements `Vertex` for a `#[repr(C)]` struct with named
+ * fields, where the fields in the layout are tagged with the
+ * `AttributeKey` variant they're read as, optionally followed
+ * by `normalized`, or `integer` for `int` & `uint` attributes:
+ *
+ * ```ignore
+ * #[vertex(Attributes::Color, normalized)]
+ * color: [u8; 4],
+ * #[vertex(Attributes::Cell, integer)]
+ * cell: [u16; 2],
+ * ```
+ *
+ * The key type is the path of the variant without its last
+ * segment, so every tagged field must use the same type.
+ */
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+  let input = syn::parse_macro_input!(input as DeriveInput);
+  match expand(&input) {
+    Ok(tokens) => tokens.into(),
+    Err(error) => error.to_compile_error().into(),
+  }
+}
+
+struct VertexField {
+  ident: Ident,
+  ty: syn::Type,
+  key: Path,
+  normalized: bool,
+  integer: bool,
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+  check_repr_c(input)?;
+
+  let fields = match &input.data {
+    Data::Struct(data) => match &data.fields {
+      Fields::Named(fields) => &fields.named,
+      _ => return Err(Error::new(input.ident.span(), "Vertex can only be derived for structs with named fields")),
+    },
+    _ => return Err(Error::new(input.ident.span(), "Vertex can only be derived for structs")),
+  };
+
+  let mut vertex_fields = vec![];
+  for field in fields.iter() {
+    let mut attrs = field.attrs.iter().filter(|a| a.path().is_ident("vertex"));
+    if let Some(attr) = attrs.next() {
+      let ident = field.ident.clone().expect("named field");
+      vertex_fields.push(parse_field(ident, field.ty.clone(), attr)?);
+    }
+    if let Some(attr) = attrs.next() {
+      return Err(Error::new(attr.span(), "a field can only be tagged with #[vertex] once"));
+    }
+  }
+
+  let key_type = match vertex_fields.first() {
+    Some(field) => key_type(&field.key)?,
+    None => return Err(Error::new(input.ident.span(), "Vertex needs at least one field tagged with #[vertex(...)]")),
+  };
+
+  let name = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+  let root = quote! { ::webpack_demo::render::layout };
+
+  let attributes = vertex_fields.iter().map(|field| {
+    let VertexField { ident, ty, key, normalized, integer } = field;
+    let integer = if *integer { quote! { .with_integer() } } else { quote! {} };
+    quote! {
+      .with_attribute(#root::VertexAttribute::of_field::<#ty>(
+        #key,
+        #normalized,
+        ::core::mem::size_of::<Self>() as i32,
+        ::core::mem::offset_of!(Self, #ident) as i32,
+      )#integer)
+    }
+  });
+
+  let writes = vertex_fields.iter().map(|field| {
+    let ident = &field.ident;
+    quote! {
+      #root::write_field(bytes, ::core::mem::offset_of!(Self, #ident), &self.#ident);
+    }
+  });
+
+  Ok(quote! {
+    impl #impl_generics #root::Vertex for #name #ty_generics #where_clause {
+      type Key = #key_type;
+
+      fn layout() -> #root::VertexLayout<Self::Key> {
+        #root::VertexLayout::create()
+          #(#attributes)*
+      }
+
+      fn write_le_bytes(&self, bytes: &mut [u8]) {
+        #(#writes)*
+      }
+    }
+  })
+}
+
+/**
+ * Without `#[repr(C)]` the compiler is free to reorder the
+ * fields, which wouldn't change the layout it generates but
+ * is very likely not what was intended. The repr is parsed
+ * as metas so modifiers like `align(16)` can come along.
+ */
+fn check_repr_c(input: &DeriveInput) -> Result<(), Error> {
+  for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+    let reprs = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+    if reprs.iter().any(|r| r.path().is_ident("C")) {
+      return Ok(());
+    }
+  }
+  Err(Error::new(input.ident.span(), "Vertex can only be derived for #[repr(C)] structs"))
+}
+
+fn parse_field(ident: Ident, ty: syn::Type, attr: &syn::Attribute) -> Result<VertexField, Error> {
+  let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+  let mut metas = metas.into_iter();
+
+  let key = match metas.next() {
+    Some(Meta::Path(path)) => path,
+    _ => return Err(Error::new(attr.span(), "expected #[vertex(Key::Variant)]")),
+  };
+
+  let (mut normalized, mut integer) = (false, false);
+  for meta in metas {
+    match meta {
+      Meta::Path(path) if path.is_ident("normalized") => normalized = true,
+      Meta::Path(path) if path.is_ident("integer") => integer = true,
+      other => return Err(Error::new(other.span(), "expected `normalized` or `integer`")),
+    }
+  }
+  if normalized && integer {
+    return Err(Error::new(attr.span(), "integer attributes can't be normalized"));
+  }
+
+  Ok(VertexField { ident, ty, key, normalized, integer })
+}
+
+fn key_type(key: &Path) -> Result<Path, Error> {
+  let mut key_type = key.clone();
+  if key_type.segments.len() < 2 {
+    return Err(Error::new(key.span(), "expected the key as an enum variant like Key::Variant"));
+  }
+  key_type.segments.pop();
+  key_type.segments.pop_punct();
+  Ok(key_type)
+}
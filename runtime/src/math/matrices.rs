@@ -93,6 +93,16 @@ impl<T> Matrix<T> where T: Copy {
     Vector { length: self.height, data }
   }
 
+  /**
+   * The data ordered column by column, which is how GL
+   * expects the matrices passed to it.
+   */
+  pub fn to_column_major(&self) -> Vec<T> {
+    (0..self.width).flat_map(|x| (0..self.height).map(move |y| (x, y)))
+      .map(|(x, y)| self.data[x + (y * self.width)])
+      .collect()
+  }
+
   pub fn row(&self, y: usize) -> Vector<T> {
     assert!(self.height > y, "y is greater than vector height");
     let data: Vec<T> = (0..self.width)
@@ -129,6 +139,12 @@ mod tests {
     assert_eq!(m[(2, 2)], u32::default());
   }
 
+  #[test]
+  fn matrix_to_column_major() {
+    let m = Matrix::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(m.to_column_major(), vec![1, 4, 2, 5, 3, 6]);
+  }
+
   #[test]
   fn matrix_mul() {
     let d1 = vec![1, 2, 3, 4];
@@ -1,34 +1,55 @@
-use std::convert::TryFrom;
-use web_sys::{
-  WebGlBuffer,
-  WebGlProgram,
-  WebGl2RenderingContext,
-  WebGlUniformLocation,
-};
 use super::constants::{
   BufferKind,
   BlendFuncFactor,
-  ClearMask,
+  ClearMasks,
   DrawArrayKind,
   DrawKind,
+  FramebufferAttachment,
+  FramebufferStatus,
+  IndexKind,
+  RenderbufferFormat,
+  ShaderType,
+  TextureParameter,
+  TextureUnit,
   ViewPrecision,
   HasBufferKind,
-  HasBlendFuncFactor,
-  HasViewPrecision,
-  HasClearMaskKind,
-  HasDrawArrayKind,
-  HasDrawKind,
 };
-use super::data::{View};
+use super::data::{DataViewError, Uint8View, View};
+use super::layout::{VertexAttribute, VertexLayout};
+use super::pipeline::{PipelineState};
+use super::super::math::matrices::{Matrix};
+#[cfg(feature = "trace")]
+use serde::{Serialize, Deserialize};
+use std::fmt::Debug;
 
-type AttributeIndex = u32;
+pub type AttributeIndex = u32;
 
 pub trait AttributeKey {
   fn name(&self) -> &str;
+
+  /**
+   * The type the attribute is declared as in the shader, which
+   * is checked against the linked program when it's known.
+   */
+  fn shader_type(&self) -> Option<ShaderType> {
+    None
+  }
 }
 
 pub trait UniformKey {
   fn name(&self) -> &str;
+
+  /**
+   * The type the uniform is declared as in the shader, which
+   * is checked against the linked program when it's known.
+   */
+  fn shader_type(&self) -> Option<ShaderType> {
+    None
+  }
+}
+
+pub trait UniformBlockKey {
+  fn name(&self) -> &str;
 }
 
 pub trait IntoAttributeIndex {
@@ -39,6 +60,10 @@ pub trait IntoUniformIndex {
   fn with_context<C>(self, context: &C) -> Result<C::UniformIndex, RenderApiError> where C: RenderAPI;
 }
 
+pub trait IntoUniformBlockIndex {
+  fn with_context<C>(self, context: &C) -> Result<C::UniformBlockIndex, RenderApiError> where C: RenderAPI;
+}
+
 /**
  * A wrapper around `WebGlRenderingContext` to reduce the
  * number of repetitive constants, but also adds a bit more
@@ -51,20 +76,61 @@ pub trait IntoUniformIndex {
  * https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.WebGlRenderingContext.html
  */
 pub trait RenderAPI {
-  type Buffer: HasBufferKind;
+  type Buffer: HasBufferKind + Debug;
+  /**
+   * A linked program, which handles compare equal when
+   * they're the same program.
+   */
+  type Program: Clone + Debug + PartialEq;
   type UniformIndex;
+  type UniformBlockIndex;
+  type Texture;
+  type Framebuffer;
+  type Renderbuffer;
+  type VertexArray: Debug;
 
   /**
-   * Wrapper around `WebGlRenderingContext::bind_buffer`.
+   * Binds the buffer to the target of its kind without
+   * changing its contents, such as to point attributes at a
+   * vertex buffer that's already been uploaded.
    *
    * https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.WebGlRenderingContext.html#method.bind_buffer
    */
-  fn bind_buffer<V>(
-      &self,
-      buffer: &Self::Buffer,
-      view: &V,
-      draw_kind: DrawKind,
-  ) where V: View;
+  fn bind_buffer(&self, buffer: &Self::Buffer);
+
+  /**
+   * Binds the buffer to an indexed binding point of its kind,
+   * which for a uniform buffer is what a uniform block reads
+   * from once it's assigned that binding point.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/bindBufferBase
+   */
+  fn bind_buffer_base(&self, buffer: &Self::Buffer, index: u32);
+
+  /**
+   * Binds the framebuffer that draws, clears & reads go to,
+   * where `None` is the default framebuffer of the canvas.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/bindFramebuffer
+   */
+  fn bind_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>);
+
+  /**
+   * Makes `unit` the active texture unit & binds the texture
+   * to it, for a sampler uniform set to the unit to read.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/bindTexture
+   */
+  fn bind_texture(&self, unit: TextureUnit, texture: &Self::Texture);
+
+  /**
+   * Binds the vertex array that attribute pointers, enabled
+   * attributes & the element buffer are stored in, where `None`
+   * is the default vertex array.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/bindVertexArray
+   */
+  fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>);
 
   /**
    * Wrapper around `WebGlRenderingContext::blend_color`.
@@ -80,6 +146,53 @@ pub trait RenderAPI {
    */
   fn blend_func(&self, src: BlendFuncFactor, dst: BlendFuncFactor);
 
+  /**
+   * Binds the buffer & replaces its contents with the view,
+   * which reallocates the buffer to the size of the view.
+   *
+   * As with `bind_buffer`, binding an element buffer changes
+   * the element buffer of the bound vertex array, so unbind
+   * it first unless that's what's intended.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/bufferData
+   */
+  fn buffer_data<V>(
+      &self,
+      buffer: &Self::Buffer,
+      view: &V,
+      draw_kind: DrawKind,
+  ) -> Result<(), RenderApiError> where V: View;
+
+  /**
+   * Binds the buffer & reallocates it to `size` bytes of
+   * zeros, to be filled in later with `buffer_sub_data`. It
+   * has the same effect on the bound vertex array as
+   * `buffer_data`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/bufferData
+   */
+  fn buffer_data_with_size(&self, buffer: &Self::Buffer, size: usize, draw_kind: DrawKind);
+
+  /**
+   * Binds the buffer & writes the view into it from `offset`
+   * in bytes, without reallocating it. Writing past the end
+   * of the buffer is a GL error that leaves it unchanged. It
+   * has the same effect on the bound vertex array as
+   * `buffer_data`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/bufferSubData
+   */
+  fn buffer_sub_data<V>(&self, buffer: &Self::Buffer, offset: usize, view: &V) -> Result<(), RenderApiError>
+      where V: View;
+
+  /**
+   * Binds the framebuffer & checks it can be drawn to, with
+   * the reason it can't as a `FramebufferStatus`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/checkFramebufferStatus
+   */
+  fn check_framebuffer_status(&self, framebuffer: &Self::Framebuffer) -> Result<(), RenderApiError>;
+
   /**
    * Wrapper around `WebGlRenderingContext::clear_color`.
    *
@@ -88,11 +201,12 @@ pub trait RenderAPI {
   fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
 
   /**
-   * Wrapper around `WebGlRenderingContext::clear`.
+   * Wrapper around `WebGlRenderingContext::clear`, which
+   * takes a single `ClearMask` or several combined with `|`.
    *
    * https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.WebGlRenderingContext.html#method.clear
    */
-  fn clear(&self, mask: ClearMask);
+  fn clear<M>(&self, mask: M) where M: Into<ClearMasks>;
 
   /**
    * Wrapper around `WebGlRenderingContext::create_buffer`.
@@ -104,6 +218,35 @@ pub trait RenderAPI {
       kind: BufferKind,
   ) -> Result<Self::Buffer, RenderApiError>;
 
+  /**
+   * Wrapper around `WebGlRenderingContext::create_framebuffer`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/createFramebuffer
+   */
+  fn create_framebuffer(&self) -> Result<Self::Framebuffer, RenderApiError>;
+
+  /**
+   * Wrapper around `WebGlRenderingContext::create_renderbuffer`,
+   * which has no storage until `renderbuffer_storage`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/createRenderbuffer
+   */
+  fn create_renderbuffer(&self) -> Result<Self::Renderbuffer, RenderApiError>;
+
+  /**
+   * Wrapper around `WebGlRenderingContext::create_texture`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/createTexture
+   */
+  fn create_texture(&self) -> Result<Self::Texture, RenderApiError>;
+
+  /**
+   * Wrapper around `WebGl2RenderingContext::create_vertex_array`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/createVertexArray
+   */
+  fn create_vertex_array(&self) -> Result<Self::VertexArray, RenderApiError>;
+
   /**
    * Wrapper around `WebGlRenderingContext::draw_arrays`.
    *
@@ -111,6 +254,36 @@ pub trait RenderAPI {
    */
   fn draw_arrays(&self, mode: DrawArrayKind, first: i32, count: i32);
 
+  /**
+   * Wrapper around `WebGl2RenderingContext::draw_arrays_instanced`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/drawArraysInstanced
+   */
+  fn draw_arrays_instanced(&self, mode: DrawArrayKind, first: i32, count: i32, instance_count: i32);
+
+  /**
+   * Wrapper around `WebGl2RenderingContext::draw_elements_with_i32`,
+   * drawing with the indices of the bound element buffer, where
+   * `offset` is in bytes.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/drawElements
+   */
+  fn draw_elements(&self, mode: DrawArrayKind, count: i32, kind: IndexKind, offset: i32);
+
+  /**
+   * Wrapper around `WebGl2RenderingContext::draw_elements_instanced_with_i32`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/drawElementsInstanced
+   */
+  fn draw_elements_instanced(
+      &self,
+      mode: DrawArrayKind,
+      count: i32,
+      kind: IndexKind,
+      offset: i32,
+      instance_count: i32,
+  );
+
   /**
    * Wrapper around `WebGlRenderingContext::enable_vertex_attrib_array`.
    *
@@ -119,6 +292,41 @@ pub trait RenderAPI {
   fn enable_vertex_attrib_array<A>(&self, key: A) -> Result<(), RenderApiError>
       where A: IntoAttributeIndex;
 
+  /**
+   * Attaches the renderbuffer to the framebuffer, binding the
+   * framebuffer.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/framebufferRenderbuffer
+   */
+  fn framebuffer_renderbuffer(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      renderbuffer: &Self::Renderbuffer,
+  );
+
+  /**
+   * Attaches the base level of the texture to the framebuffer,
+   * binding the framebuffer.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/framebufferTexture2D
+   */
+  fn framebuffer_texture_2d(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      texture: &Self::Texture,
+  );
+
+  /**
+   * Generates the mipmaps of the texture from its current
+   * image. The textures bound to each unit are left as they
+   * were, as with the other texture edits.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/generateMipmap
+   */
+  fn generate_mipmap(&self, texture: &Self::Texture);
+
   /**
    * Type safe way of retrieving attribute indexs from the shader.
    */
@@ -132,114 +340,285 @@ pub trait RenderAPI {
       where UK: UniformKey;
 
   /**
-   * Updates the viewport for the shader.
+   * Type safe way of retrieving uniform block indexes from
+   * the shader.
    */
-  fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32);
+  fn get_uniform_block<BK>(&self, key: BK) -> Result<Self::UniformBlockIndex, RenderApiError>
+      where BK: UniformBlockKey;
 
   /**
-   * Type safe wrapper for `uniformf2`
+   * Reads back RGBA8 pixels of the bound framebuffer, where
+   * `(x, y)` is the bottom left & the bottom row comes first.
    *
-   * https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.WebGlRenderingContext.html#method.uniform2f
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/readPixels
    */
-  fn uniform2f<U>(&self, key: U, x: f32, y: f32) -> Result<(), RenderApiError> where U: IntoUniformIndex;
+  fn read_pixels(&self, x: i32, y: i32, width: i32, height: i32) -> Result<Vec<u8>, RenderApiError>;
 
   /**
-   * Wrapper around `WebGlRenderingContext::vertex_attrib_pointer_with_i32`.
+   * Allocates the storage of the renderbuffer, binding it.
    *
-   * https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.WebGlRenderingContext.html#method.vertex_attrib_pointer_with_i32
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/renderbufferStorage
    */
-  fn vertex_attrib_pointer_with_i32<A>(
+  fn renderbuffer_storage(
       &self,
-      key: A,
-      size: i32,
-      precision: ViewPrecision,
-      normalized: bool,
-      stride: i32,
-      offset: i32
-  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex;
-}
+      renderbuffer: &Self::Renderbuffer,
+      format: RenderbufferFormat,
+      width: i32,
+      height: i32,
+  );
 
-#[derive(Debug)]
-pub struct WebRenderAPI {
-  gl: WebGl2RenderingContext,
-  program: WebGlProgram,
-}
+  /**
+   * Enables or disables the depth, stencil, scissor & cull
+   * tests along with blending, then sets the state of each
+   * test that's enabled & the colour mask.
+   *
+   * The depth & stencil write masks are reset when their test
+   * is disabled, so they don't stop those buffers clearing.
+   */
+  fn set_pipeline_state(&self, state: &PipelineState);
 
-impl WebRenderAPI {
-  pub fn create(gl: WebGl2RenderingContext, program: WebGlProgram) -> Self {
-    WebRenderAPI { gl, program }
+  /**
+   * Points every attribute of the layout at the bound array
+   * buffer & enables it, then sets the divisor, where more
+   * than 0 makes the attributes advance per instance. It's
+   * always set, so a location that used to be instanced in
+   * the bound vertex array is reset to per vertex.
+   */
+  fn set_vertex_layout<A>(&self, layout: &VertexLayout<A>, divisor: u32) -> Result<(), RenderApiError>
+      where A: IntoAttributeIndex + Copy {
+    for attribute in layout.attributes.iter() {
+      let VertexAttribute { key, size, precision, normalized, integer, stride, offset } = *attribute;
+      if integer {
+        self.vertex_attrib_i_pointer_with_i32(key, size, precision, stride, offset)?;
+      } else {
+        self.vertex_attrib_pointer_with_i32(key, size, precision, normalized, stride, offset)?;
+      }
+      self.enable_vertex_attrib_array(key)?;
+      self.vertex_attrib_divisor(key, divisor)?;
+    }
+    Ok(())
   }
-}
 
-/**
- * The Web Gl binding for the interface.
- */
-impl RenderAPI for WebRenderAPI {
-  type Buffer = WebRenderBuffer;
-  type UniformIndex = WebGlUniformLocation;
+  /**
+   * Sets the value of a uniform, which every `uniform*`
+   * method goes through.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/uniform
+   */
+  fn set_uniform<U>(&self, key: U, value: UniformValue) -> Result<(), RenderApiError> where U: IntoUniformIndex;
 
-  fn bind_buffer<V>(
+  /**
+   * Updates the viewport for the shader.
+   */
+  fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32);
+
+  /**
+   * Uploads `pixels` as the RGBA image of the texture, with a
+   * byte per channel & the first row at the bottom.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texImage2D
+   */
+  fn tex_image_2d(
       &self,
-      buffer: &Self::Buffer,
-      view: &V,
-      draw_kind: DrawKind,
-  ) where V: View {
-    let kind = buffer.buffer_kind_constant();
-    let draw = draw_kind.draw_kind_constant();
-    self.gl.bind_buffer(kind, Some(&buffer.internal));
-    self.gl.buffer_data_with_array_buffer_view(kind, view.object(), draw)
+      texture: &Self::Texture,
+      width: i32,
+      height: i32,
+      pixels: &Uint8View,
+  ) -> Result<(), RenderApiError>;
+
+  /**
+   * Sets a sampler parameter of the texture.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texParameter
+   */
+  fn tex_parameter(&self, texture: &Self::Texture, parameter: TextureParameter);
+
+  /**
+   * Type safe wrapper for `uniform1f`.
+   */
+  fn uniform1f<U>(&self, key: U, x: f32) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::Float(x))
   }
 
-  fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
-    self.gl.blend_color(red, green, blue, alpha);
+  /**
+   * Type safe wrapper for `uniform1fv` for an array uniform.
+   */
+  fn uniform1fv<U>(&self, key: U, values: &[f32]) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::FloatArray(values.to_vec()))
   }
 
-  fn blend_func(&self, src: BlendFuncFactor, dst: BlendFuncFactor) {
-    self.gl.blend_func(src.blend_func_factor_constant(), dst.blend_func_factor_constant());
+  /**
+   * Type safe wrapper for `uniform1i`, which is also how a
+   * sampler is pointed at a texture unit.
+   */
+  fn uniform1i<U>(&self, key: U, x: i32) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::Int(x))
   }
 
-  fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
-    self.gl.clear_color(red, green, blue, alpha);
+  /**
+   * Type safe wrapper for `uniform1iv` for an array uniform.
+   */
+  fn uniform1iv<U>(&self, key: U, values: &[i32]) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::IntArray(values.to_vec()))
   }
 
-  fn clear(&self, mask: ClearMask) {
-    self.gl.clear(mask.clear_mask_constant());
+  /**
+   * Type safe wrapper for `uniform2f`.
+   */
+  fn uniform2f<U>(&self, key: U, x: f32, y: f32) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::Vec2([x, y]))
   }
 
-  fn create_buffer(&self, kind: BufferKind) -> Result<Self::Buffer, RenderApiError> {
-    self.gl.create_buffer().ok_or(RenderApiError::FailedToCreateBuffer).map(|internal| {
-      WebRenderBuffer { kind, internal }
-    })
+  /**
+   * Type safe wrapper for `uniform2fv` for an array uniform.
+   */
+  fn uniform2fv<U>(&self, key: U, values: &[[f32; 2]]) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::Vec2Array(values.to_vec()))
   }
 
-  fn draw_arrays(&self, mode: DrawArrayKind, first: i32, count: i32) {
-    self.gl.draw_arrays(mode.draw_array_kind_constant(), first, count);
+  /**
+   * Type safe wrapper for `uniform2i`.
+   */
+  fn uniform2i<U>(&self, key: U, x: i32, y: i32) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::IVec2([x, y]))
   }
 
-  fn enable_vertex_attrib_array<A>(&self, key: A) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
-    key.with_context(self).map(|i| self.gl.enable_vertex_attrib_array(i))
+  /**
+   * Type safe wrapper for `uniform2iv` for an array uniform.
+   */
+  fn uniform2iv<U>(&self, key: U, values: &[[i32; 2]]) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::IVec2Array(values.to_vec()))
   }
 
-  fn get_attribute<AK>(&self, key: AK) -> Result<AttributeIndex, RenderApiError> where AK: AttributeKey {
-    let name = key.name();
-    let glint = self.gl.get_attrib_location(&self.program, name);
-    u32::try_from(glint).map_err(|_| RenderApiError::InvalidAttributeName(name.to_string()))
+  /**
+   * Type safe wrapper for `uniform3f`.
+   */
+  fn uniform3f<U>(&self, key: U, x: f32, y: f32, z: f32) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::Vec3([x, y, z]))
   }
 
-  fn get_uniform<UK>(&self, key: UK) -> Result<Self::UniformIndex, RenderApiError> where UK: UniformKey {
-    let name = key.name();
-    let location = self.gl.get_uniform_location(&self.program, name);
-    location.ok_or(RenderApiError::InvalidUniformName(name.to_string()))
+  /**
+   * Type safe wrapper for `uniform3fv` for an array uniform.
+   */
+  fn uniform3fv<U>(&self, key: U, values: &[[f32; 3]]) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::Vec3Array(values.to_vec()))
   }
 
-  fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32) {
-    self.gl.viewport(x, y, width, height);
+  /**
+   * Type safe wrapper for `uniform3i`.
+   */
+  fn uniform3i<U>(&self, key: U, x: i32, y: i32, z: i32) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::IVec3([x, y, z]))
   }
 
-  fn uniform2f<U>(&self, key: U, x: f32, y: f32) -> Result<(), RenderApiError> where U: IntoUniformIndex {
-    key.with_context(self).map(|index| self.gl.uniform2f(Some(&index), x, y))
+  /**
+   * Type safe wrapper for `uniform3iv` for an array uniform.
+   */
+  fn uniform3iv<U>(&self, key: U, values: &[[i32; 3]]) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::IVec3Array(values.to_vec()))
+  }
+
+  /**
+   * Type safe wrapper for `uniform4f`.
+   */
+  fn uniform4f<U>(&self, key: U, x: f32, y: f32, z: f32, w: f32) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::Vec4([x, y, z, w]))
   }
 
+  /**
+   * Type safe wrapper for `uniform4fv` for an array uniform.
+   */
+  fn uniform4fv<U>(&self, key: U, values: &[[f32; 4]]) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::Vec4Array(values.to_vec()))
+  }
+
+  /**
+   * Type safe wrapper for `uniform4i`.
+   */
+  fn uniform4i<U>(&self, key: U, x: i32, y: i32, z: i32, w: i32) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::IVec4([x, y, z, w]))
+  }
+
+  /**
+   * Type safe wrapper for `uniform4iv` for an array uniform.
+   */
+  fn uniform4iv<U>(&self, key: U, values: &[[i32; 4]]) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::IVec4Array(values.to_vec()))
+  }
+
+  /**
+   * Assigns the uniform block the binding point that uniform
+   * buffers are bound to with `bind_buffer_base`.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/uniformBlockBinding
+   */
+  fn uniform_block_binding<B>(&self, key: B, binding: u32) -> Result<(), RenderApiError> where B: IntoUniformBlockIndex;
+
+  /**
+   * Type safe wrapper for `uniformMatrix2fv`, which fails if
+   * the matrix isn't 2x2.
+   */
+  fn uniform_matrix2fv<U>(&self, key: U, matrix: &Matrix<f32>) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::mat2(matrix)?)
+  }
+
+  /**
+   * Type safe wrapper for `uniformMatrix3fv`, which fails if
+   * the matrix isn't 3x3.
+   */
+  fn uniform_matrix3fv<U>(&self, key: U, matrix: &Matrix<f32>) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::mat3(matrix)?)
+  }
+
+  /**
+   * Type safe wrapper for `uniformMatrix4fv`, which fails if
+   * the matrix isn't 4x4.
+   */
+  fn uniform_matrix4fv<U>(&self, key: U, matrix: &Matrix<f32>) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    self.set_uniform(key, UniformValue::mat4(matrix)?)
+  }
+
+  /**
+   * Makes `program` the one that's drawn with, and the one
+   * attributes, uniforms & uniform blocks are looked up in.
+   * Uniform values belong to the program they were set on,
+   * so they're still set when switching back to it.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/useProgram
+   */
+  fn use_program(&self, program: &Self::Program);
+
+  /**
+   * Sets how many instances share each value of a vertex
+   * attribute, where 0 means it advances per vertex instead.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/vertexAttribDivisor
+   */
+  fn vertex_attrib_divisor<A>(&self, key: A, divisor: u32) -> Result<(), RenderApiError>
+      where A: IntoAttributeIndex;
+
+  /**
+   * Like `vertex_attrib_pointer_with_i32` for attributes the
+   * shader declares as `int`, `uint` or one of their vectors,
+   * which are read as integers instead of being converted to
+   * floats, so the precision must be an integer one.
+   *
+   * https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/vertexAttribIPointer
+   */
+  fn vertex_attrib_i_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex;
+
+  /**
+   * Wrapper around `WebGlRenderingContext::vertex_attrib_pointer_with_i32`.
+   *
+   * https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.WebGlRenderingContext.html#method.vertex_attrib_pointer_with_i32
+   */
   fn vertex_attrib_pointer_with_i32<A>(
       &self,
       key: A,
@@ -248,48 +627,153 @@ impl RenderAPI for WebRenderAPI {
       normalized: bool,
       stride: i32,
       offset: i32
-  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
-    key.with_context(self).map(|index| {
-      self.gl.vertex_attrib_pointer_with_i32(
-          index,
-          size,
-          precision.view_precision_constant(),
-          normalized,
-          stride,
-          offset,
-      )
-    })
-  }
-}
-
-#[derive(Clone, Debug)]
-pub struct WebRenderBuffer {
-  pub kind: BufferKind,
-  pub internal: WebGlBuffer,
-}
-
-impl HasBufferKind for WebRenderBuffer {
-  fn buffer_kind_constant(&self) -> u32 {
-    self.kind.buffer_kind_constant()
-  }
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex;
 }
 
+#[derive(Debug)]
 pub enum RenderApiError {
+  BufferOutOfRange { capacity: usize, offset: usize, size: usize },
+  DataView(DataViewError),
   FailedToCreateBuffer,
+  FailedToCreateFramebuffer,
+  FailedToCreateRenderbuffer,
+  FailedToCreateTexture,
+  FailedToCreateVertexArray,
+  FailedToReadPixels,
+  FailedToUploadTexture,
+  IncompleteFramebuffer(FramebufferStatus),
   InvalidAttributeName(String),
+  InvalidMatrixDimensions { expected: usize, width: usize, height: usize },
   InvalidUniformName(String),
+  InvalidUniformBlockName(String),
+  InvalidTextureData { expected: usize, actual: usize },
+}
+
+impl From<DataViewError> for RenderApiError {
+  fn from(error: DataViewError) -> Self {
+    RenderApiError::DataView(error)
+  }
 }
 
 impl ToString for RenderApiError {
   fn to_string(&self) -> String {
     match self {
+      RenderApiError::BufferOutOfRange { capacity, offset, size } => {
+        format!("Buffer out of range, writing {} bytes at {} into {} bytes", size, offset, capacity)
+      },
+      RenderApiError::DataView(e) => format!("Invalid view, {}", e.to_string()),
       RenderApiError::FailedToCreateBuffer => "Failed to create buffer".to_string(),
+      RenderApiError::FailedToCreateFramebuffer => "Failed to create framebuffer".to_string(),
+      RenderApiError::FailedToCreateRenderbuffer => "Failed to create renderbuffer".to_string(),
+      RenderApiError::FailedToCreateTexture => "Failed to create texture".to_string(),
+      RenderApiError::FailedToCreateVertexArray => "Failed to create vertex array".to_string(),
+      RenderApiError::FailedToReadPixels => "Failed to read pixels".to_string(),
+      RenderApiError::FailedToUploadTexture => "Failed to upload texture".to_string(),
+      RenderApiError::IncompleteFramebuffer(s) => format!("Incomplete framebuffer, {:?}", s),
       RenderApiError::InvalidAttributeName(s) => format!("Invalid attribute name, {}", s),
+      RenderApiError::InvalidMatrixDimensions { expected, width, height } => {
+        format!("Invalid matrix, expected {}x{} but got {}x{}", expected, expected, width, height)
+      },
       RenderApiError::InvalidUniformName(s) => format!("Invalid uniform name, {}", s),
+      RenderApiError::InvalidUniformBlockName(s) => format!("Invalid uniform block name, {}", s),
+      RenderApiError::InvalidTextureData { expected, actual } => {
+        format!("Invalid texture data, expected {} bytes but got {}", expected, actual)
+      },
     }
   }
 }
 
+/**
+ * A value for each type of uniform, where matrices are stored
+ * in column major order as GL expects them.
+ */
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum UniformValue {
+  Float(f32),
+  Vec2([f32; 2]),
+  Vec3([f32; 3]),
+  Vec4([f32; 4]),
+  Int(i32),
+  IVec2([i32; 2]),
+  IVec3([i32; 3]),
+  IVec4([i32; 4]),
+  FloatArray(Vec<f32>),
+  Vec2Array(Vec<[f32; 2]>),
+  Vec3Array(Vec<[f32; 3]>),
+  Vec4Array(Vec<[f32; 4]>),
+  IntArray(Vec<i32>),
+  IVec2Array(Vec<[i32; 2]>),
+  IVec3Array(Vec<[i32; 3]>),
+  IVec4Array(Vec<[i32; 4]>),
+  Mat2([f32; 4]),
+  Mat3([f32; 9]),
+  Mat4([f32; 16]),
+}
+
+impl UniformValue {
+  pub fn mat2(matrix: &Matrix<f32>) -> Result<Self, RenderApiError> {
+    let mut data = [0.0; 4];
+    data.copy_from_slice(&square_matrix(matrix, 2)?);
+    Ok(UniformValue::Mat2(data))
+  }
+
+  pub fn mat3(matrix: &Matrix<f32>) -> Result<Self, RenderApiError> {
+    let mut data = [0.0; 9];
+    data.copy_from_slice(&square_matrix(matrix, 3)?);
+    Ok(UniformValue::Mat3(data))
+  }
+
+  pub fn mat4(matrix: &Matrix<f32>) -> Result<Self, RenderApiError> {
+    let mut data = [0.0; 16];
+    data.copy_from_slice(&square_matrix(matrix, 4)?);
+    Ok(UniformValue::Mat4(data))
+  }
+
+  /**
+   * The type of uniform the value is for, where arrays are the
+   * type of their elements.
+   */
+  pub fn shader_type(&self) -> ShaderType {
+    match self {
+      UniformValue::Float(_) | UniformValue::FloatArray(_) => ShaderType::Float,
+      UniformValue::Vec2(_) | UniformValue::Vec2Array(_) => ShaderType::FloatVec2,
+      UniformValue::Vec3(_) | UniformValue::Vec3Array(_) => ShaderType::FloatVec3,
+      UniformValue::Vec4(_) | UniformValue::Vec4Array(_) => ShaderType::FloatVec4,
+      UniformValue::Int(_) | UniformValue::IntArray(_) => ShaderType::Int,
+      UniformValue::IVec2(_) | UniformValue::IVec2Array(_) => ShaderType::IntVec2,
+      UniformValue::IVec3(_) | UniformValue::IVec3Array(_) => ShaderType::IntVec3,
+      UniformValue::IVec4(_) | UniformValue::IVec4Array(_) => ShaderType::IntVec4,
+      UniformValue::Mat2(_) => ShaderType::FloatMat2,
+      UniformValue::Mat3(_) => ShaderType::FloatMat3,
+      UniformValue::Mat4(_) => ShaderType::FloatMat4,
+    }
+  }
+
+  /**
+   * If GL accepts the value for a uniform of `kind`, where
+   * samplers are set with ints & bools with ints or floats.
+   */
+  pub fn can_set(&self, kind: ShaderType) -> bool {
+    let own = self.shader_type();
+    match kind {
+      ShaderType::Sampler2D | ShaderType::SamplerCube => own == ShaderType::Int,
+      ShaderType::Bool => own == ShaderType::Int || own == ShaderType::Float,
+      ShaderType::BoolVec2 => own == ShaderType::IntVec2 || own == ShaderType::FloatVec2,
+      ShaderType::BoolVec3 => own == ShaderType::IntVec3 || own == ShaderType::FloatVec3,
+      ShaderType::BoolVec4 => own == ShaderType::IntVec4 || own == ShaderType::FloatVec4,
+      kind => own == kind,
+    }
+  }
+}
+
+fn square_matrix(matrix: &Matrix<f32>, size: usize) -> Result<Vec<f32>, RenderApiError> {
+  match (matrix.width(), matrix.height()) {
+    (width, height) if width == size && height == size => Ok(matrix.to_column_major()),
+    (width, height) => Err(RenderApiError::InvalidMatrixDimensions { expected: size, width, height }),
+  }
+}
+
 impl<A> IntoAttributeIndex for A where A: AttributeKey {
   fn with_context<C>(self, context: &C) -> Result<AttributeIndex, RenderApiError> where C: RenderAPI {
     context.get_attribute(self)
@@ -307,3 +791,43 @@ impl<U> IntoUniformIndex for U where U: UniformKey {
     context.get_uniform(self)
   }
 }
+
+impl<B> IntoUniformBlockIndex for B where B: UniformBlockKey {
+  fn with_context<C>(self, context: &C) -> Result<C::UniformBlockIndex, RenderApiError> where C: RenderAPI {
+    context.get_uniform_block(self)
+  }
+}
+
+/**
+ * The number of bytes an RGBA texture of the given size needs,
+ * which `tex_image_2d` checks the pixels against.
+ */
+pub(crate) fn check_texture_data(width: i32, height: i32, pixels: &Uint8View) -> Result<(), RenderApiError> {
+  let expected = width.max(0) as usize * height.max(0) as usize * 4;
+  match pixels.length() {
+    actual if actual == expected => Ok(()),
+    actual => Err(RenderApiError::InvalidTextureData { expected, actual }),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matrix_uniforms_are_column_major() {
+    let matrix = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(UniformValue::mat2(&matrix).unwrap(), UniformValue::Mat2([1.0, 3.0, 2.0, 4.0]));
+  }
+
+  #[test]
+  fn matrix_uniforms_must_be_square() {
+    let matrix = Matrix::new(3, 2, vec![0.0; 6]);
+    match UniformValue::mat3(&matrix) {
+      Err(RenderApiError::InvalidMatrixDimensions { expected, width, height }) => {
+        assert_eq!((expected, width, height), (3, 3, 2));
+      },
+      other => panic!("expected invalid matrix dimensions, got {:?}", other),
+    }
+  }
+}
@@ -0,0 +1,245 @@
+use super::api::{RenderAPI, RenderApiError};
+use super::constants::{BufferKind, DrawKind};
+use super::data::{View};
+
+/**
+ * How the capacity of a `DynamicBuffer` grows when an upload
+ * doesn't fit, where growing by more than is needed means
+ * uploads that grow a little at a time don't reallocate the
+ * buffer every time.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GrowthPolicy {
+  Exact,
+  #[default]
+  Double,
+  Chunked(usize),
+}
+
+impl GrowthPolicy {
+  /**
+   * The capacity to grow to so `required` bytes fit, which
+   * is never less than `required`.
+   */
+  pub fn grow(&self, capacity: usize, required: usize) -> usize {
+    match *self {
+      GrowthPolicy::Exact => required,
+      GrowthPolicy::Double => required.max(capacity * 2),
+      GrowthPolicy::Chunked(0) => required,
+      GrowthPolicy::Chunked(chunk) => required.div_ceil(chunk) * chunk,
+    }
+  }
+}
+
+/**
+ * A buffer that keeps track of how much has been allocated
+ * for it, so contents that fit are written in place with
+ * `buffer_sub_data` instead of reallocating the buffer, &
+ * parts of the contents can be updated on their own.
+ *
+ * Binding is separate from uploading, so the buffer can be
+ * bound without its contents being sent again. Element buffers
+ * unbind the vertex array before uploading, as the upload
+ * would otherwise replace the element buffer it was bound to.
+ */
+#[derive(Debug)]
+pub struct DynamicBuffer<R: RenderAPI> {
+  buffer: R::Buffer,
+  kind: BufferKind,
+  draw_kind: DrawKind,
+  growth: GrowthPolicy,
+  capacity: usize,
+  length: usize,
+}
+
+impl<R> DynamicBuffer<R> where R: RenderAPI {
+  pub fn create(context: &R, kind: BufferKind, draw_kind: DrawKind) -> Result<Self, RenderApiError> {
+    let buffer = context.create_buffer(kind)?;
+    Ok(DynamicBuffer { buffer, kind, draw_kind, growth: GrowthPolicy::default(), capacity: 0, length: 0 })
+  }
+
+  pub fn with_growth(mut self, growth: GrowthPolicy) -> Self {
+    self.growth = growth;
+    self
+  }
+
+  pub fn bind(&self, context: &R) {
+    context.bind_buffer(&self.buffer);
+  }
+
+  /**
+   * Reallocates the buffer to `capacity` bytes of zeros, so
+   * it can be filled in with `write` without growing.
+   */
+  pub fn allocate(&mut self, context: &R, capacity: usize) {
+    self.unbind_vertex_array(context);
+    context.buffer_data_with_size(&self.buffer, capacity, self.draw_kind);
+    self.capacity = capacity;
+    self.length = 0;
+  }
+
+  /**
+   * Replaces the contents with the view, which is written in
+   * place if it fits, otherwise the buffer grows first.
+   */
+  pub fn upload<V>(&mut self, context: &R, view: &V) -> Result<(), RenderApiError> where V: View {
+    let size = byte_length(view);
+    self.unbind_vertex_array(context);
+    if size > self.capacity {
+      let capacity = self.growth.grow(self.capacity, size);
+      if capacity == size {
+        context.buffer_data(&self.buffer, view, self.draw_kind)?;
+        self.capacity = capacity;
+      } else {
+        self.allocate(context, capacity);
+        context.buffer_sub_data(&self.buffer, 0, view)?;
+      }
+    } else {
+      context.buffer_sub_data(&self.buffer, 0, view)?;
+    }
+    self.length = size;
+    Ok(())
+  }
+
+  /**
+   * Writes the view over part of the contents from `offset`
+   * in bytes. It can't grow the buffer, as that would lose
+   * the rest of the contents, so the write must fit.
+   */
+  pub fn write<V>(&mut self, context: &R, offset: usize, view: &V) -> Result<(), RenderApiError> where V: View {
+    let size = byte_length(view);
+    if offset + size > self.capacity {
+      return Err(RenderApiError::BufferOutOfRange { capacity: self.capacity, offset, size });
+    }
+    self.unbind_vertex_array(context);
+    context.buffer_sub_data(&self.buffer, offset, view)?;
+    self.length = self.length.max(offset + size);
+    Ok(())
+  }
+
+  fn unbind_vertex_array(&self, context: &R) {
+    if self.kind == BufferKind::ElementBuffer {
+      context.bind_vertex_array(None);
+    }
+  }
+
+  pub fn buffer(&self) -> &R::Buffer {
+    &self.buffer
+  }
+
+  /**
+   * The number of bytes allocated for the buffer.
+   */
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  /**
+   * The number of bytes of the buffer that have been written.
+   */
+  pub fn length(&self) -> usize {
+    self.length
+  }
+}
+
+fn byte_length<V>(view: &V) -> usize where V: View {
+  view.length() * view.get_precision().size()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::constants::{ViewPrecision};
+  use super::super::data::{Float32View, Uint16View};
+  use super::super::recording::{Command, RecordingBuffer, RecordingRenderAPI};
+
+  fn floats(values: &[f32]) -> Float32View {
+    Float32View::create(values).unwrap()
+  }
+
+  #[test]
+  fn growth_policies() {
+    assert_eq!(GrowthPolicy::Exact.grow(16, 20), 20);
+    assert_eq!(GrowthPolicy::Double.grow(16, 20), 32);
+    assert_eq!(GrowthPolicy::Double.grow(16, 40), 40);
+    assert_eq!(GrowthPolicy::Double.grow(0, 4), 4);
+    assert_eq!(GrowthPolicy::Chunked(64).grow(0, 65), 128);
+    assert_eq!(GrowthPolicy::Chunked(0).grow(0, 65), 65);
+  }
+
+  #[test]
+  fn uploads_in_place_once_allocated() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    let mut buffer = DynamicBuffer::create(&api, BufferKind::ArrayBuffer, DrawKind::DynamicDraw).unwrap();
+    buffer.upload(&api, &floats(&[1.0, 2.0])).unwrap();
+    buffer.upload(&api, &floats(&[3.0])).unwrap();
+    buffer.upload(&api, &floats(&[4.0, 5.0, 6.0])).unwrap();
+    let target = RecordingBuffer { id: 0, kind: BufferKind::ArrayBuffer };
+
+    assert_eq!((buffer.capacity(), buffer.length()), (16, 12));
+    assert_eq!(api.take_commands(), vec![
+      Command::CreateBuffer(target),
+      Command::BufferData {
+        buffer: target,
+        precision: ViewPrecision::Float,
+        data: floats(&[1.0, 2.0]).to_bytes(),
+        draw_kind: DrawKind::DynamicDraw,
+      },
+      Command::BufferSubData {
+        buffer: target,
+        offset: 0,
+        precision: ViewPrecision::Float,
+        data: floats(&[3.0]).to_bytes(),
+      },
+      Command::BufferDataWithSize { buffer: target, size: 16, draw_kind: DrawKind::DynamicDraw },
+      Command::BufferSubData {
+        buffer: target,
+        offset: 0,
+        precision: ViewPrecision::Float,
+        data: floats(&[4.0, 5.0, 6.0]).to_bytes(),
+      },
+    ]);
+  }
+
+  #[test]
+  fn writes_part_of_the_contents() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    let mut buffer = DynamicBuffer::create(&api, BufferKind::ArrayBuffer, DrawKind::DynamicDraw).unwrap();
+    buffer.allocate(&api, 16);
+    buffer.write(&api, 8, &floats(&[1.0])).unwrap();
+    assert_eq!(buffer.length(), 12);
+
+    match buffer.write(&api, 12, &floats(&[1.0, 2.0])) {
+      Err(RenderApiError::BufferOutOfRange { capacity, offset, size }) => {
+        assert_eq!((capacity, offset, size), (16, 12, 8));
+      },
+      other => panic!("expected buffer out of range, got {:?}", other),
+    }
+    assert_eq!(api.take_commands().len(), 3);
+  }
+
+  #[test]
+  fn element_uploads_leave_the_vertex_array_alone() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    let vertex_array = api.create_vertex_array().unwrap();
+    let mut buffer = DynamicBuffer::create(&api, BufferKind::ElementBuffer, DrawKind::StaticDraw).unwrap();
+    api.bind_vertex_array(Some(&vertex_array));
+    api.take_commands();
+
+    buffer.upload(&api, &Uint16View::create(&[0, 1, 2]).unwrap()).unwrap();
+    let commands = api.take_commands();
+    assert_eq!(commands[0], Command::BindVertexArray(None));
+    assert!(matches!(commands[1], Command::BufferData { .. }));
+  }
+
+  #[test]
+  fn binds_without_uploading() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    let buffer = DynamicBuffer::create(&api, BufferKind::ElementBuffer, DrawKind::StaticDraw).unwrap();
+    api.take_commands();
+    buffer.bind(&api);
+    assert_eq!(api.take_commands(), vec![
+      Command::BindBuffer(RecordingBuffer { id: 0, kind: BufferKind::ElementBuffer }),
+    ]);
+  }
+}
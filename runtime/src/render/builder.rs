@@ -1,20 +1,38 @@
-use web_sys::{WebGl2RenderingContext, WebGlShader};
-use super::api::{WebRenderAPI};
-
-
+#[cfg(feature = "web")]
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader};
+use super::api::{RenderApiError};
+#[cfg(feature = "web")]
+use super::constants::{HasShaderStage, ShaderStage};
+use super::diagnostics::{CompileError};
+#[cfg(feature = "web")]
+use super::preprocessor::{ShaderPreprocessor};
+use super::preprocessor::{PreprocessError};
+use super::reflection::{ReflectionMismatch};
+#[cfg(feature = "web")]
+use super::locations::{ProgramKeys};
+#[cfg(feature = "web")]
+use super::web::{WebProgram, WebRenderAPI};
+
+
+#[cfg(feature = "web")]
 #[derive(Debug)]
 pub struct RenderBuilder {
   webgl_context: Option<WebGl2RenderingContext>,
   vert_shader: Option<WebGlShader>,
   frag_shader: Option<WebGlShader>,
+  program_keys: ProgramKeys,
+  preprocessor: ShaderPreprocessor,
 }
 
+#[cfg(feature = "web")]
 impl RenderBuilder {
   pub fn new() -> Self {
     RenderBuilder {
       webgl_context: None,
       vert_shader: None,
       frag_shader: None,
+      program_keys: ProgramKeys::create(),
+      preprocessor: ShaderPreprocessor::create(),
     }
   }
 
@@ -23,38 +41,44 @@ impl RenderBuilder {
   }
 
   pub fn set_frag_shader(&mut self, shader_source: &str) -> Result<(), BuildError> {
-    let shader_type = WebGl2RenderingContext::FRAGMENT_SHADER;
-    self.frag_shader = Some(self.create_shader(shader_source, shader_type)?);
+    self.frag_shader = Some(self.create_shader(shader_source, ShaderStage::Fragment)?);
     Ok(())
   }
 
   pub fn set_vert_shader(&mut self, shader_source: &str) -> Result<(), BuildError> {
-    let shader_type = WebGl2RenderingContext::VERTEX_SHADER;
-    self.vert_shader = Some(self.create_shader(shader_source, shader_type)?);
+    self.vert_shader = Some(self.create_shader(shader_source, ShaderStage::Vertex)?);
     Ok(())
   }
 
+  /**
+   * Runs on the shaders before they're compiled, so this has
+   * to be set before they are.
+   */
+  pub fn set_preprocessor(&mut self, preprocessor: ShaderPreprocessor) {
+    self.preprocessor = preprocessor;
+  }
+
+  /**
+   * The keys the linked program has to have, otherwise
+   * `build_render_api` fails.
+   */
+  pub fn set_program_keys(&mut self, keys: ProgramKeys) {
+    self.program_keys = keys;
+  }
+
   pub fn build_render_api(&self) -> Result<WebRenderAPI, BuildError> {
     let context = self.webgl_context.clone().ok_or(BuildError::ExpectedContext)?;
     let vert_shader = self.vert_shader.clone().ok_or(BuildError::ExpectedVertShaded)?;
     let frag_shader = self.frag_shader.clone().ok_or(BuildError::ExpectedFragShaded)?;
-    let program = context.create_program().ok_or(BuildError::CannotCreateProgram)?;
-
-    context.attach_shader(&program, &vert_shader);
-    context.attach_shader(&program, &frag_shader);
-    context.link_program(&program);
-
-    let did_link = context
-      .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
-      .as_bool()
-      .ok_or(BuildError::FailedToLinkProgram)?;
-
-    return if did_link {
-      context.use_program(Some(&program));
-      Ok(WebRenderAPI::create(context, program))
-    } else {
-      Err(BuildError::FailedToLinkProgram)
-    };
+    let program = link_program(&context, &vert_shader, &frag_shader)?;
+
+    context.use_program(Some(&program));
+    let program = WebProgram::create(&context, program);
+    program.reflection().check_keys(&self.program_keys).map_err(BuildError::MismatchedProgramKeys)?;
+
+    let render_api = WebRenderAPI::create(context, program);
+    self.program_keys.resolve(&render_api).map_err(BuildError::MissingProgramKey)?;
+    Ok(render_api)
   }
 
   fn get_context(&self) -> Option<&WebGl2RenderingContext> {
@@ -64,23 +88,59 @@ impl RenderBuilder {
     }
   }
 
-  fn create_shader(&self, shader_source: &str, shader_type: u32) -> Result<WebGlShader, BuildError> {
+  fn create_shader(&self, shader_source: &str, stage: ShaderStage) -> Result<WebGlShader, BuildError> {
     let context = self.get_context().ok_or(BuildError::ExpectedContext)?;
+    let shader = self.preprocessor.process(stage.name(), shader_source).map_err(BuildError::FailedToPreprocessShader)?;
+    compile_shader(context, &shader.source, stage).map_err(|error| match error {
+      BuildError::FailedToCompileShader(error) => BuildError::FailedToCompileShader(error.map_lines(&shader)),
+      error => error,
+    })
+  }
+}
 
-    let shader = context.create_shader(shader_type).ok_or(BuildError::CannotCreateShader)?;
-    context.shader_source(&shader, shader_source);
-    context.compile_shader(&shader);
-
-    let did_compile = context
-      .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
-      .as_bool()
-      .ok_or(BuildError::FailedToCompileShader(None))?;
+#[cfg(feature = "web")]
+pub(crate) fn compile_shader(
+    context: &WebGl2RenderingContext,
+    shader_source: &str,
+    stage: ShaderStage,
+) -> Result<WebGlShader, BuildError> {
+  let shader = context.create_shader(stage.shader_stage_constant()).ok_or(BuildError::CannotCreateShader)?;
+  context.shader_source(&shader, shader_source);
+  context.compile_shader(&shader);
+
+  let did_compile = context
+    .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
+    .as_bool()
+    .unwrap_or(false);
+
+  return if did_compile {
+    Ok(shader)
+  } else {
+    let log = context.get_shader_info_log(&shader);
+    Err(BuildError::FailedToCompileShader(CompileError::create(stage, shader_source, log)))
+  }
+}
 
-    return if did_compile {
-      Ok(shader)
-    } else {
-      Err(BuildError::FailedToCompileShader(context.get_shader_info_log(&shader)))
-    }
+#[cfg(feature = "web")]
+pub(crate) fn link_program(
+    context: &WebGl2RenderingContext,
+    vert_shader: &WebGlShader,
+    frag_shader: &WebGlShader,
+) -> Result<WebGlProgram, BuildError> {
+  let program = context.create_program().ok_or(BuildError::CannotCreateProgram)?;
+  context.attach_shader(&program, vert_shader);
+  context.attach_shader(&program, frag_shader);
+  context.link_program(&program);
+
+  let did_link = context
+    .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
+    .as_bool()
+    .unwrap_or(false);
+
+  return if did_link {
+    Ok(program)
+  } else {
+    Err(BuildError::FailedToLinkProgram(context.get_program_info_log(&program)))
   }
 }
 
@@ -88,10 +148,44 @@ pub enum BuildError {
   ExpectedContext,
   ExpectedVertShaded,
   ExpectedFragShaded,
-  FailedToCompileShader(Option<String>),
-  FailedToLinkProgram,
+  FailedToPreprocessShader(PreprocessError),
+  FailedToCompileShader(CompileError),
+  /**
+   * The program's info log, which is where mismatched
+   * varyings between the shaders are reported.
+   */
+  FailedToLinkProgram(Option<String>),
+  MissingProgramKey(RenderApiError),
+  /**
+   * Keys that aren't active in the linked program, or that
+   * are declared with a different type.
+   */
+  MismatchedProgramKeys(Vec<ReflectionMismatch>),
   CannotCreateShader,
   CannotCreateProgram,
+  CannotCreateVertexArray,
+}
+
+impl BuildError {
+  /**
+   * The name of the variant, for telling errors apart
+   * without matching on their message.
+   */
+  pub fn kind(&self) -> &'static str {
+    match self {
+      BuildError::ExpectedContext => "ExpectedContext",
+      BuildError::ExpectedVertShaded => "ExpectedVertShaded",
+      BuildError::ExpectedFragShaded => "ExpectedFragShaded",
+      BuildError::FailedToPreprocessShader(_) => "FailedToPreprocessShader",
+      BuildError::FailedToCompileShader(_) => "FailedToCompileShader",
+      BuildError::FailedToLinkProgram(_) => "FailedToLinkProgram",
+      BuildError::MissingProgramKey(_) => "MissingProgramKey",
+      BuildError::MismatchedProgramKeys(_) => "MismatchedProgramKeys",
+      BuildError::CannotCreateShader => "CannotCreateShader",
+      BuildError::CannotCreateProgram => "CannotCreateProgram",
+      BuildError::CannotCreateVertexArray => "CannotCreateVertexArray",
+    }
+  }
 }
 
 impl ToString for BuildError {
@@ -100,13 +194,20 @@ impl ToString for BuildError {
       BuildError::ExpectedContext => "expected webgl context to be defined".to_string(),
       BuildError::ExpectedFragShaded => "expected frag shader to be defined".to_string(),
       BuildError::ExpectedVertShaded => "expected vert shader to be defined".to_string(),
-      BuildError::FailedToCompileShader(reason) => match reason {
-        None => "failed to compile shader, for an unknown reason".to_string(),
-        Some(reason) => format!("failed to compile shader: {}", reason),
+      BuildError::FailedToPreprocessShader(error) => format!("failed to preprocess shader: {}", error),
+      BuildError::FailedToCompileShader(error) => error.to_string(),
+      BuildError::FailedToLinkProgram(log) => match log.as_ref().map(|log| log.trim()) {
+        None | Some("") => "failed to link program, for an unknown reason".to_string(),
+        Some(log) => format!("failed to link program: {}", log),
       },
-      BuildError::FailedToLinkProgram => "failed to link program".to_string(),
+      BuildError::MissingProgramKey(error) => format!("linked program is missing a key: {}", error.to_string()),
+      BuildError::MismatchedProgramKeys(mismatches) => format!(
+        "linked program doesn't match its keys: {}",
+        mismatches.iter().map(|m| m.to_string()).collect::<Vec<_>>().join(", "),
+      ),
       BuildError::CannotCreateShader => "could not create a shader from the context".to_string(),
       BuildError::CannotCreateProgram => "could not create a program from the context".to_string(),
+      BuildError::CannotCreateVertexArray => "could not create a vertex array from the context".to_string(),
     }
   }
 }
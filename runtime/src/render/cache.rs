@@ -0,0 +1,758 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use super::api::{
+  AttributeIndex,
+  AttributeKey,
+  IntoAttributeIndex,
+  IntoUniformBlockIndex,
+  IntoUniformIndex,
+  RenderAPI,
+  RenderApiError,
+  UniformBlockKey,
+  UniformKey,
+  UniformValue,
+};
+use super::constants::{
+  BufferKind,
+  BlendFuncFactor,
+  ClearMasks,
+  DrawArrayKind,
+  DrawKind,
+  FramebufferAttachment,
+  IndexKind,
+  RenderbufferFormat,
+  TextureParameter,
+  TextureUnit,
+  ViewPrecision,
+  HasBufferKind,
+};
+use super::data::{Uint8View, View};
+use super::gl;
+use super::pipeline::{PipelineState};
+
+/**
+ * Wraps another `RenderAPI`, skipping calls that wouldn't
+ * change the state of the context, such as setting a uniform
+ * to the value it already has or binding what's already bound.
+ *
+ * Attribute, uniform & uniform block lookups are only made
+ * once per name in each program, which is also where the
+ * values of uniforms are kept. The wrapped API is still given
+ * uniforms by name when their value changes.
+ *
+ * The buffer bound to each target is tracked, including the
+ * binding uploads make, except the element buffer is part of
+ * the bound vertex array so it's forgotten when that changes.
+ *
+ * Nothing is assumed about the state of the wrapped API, so
+ * the first call setting any state is always made.
+ */
+#[derive(Debug)]
+pub struct CachedRenderAPI<R> where R: RenderAPI {
+  inner: R,
+  state: RefCell<CacheState>,
+  programs: RefCell<Vec<R::Program>>,
+  stats: Cell<CacheStats>,
+  next_buffer_id: Cell<usize>,
+  next_texture_id: Cell<usize>,
+  next_framebuffer_id: Cell<usize>,
+  next_vertex_array_id: Cell<usize>,
+}
+
+/**
+ * The number of calls made against the wrapped API, & the
+ * number that were skipped as redundant.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+  pub issued: usize,
+  pub skipped: usize,
+}
+
+/**
+ * The state last set on the wrapped API, where `None` is
+ * state that isn't known.
+ */
+#[derive(Debug, Default)]
+struct CacheState {
+  /**
+   * The position of the program in use in `programs`, where
+   * `None` is the program in use before `use_program`.
+   */
+  program: Option<usize>,
+  program_known: bool,
+  programs: HashMap<Option<usize>, ProgramCache>,
+  /**
+   * The buffer bound to each target, by its constant.
+   */
+  buffers: HashMap<u32, usize>,
+  buffer_bases: HashMap<u32, usize>,
+  texture_units: HashMap<u32, usize>,
+  active_texture: Option<u32>,
+  framebuffer: Option<Option<usize>>,
+  vertex_array: Option<Option<usize>>,
+  blend_color: Option<[f32; 4]>,
+  blend_func: Option<(BlendFuncFactor, BlendFuncFactor)>,
+  clear_color: Option<[f32; 4]>,
+  viewport: Option<(i32, i32, i32, i32)>,
+  pipeline: Option<PipelineState>,
+}
+
+#[derive(Debug, Default)]
+struct ProgramCache {
+  attributes: HashMap<String, AttributeIndex>,
+  uniforms: HashMap<String, Option<UniformValue>>,
+  uniform_blocks: HashMap<String, Option<u32>>,
+}
+
+impl CacheState {
+  fn program_mut(&mut self) -> &mut ProgramCache {
+    self.programs.entry(self.program).or_default()
+  }
+
+  /**
+   * Records the buffer as bound to the target of its kind,
+   * returning if it wasn't already.
+   */
+  fn buffer_bound<B>(&mut self, buffer: &CachedBuffer<B>) -> bool where B: HasBufferKind {
+    self.buffers.insert(buffer.buffer_kind_constant(), buffer.id) != Some(buffer.id)
+  }
+}
+
+impl<R> CachedRenderAPI<R> where R: RenderAPI {
+  pub fn create(inner: R) -> Self {
+    CachedRenderAPI {
+      inner,
+      state: RefCell::new(CacheState::default()),
+      programs: RefCell::new(vec![]),
+      stats: Cell::new(CacheStats::default()),
+      next_buffer_id: Cell::new(0),
+      next_texture_id: Cell::new(0),
+      next_framebuffer_id: Cell::new(0),
+      next_vertex_array_id: Cell::new(0),
+    }
+  }
+
+  pub fn inner(&self) -> &R {
+    &self.inner
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    self.stats.get()
+  }
+
+  pub fn reset_stats(&self) {
+    self.stats.set(CacheStats::default());
+  }
+
+  /**
+   * Forgets the state of the wrapped API, for when it's been
+   * changed by something other than this.
+   */
+  pub fn invalidate(&self) {
+    let mut state = self.state.borrow_mut();
+    let mut programs = std::mem::take(&mut state.programs);
+    for program in programs.values_mut() {
+      program.uniforms.values_mut().for_each(|value| *value = None);
+      program.uniform_blocks.values_mut().for_each(|binding| *binding = None);
+    }
+    *state = CacheState { program: state.program, programs, ..CacheState::default() };
+  }
+
+  fn count(&self, issued: bool) {
+    let mut stats = self.stats.get();
+    if issued {
+      stats.issued += 1;
+    } else {
+      stats.skipped += 1;
+    }
+    self.stats.set(stats);
+  }
+
+  /**
+   * Replaces the cached value, returning if it changed & so
+   * if the call needs to be made.
+   */
+  fn update<T>(&self, value: T, field: fn(&mut CacheState) -> &mut Option<T>) -> bool where T: PartialEq {
+    let mut state = self.state.borrow_mut();
+    let cached = field(&mut state);
+    let changed = cached.as_ref() != Some(&value);
+    *cached = Some(value);
+    self.count(changed);
+    changed
+  }
+
+  fn next_id(counter: &Cell<usize>) -> usize {
+    let id = counter.get();
+    counter.set(id + 1);
+    id
+  }
+}
+
+impl<R> RenderAPI for CachedRenderAPI<R> where R: RenderAPI {
+  type Buffer = CachedBuffer<R::Buffer>;
+  type Program = R::Program;
+  type UniformIndex = CachedKey;
+  type UniformBlockIndex = CachedKey;
+  type Texture = CachedTexture<R::Texture>;
+  type Framebuffer = CachedFramebuffer<R::Framebuffer>;
+  type Renderbuffer = R::Renderbuffer;
+  type VertexArray = CachedVertexArray<R::VertexArray>;
+
+  fn bind_buffer(&self, buffer: &Self::Buffer) {
+    let changed = self.state.borrow_mut().buffer_bound(buffer);
+    self.count(changed);
+    if changed {
+      self.inner.bind_buffer(&buffer.inner);
+    }
+  }
+
+  fn bind_buffer_base(&self, buffer: &Self::Buffer, index: u32) {
+    let changed = {
+      let mut state = self.state.borrow_mut();
+      let previous = state.buffer_bases.insert(index, buffer.id);
+      // also binds the buffer to the target of its kind.
+      state.buffer_bound(buffer);
+      previous != Some(buffer.id)
+    };
+    self.count(changed);
+    if changed {
+      self.inner.bind_buffer_base(&buffer.inner, index);
+    }
+  }
+
+  fn bind_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>) {
+    if self.update(framebuffer.map(|f| f.id), |s| &mut s.framebuffer) {
+      self.inner.bind_framebuffer(framebuffer.map(|f| &f.inner));
+    }
+  }
+
+  fn bind_texture(&self, unit: TextureUnit, texture: &Self::Texture) {
+    let changed = {
+      let mut state = self.state.borrow_mut();
+      let changed = state.active_texture != Some(unit.0) || state.texture_units.get(&unit.0) != Some(&texture.id);
+      state.active_texture = Some(unit.0);
+      state.texture_units.insert(unit.0, texture.id);
+      changed
+    };
+    self.count(changed);
+    if changed {
+      self.inner.bind_texture(unit, &texture.inner);
+    }
+  }
+
+  fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>) {
+    if self.update(vertex_array.map(|v| v.id), |s| &mut s.vertex_array) {
+      self.state.borrow_mut().buffers.remove(&gl::ELEMENT_ARRAY_BUFFER);
+      self.inner.bind_vertex_array(vertex_array.map(|v| &v.inner));
+    }
+  }
+
+  fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    if self.update([red, green, blue, alpha], |s| &mut s.blend_color) {
+      self.inner.blend_color(red, green, blue, alpha);
+    }
+  }
+
+  fn blend_func(&self, src: BlendFuncFactor, dst: BlendFuncFactor) {
+    if self.update((src, dst), |s| &mut s.blend_func) {
+      self.inner.blend_func(src, dst);
+    }
+  }
+
+  fn buffer_data<V>(
+      &self,
+      buffer: &Self::Buffer,
+      view: &V,
+      draw_kind: DrawKind,
+  ) -> Result<(), RenderApiError> where V: View {
+    self.count(true);
+    self.state.borrow_mut().buffer_bound(buffer);
+    self.inner.buffer_data(&buffer.inner, view, draw_kind)
+  }
+
+  fn buffer_data_with_size(&self, buffer: &Self::Buffer, size: usize, draw_kind: DrawKind) {
+    self.count(true);
+    self.state.borrow_mut().buffer_bound(buffer);
+    self.inner.buffer_data_with_size(&buffer.inner, size, draw_kind);
+  }
+
+  fn buffer_sub_data<V>(&self, buffer: &Self::Buffer, offset: usize, view: &V) -> Result<(), RenderApiError> where V: View {
+    self.count(true);
+    self.state.borrow_mut().buffer_bound(buffer);
+    self.inner.buffer_sub_data(&buffer.inner, offset, view)
+  }
+
+  fn check_framebuffer_status(&self, framebuffer: &Self::Framebuffer) -> Result<(), RenderApiError> {
+    self.count(true);
+    self.state.borrow_mut().framebuffer = Some(Some(framebuffer.id));
+    self.inner.check_framebuffer_status(&framebuffer.inner)
+  }
+
+  fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    if self.update([red, green, blue, alpha], |s| &mut s.clear_color) {
+      self.inner.clear_color(red, green, blue, alpha);
+    }
+  }
+
+  fn clear<M>(&self, mask: M) where M: Into<ClearMasks> {
+    self.count(true);
+    self.inner.clear(mask);
+  }
+
+  fn create_buffer(&self, kind: BufferKind) -> Result<Self::Buffer, RenderApiError> {
+    self.count(true);
+    let inner = self.inner.create_buffer(kind)?;
+    Ok(CachedBuffer { id: Self::next_id(&self.next_buffer_id), inner })
+  }
+
+  fn create_framebuffer(&self) -> Result<Self::Framebuffer, RenderApiError> {
+    self.count(true);
+    let inner = self.inner.create_framebuffer()?;
+    Ok(CachedFramebuffer { id: Self::next_id(&self.next_framebuffer_id), inner })
+  }
+
+  fn create_renderbuffer(&self) -> Result<Self::Renderbuffer, RenderApiError> {
+    self.count(true);
+    self.inner.create_renderbuffer()
+  }
+
+  fn create_texture(&self) -> Result<Self::Texture, RenderApiError> {
+    self.count(true);
+    let inner = self.inner.create_texture()?;
+    Ok(CachedTexture { id: Self::next_id(&self.next_texture_id), inner })
+  }
+
+  fn create_vertex_array(&self) -> Result<Self::VertexArray, RenderApiError> {
+    self.count(true);
+    let inner = self.inner.create_vertex_array()?;
+    Ok(CachedVertexArray { id: Self::next_id(&self.next_vertex_array_id), inner })
+  }
+
+  fn draw_arrays(&self, mode: DrawArrayKind, first: i32, count: i32) {
+    self.count(true);
+    self.inner.draw_arrays(mode, first, count);
+  }
+
+  fn draw_arrays_instanced(&self, mode: DrawArrayKind, first: i32, count: i32, instance_count: i32) {
+    self.count(true);
+    self.inner.draw_arrays_instanced(mode, first, count, instance_count);
+  }
+
+  fn draw_elements(&self, mode: DrawArrayKind, count: i32, kind: IndexKind, offset: i32) {
+    self.count(true);
+    self.inner.draw_elements(mode, count, kind, offset);
+  }
+
+  fn draw_elements_instanced(
+      &self,
+      mode: DrawArrayKind,
+      count: i32,
+      kind: IndexKind,
+      offset: i32,
+      instance_count: i32,
+  ) {
+    self.count(true);
+    self.inner.draw_elements_instanced(mode, count, kind, offset, instance_count);
+  }
+
+  fn enable_vertex_attrib_array<A>(&self, key: A) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    let index = key.with_context(self)?;
+    self.count(true);
+    self.inner.enable_vertex_attrib_array(index)
+  }
+
+  fn framebuffer_renderbuffer(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      renderbuffer: &Self::Renderbuffer,
+  ) {
+    self.count(true);
+    self.state.borrow_mut().framebuffer = Some(Some(framebuffer.id));
+    self.inner.framebuffer_renderbuffer(&framebuffer.inner, attachment, renderbuffer);
+  }
+
+  fn framebuffer_texture_2d(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      texture: &Self::Texture,
+  ) {
+    self.count(true);
+    self.state.borrow_mut().framebuffer = Some(Some(framebuffer.id));
+    self.inner.framebuffer_texture_2d(&framebuffer.inner, attachment, &texture.inner);
+  }
+
+  fn generate_mipmap(&self, texture: &Self::Texture) {
+    self.count(true);
+    self.inner.generate_mipmap(&texture.inner);
+  }
+
+  fn get_attribute<AK>(&self, key: AK) -> Result<AttributeIndex, RenderApiError> where AK: AttributeKey {
+    let cached = self.state.borrow_mut().program_mut().attributes.get(key.name()).copied();
+    self.count(cached.is_none());
+    match cached {
+      Some(index) => Ok(index),
+      None => {
+        let name = key.name().to_string();
+        let index = self.inner.get_attribute(key)?;
+        self.state.borrow_mut().program_mut().attributes.insert(name, index);
+        Ok(index)
+      },
+    }
+  }
+
+  fn get_uniform<UK>(&self, key: UK) -> Result<Self::UniformIndex, RenderApiError> where UK: UniformKey {
+    let cached = self.state.borrow_mut().program_mut().uniforms.contains_key(key.name());
+    self.count(!cached);
+    let name = key.name().to_string();
+    if !cached {
+      self.inner.get_uniform(key)?;
+      self.state.borrow_mut().program_mut().uniforms.insert(name.clone(), None);
+    }
+    Ok(CachedKey(name))
+  }
+
+  fn get_uniform_block<BK>(&self, key: BK) -> Result<Self::UniformBlockIndex, RenderApiError> where BK: UniformBlockKey {
+    let cached = self.state.borrow_mut().program_mut().uniform_blocks.contains_key(key.name());
+    self.count(!cached);
+    let name = key.name().to_string();
+    if !cached {
+      self.inner.get_uniform_block(key)?;
+      self.state.borrow_mut().program_mut().uniform_blocks.insert(name.clone(), None);
+    }
+    Ok(CachedKey(name))
+  }
+
+  fn read_pixels(&self, x: i32, y: i32, width: i32, height: i32) -> Result<Vec<u8>, RenderApiError> {
+    self.count(true);
+    self.inner.read_pixels(x, y, width, height)
+  }
+
+  fn renderbuffer_storage(
+      &self,
+      renderbuffer: &Self::Renderbuffer,
+      format: RenderbufferFormat,
+      width: i32,
+      height: i32,
+  ) {
+    self.count(true);
+    self.inner.renderbuffer_storage(renderbuffer, format, width, height);
+  }
+
+  fn set_pipeline_state(&self, state: &PipelineState) {
+    if self.update(*state, |s| &mut s.pipeline) {
+      self.inner.set_pipeline_state(state);
+    }
+  }
+
+  fn set_uniform<U>(&self, key: U, value: UniformValue) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    let uniform = key.with_context(self)?;
+    let unchanged = self.state.borrow_mut().program_mut().uniforms.get(&uniform.0) == Some(&Some(value.clone()));
+    self.count(!unchanged);
+    if !unchanged {
+      self.inner.set_uniform(uniform.clone(), value.clone())?;
+      self.state.borrow_mut().program_mut().uniforms.insert(uniform.0, Some(value));
+    }
+    Ok(())
+  }
+
+  fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+    if self.update((x, y, width, height), |s| &mut s.viewport) {
+      self.inner.set_viewport(x, y, width, height);
+    }
+  }
+
+  fn tex_image_2d(
+      &self,
+      texture: &Self::Texture,
+      width: i32,
+      height: i32,
+      pixels: &Uint8View,
+  ) -> Result<(), RenderApiError> {
+    self.count(true);
+    self.inner.tex_image_2d(&texture.inner, width, height, pixels)
+  }
+
+  fn tex_parameter(&self, texture: &Self::Texture, parameter: TextureParameter) {
+    self.count(true);
+    self.inner.tex_parameter(&texture.inner, parameter);
+  }
+
+  fn uniform_block_binding<B>(&self, key: B, binding: u32) -> Result<(), RenderApiError> where B: IntoUniformBlockIndex {
+    let block = key.with_context(self)?;
+    let unchanged = self.state.borrow_mut().program_mut().uniform_blocks.get(&block.0) == Some(&Some(binding));
+    self.count(!unchanged);
+    if !unchanged {
+      self.inner.uniform_block_binding(block.clone(), binding)?;
+      self.state.borrow_mut().program_mut().uniform_blocks.insert(block.0, Some(binding));
+    }
+    Ok(())
+  }
+
+  fn use_program(&self, program: &Self::Program) {
+    let id = {
+      let mut programs = self.programs.borrow_mut();
+      match programs.iter().position(|p| p == program) {
+        Some(id) => id,
+        None => {
+          programs.push(program.clone());
+          programs.len() - 1
+        },
+      }
+    };
+
+    let changed = {
+      let mut state = self.state.borrow_mut();
+      let changed = !state.program_known || state.program != Some(id);
+      state.program = Some(id);
+      state.program_known = true;
+      changed
+    };
+    self.count(changed);
+    if changed {
+      self.inner.use_program(program);
+    }
+  }
+
+  fn vertex_attrib_divisor<A>(&self, key: A, divisor: u32) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    let index = key.with_context(self)?;
+    self.count(true);
+    self.inner.vertex_attrib_divisor(index, divisor)
+  }
+
+  fn vertex_attrib_i_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    let index = key.with_context(self)?;
+    self.count(true);
+    self.inner.vertex_attrib_i_pointer_with_i32(index, size, precision, stride, offset)
+  }
+
+  fn vertex_attrib_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      normalized: bool,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    let index = key.with_context(self)?;
+    self.count(true);
+    self.inner.vertex_attrib_pointer_with_i32(index, size, precision, normalized, stride, offset)
+  }
+}
+
+/**
+ * A uniform or uniform block that's been looked up, which is
+ * passed to the wrapped API by name.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedKey(pub String);
+
+impl UniformKey for CachedKey {
+  fn name(&self) -> &str {
+    &self.0
+  }
+}
+
+impl UniformBlockKey for CachedKey {
+  fn name(&self) -> &str {
+    &self.0
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CachedBuffer<B> {
+  pub id: usize,
+  pub inner: B,
+}
+
+impl<B> HasBufferKind for CachedBuffer<B> where B: HasBufferKind {
+  fn buffer_kind_constant(&self) -> u32 {
+    self.inner.buffer_kind_constant()
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CachedTexture<T> {
+  pub id: usize,
+  pub inner: T,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CachedFramebuffer<F> {
+  pub id: usize,
+  pub inner: F,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CachedVertexArray<V> {
+  pub id: usize,
+  pub inner: V,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::constants::{TextureWrap};
+  use super::super::recording::{
+    Command,
+    RecordingProgram,
+    RecordingRenderAPI,
+    RecordingTexture,
+    RecordingUniformIndex,
+  };
+
+  #[derive(Clone, Copy)]
+  struct Keys;
+
+  impl UniformKey for Keys {
+    fn name(&self) -> &str { "color" }
+  }
+
+  #[test]
+  fn skips_setting_state_to_its_current_value() {
+    let api = CachedRenderAPI::create(RecordingRenderAPI::create(&[], &[]));
+    api.clear_color(0.0, 0.0, 0.0, 1.0);
+    api.clear_color(0.0, 0.0, 0.0, 1.0);
+    api.set_viewport(0, 0, 10, 10);
+    api.set_viewport(0, 0, 10, 10);
+    api.clear_color(1.0, 0.0, 0.0, 1.0);
+
+    assert_eq!(api.inner().take_commands(), vec![
+      Command::ClearColor { red: 0.0, green: 0.0, blue: 0.0, alpha: 1.0 },
+      Command::SetViewport { x: 0, y: 0, width: 10, height: 10 },
+      Command::ClearColor { red: 1.0, green: 0.0, blue: 0.0, alpha: 1.0 },
+    ]);
+    assert_eq!(api.stats(), CacheStats { issued: 3, skipped: 2 });
+  }
+
+  #[test]
+  fn only_sets_uniforms_when_their_value_changes() {
+    let api = CachedRenderAPI::create(RecordingRenderAPI::create(&[], &["color"]));
+    api.uniform2f(Keys, 1.0, 0.0).unwrap();
+    api.uniform2f(Keys, 1.0, 0.0).unwrap();
+    api.uniform2f(Keys, 0.0, 1.0).unwrap();
+
+    let index = RecordingUniformIndex(0);
+    assert_eq!(api.inner().take_commands(), vec![
+      Command::SetUniform { index, value: UniformValue::Vec2([1.0, 0.0]) },
+      Command::SetUniform { index, value: UniformValue::Vec2([0.0, 1.0]) },
+    ]);
+    // the uniform is only looked up by the first call.
+    assert_eq!(api.stats(), CacheStats { issued: 3, skipped: 3 });
+  }
+
+  #[test]
+  fn texture_operations_keep_the_bound_textures() {
+    let api = CachedRenderAPI::create(RecordingRenderAPI::create(&[], &[]));
+    let first = api.create_texture().unwrap();
+    let second = api.create_texture().unwrap();
+    api.bind_texture(TextureUnit(0), &first);
+    api.bind_texture(TextureUnit(1), &second);
+    api.inner().take_commands();
+
+    api.tex_parameter(&first, TextureParameter::WrapS(TextureWrap::Repeat));
+    api.generate_mipmap(&first);
+    api.bind_texture(TextureUnit(1), &second);
+    assert_eq!(api.inner().take_commands(), vec![
+      Command::TexParameter {
+        texture: RecordingTexture(0),
+        parameter: TextureParameter::WrapS(TextureWrap::Repeat),
+      },
+      Command::GenerateMipmap(RecordingTexture(0)),
+    ]);
+  }
+
+  #[test]
+  fn skips_binding_the_bound_buffer() {
+    let api = CachedRenderAPI::create(RecordingRenderAPI::create(&[], &[]));
+    let first = api.create_buffer(BufferKind::ArrayBuffer).unwrap();
+    let second = api.create_buffer(BufferKind::ArrayBuffer).unwrap();
+    let uniforms = api.create_buffer(BufferKind::UniformBuffer).unwrap();
+    api.inner().take_commands();
+    api.reset_stats();
+
+    api.bind_buffer(&first);
+    api.bind_buffer(&first);
+    api.bind_buffer(&uniforms);
+    api.bind_buffer(&first);
+    api.bind_buffer(&second);
+
+    assert_eq!(api.inner().take_commands(), vec![
+      Command::BindBuffer(first.inner),
+      Command::BindBuffer(uniforms.inner),
+      Command::BindBuffer(second.inner),
+    ]);
+    assert_eq!(api.stats(), CacheStats { issued: 3, skipped: 2 });
+  }
+
+  #[test]
+  fn uploads_bind_the_buffer_until_the_vertex_array_changes() {
+    let api = CachedRenderAPI::create(RecordingRenderAPI::create(&[], &[]));
+    let vertices = api.create_buffer(BufferKind::ArrayBuffer).unwrap();
+    let indices = api.create_buffer(BufferKind::ElementBuffer).unwrap();
+    let vertex_array = api.create_vertex_array().unwrap();
+    api.buffer_data_with_size(&vertices, 16, DrawKind::StaticDraw);
+    api.buffer_data_with_size(&indices, 16, DrawKind::StaticDraw);
+    api.inner().take_commands();
+
+    api.bind_buffer(&vertices);
+    api.bind_buffer(&indices);
+    assert_eq!(api.inner().take_commands(), vec![]);
+
+    // the element buffer is part of the vertex array.
+    api.bind_vertex_array(Some(&vertex_array));
+    api.bind_buffer(&vertices);
+    api.bind_buffer(&indices);
+    assert_eq!(api.inner().take_commands(), vec![
+      Command::BindVertexArray(Some(vertex_array.inner)),
+      Command::BindBuffer(indices.inner),
+    ]);
+  }
+
+  #[test]
+  fn uniform_values_are_kept_per_program() {
+    let api = CachedRenderAPI::create(RecordingRenderAPI::create(&[], &["color"]));
+    let first = RecordingProgram(0);
+    let second = api.inner().add_program(&[], &["color"]);
+    api.use_program(&first);
+    api.uniform1f(Keys, 1.0).unwrap();
+    api.use_program(&second);
+    api.uniform1f(Keys, 1.0).unwrap();
+    api.use_program(&second);
+    api.use_program(&first);
+    api.uniform1f(Keys, 1.0).unwrap();
+
+    let value = UniformValue::Float(1.0);
+    assert_eq!(api.inner().take_commands(), vec![
+      Command::UseProgram(first),
+      Command::SetUniform { index: RecordingUniformIndex(0), value: value.clone() },
+      Command::UseProgram(second),
+      Command::SetUniform { index: RecordingUniformIndex(0), value },
+      Command::UseProgram(first),
+    ]);
+  }
+
+  #[test]
+  fn invalidate_forgets_state_but_not_lookups() {
+    let api = CachedRenderAPI::create(RecordingRenderAPI::create(&[], &["color"]));
+    api.uniform1f(Keys, 1.0).unwrap();
+    api.clear_color(0.0, 0.0, 0.0, 1.0);
+    api.invalidate();
+    api.reset_stats();
+    api.uniform1f(Keys, 1.0).unwrap();
+    api.clear_color(0.0, 0.0, 0.0, 1.0);
+
+    assert_eq!(api.inner().take_commands().len(), 4);
+    assert_eq!(api.stats(), CacheStats { issued: 2, skipped: 1 });
+  }
+}
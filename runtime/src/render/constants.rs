@@ -1,6 +1,10 @@
-use web_sys::{WebGl2RenderingContext};
+#[cfg(feature = "trace")]
+use serde::{Serialize, Deserialize};
+use std::ops::BitOr;
+use super::gl;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
 pub enum DrawKind {
   StaticDraw,
   DynamicDraw,
@@ -14,14 +18,15 @@ pub trait HasDrawKind {
 impl HasDrawKind for DrawKind {
   fn draw_kind_constant(&self) -> u32 {
     match self {
-      DrawKind::StaticDraw => WebGl2RenderingContext::STATIC_DRAW,
-      DrawKind::DynamicDraw => WebGl2RenderingContext::DYNAMIC_DRAW,
-      DrawKind::StreamDraw => WebGl2RenderingContext::STREAM_DRAW,
+      DrawKind::StaticDraw => gl::STATIC_DRAW,
+      DrawKind::DynamicDraw => gl::DYNAMIC_DRAW,
+      DrawKind::StreamDraw => gl::STREAM_DRAW,
     }
   }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
 pub enum DrawArrayKind {
   Points,
   LineStrip,
@@ -39,18 +44,19 @@ pub trait HasDrawArrayKind {
 impl HasDrawArrayKind for DrawArrayKind {
   fn draw_array_kind_constant(&self) -> u32 {
     match self {
-      DrawArrayKind::Points => WebGl2RenderingContext::POINTS,
-      DrawArrayKind::LineStrip => WebGl2RenderingContext::LINE_STRIP,
-      DrawArrayKind::LineLoop => WebGl2RenderingContext::LINE_LOOP,
-      DrawArrayKind::Lines => WebGl2RenderingContext::LINES,
-      DrawArrayKind::TriangleStrip => WebGl2RenderingContext::TRIANGLE_STRIP,
-      DrawArrayKind::TriangleFan => WebGl2RenderingContext::TRIANGLE_FAN,
-      DrawArrayKind::Triangles => WebGl2RenderingContext::TRIANGLES,
+      DrawArrayKind::Points => gl::POINTS,
+      DrawArrayKind::LineStrip => gl::LINE_STRIP,
+      DrawArrayKind::LineLoop => gl::LINE_LOOP,
+      DrawArrayKind::Lines => gl::LINES,
+      DrawArrayKind::TriangleStrip => gl::TRIANGLE_STRIP,
+      DrawArrayKind::TriangleFan => gl::TRIANGLE_FAN,
+      DrawArrayKind::Triangles => gl::TRIANGLES,
     }
   }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
 pub enum ClearMask {
   ColorBufferBit,
   DepthBufferBit,
@@ -64,17 +70,216 @@ pub trait HasClearMaskKind {
 impl HasClearMaskKind for ClearMask {
   fn clear_mask_constant(&self) -> u32 {
     match self {
-      ClearMask::ColorBufferBit => WebGl2RenderingContext::COLOR_BUFFER_BIT,
-      ClearMask::DepthBufferBit => WebGl2RenderingContext::DEPTH_BUFFER_BIT,
-      ClearMask::StencilBufferBit => WebGl2RenderingContext::STENCIL_BUFFER_BIT,
+      ClearMask::ColorBufferBit => gl::COLOR_BUFFER_BIT,
+      ClearMask::DepthBufferBit => gl::DEPTH_BUFFER_BIT,
+      ClearMask::StencilBufferBit => gl::STENCIL_BUFFER_BIT,
     }
   }
 }
 
-#[derive(Clone, Copy, Debug)]
+/**
+ * The buffers cleared by a single `clear`, which is made by
+ * combining `ClearMask`s with `|`.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub struct ClearMasks {
+  pub color: bool,
+  pub depth: bool,
+  pub stencil: bool,
+}
+
+impl ClearMasks {
+  pub fn contains(&self, mask: ClearMask) -> bool {
+    match mask {
+      ClearMask::ColorBufferBit => self.color,
+      ClearMask::DepthBufferBit => self.depth,
+      ClearMask::StencilBufferBit => self.stencil,
+    }
+  }
+}
+
+impl From<ClearMask> for ClearMasks {
+  fn from(mask: ClearMask) -> Self {
+    ClearMasks::default() | mask
+  }
+}
+
+impl BitOr<ClearMask> for ClearMasks {
+  type Output = ClearMasks;
+
+  fn bitor(self, mask: ClearMask) -> ClearMasks {
+    ClearMasks {
+      color: self.color || mask == ClearMask::ColorBufferBit,
+      depth: self.depth || mask == ClearMask::DepthBufferBit,
+      stencil: self.stencil || mask == ClearMask::StencilBufferBit,
+    }
+  }
+}
+
+impl BitOr<ClearMask> for ClearMask {
+  type Output = ClearMasks;
+
+  fn bitor(self, mask: ClearMask) -> ClearMasks {
+    ClearMasks::from(self) | mask
+  }
+}
+
+impl HasClearMaskKind for ClearMasks {
+  fn clear_mask_constant(&self) -> u32 {
+    [ClearMask::ColorBufferBit, ClearMask::DepthBufferBit, ClearMask::StencilBufferBit].iter()
+      .filter(|mask| self.contains(**mask))
+      .fold(0, |bits, mask| bits | mask.clear_mask_constant())
+  }
+}
+
+/**
+ * How a depth or stencil test compares the incoming value
+ * against the value stored in the buffer.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum CompareFunc {
+  Never,
+  Less,
+  Equal,
+  LessOrEqual,
+  Greater,
+  NotEqual,
+  GreaterOrEqual,
+  Always,
+}
+
+pub trait HasCompareFunc {
+  fn compare_func_constant(&self) -> u32;
+}
+
+impl HasCompareFunc for CompareFunc {
+  fn compare_func_constant(&self) -> u32 {
+    match self {
+      CompareFunc::Never => gl::NEVER,
+      CompareFunc::Less => gl::LESS,
+      CompareFunc::Equal => gl::EQUAL,
+      CompareFunc::LessOrEqual => gl::LEQUAL,
+      CompareFunc::Greater => gl::GREATER,
+      CompareFunc::NotEqual => gl::NOTEQUAL,
+      CompareFunc::GreaterOrEqual => gl::GEQUAL,
+      CompareFunc::Always => gl::ALWAYS,
+    }
+  }
+}
+
+/**
+ * What happens to the stored stencil value after the stencil
+ * & depth tests.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum StencilOp {
+  Keep,
+  Zero,
+  /**
+   * Replaces the value with the reference value.
+   */
+  Replace,
+  /**
+   * Increments the value, clamping at the maximum.
+   */
+  Increment,
+  /**
+   * Increments the value, wrapping to 0 past the maximum.
+   */
+  IncrementWrap,
+  /**
+   * Decrements the value, clamping at 0.
+   */
+  Decrement,
+  /**
+   * Decrements the value, wrapping to the maximum past 0.
+   */
+  DecrementWrap,
+  Invert,
+}
+
+pub trait HasStencilOp {
+  fn stencil_op_constant(&self) -> u32;
+}
+
+impl HasStencilOp for StencilOp {
+  fn stencil_op_constant(&self) -> u32 {
+    match self {
+      StencilOp::Keep => gl::KEEP,
+      StencilOp::Zero => gl::ZERO,
+      StencilOp::Replace => gl::REPLACE,
+      StencilOp::Increment => gl::INCR,
+      StencilOp::IncrementWrap => gl::INCR_WRAP,
+      StencilOp::Decrement => gl::DECR,
+      StencilOp::DecrementWrap => gl::DECR_WRAP,
+      StencilOp::Invert => gl::INVERT,
+    }
+  }
+}
+
+/**
+ * The faces of triangles that are culled, a triangle is
+ * front facing when its vertices are counter clockwise.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum CullFace {
+  Front,
+  Back,
+  FrontAndBack,
+}
+
+pub trait HasCullFace {
+  fn cull_face_constant(&self) -> u32;
+}
+
+impl HasCullFace for CullFace {
+  fn cull_face_constant(&self) -> u32 {
+    match self {
+      CullFace::Front => gl::FRONT,
+      CullFace::Back => gl::BACK,
+      CullFace::FrontAndBack => gl::FRONT_AND_BACK,
+    }
+  }
+}
+
+/**
+ * A capability toggled with `enable` & `disable`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+  Blend,
+  CullFace,
+  DepthTest,
+  ScissorTest,
+  StencilTest,
+}
+
+pub trait HasCapability {
+  fn capability_constant(&self) -> u32;
+}
+
+impl HasCapability for Capability {
+  fn capability_constant(&self) -> u32 {
+    match self {
+      Capability::Blend => gl::BLEND,
+      Capability::CullFace => gl::CULL_FACE,
+      Capability::DepthTest => gl::DEPTH_TEST,
+      Capability::ScissorTest => gl::SCISSOR_TEST,
+      Capability::StencilTest => gl::STENCIL_TEST,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
 pub enum BufferKind {
   ArrayBuffer,
   ElementBuffer,
+  UniformBuffer,
 }
 
 pub trait HasBufferKind {
@@ -84,19 +289,24 @@ pub trait HasBufferKind {
 impl HasBufferKind for BufferKind {
   fn buffer_kind_constant(&self) -> u32 {
     match self {
-      BufferKind::ArrayBuffer => WebGl2RenderingContext::ARRAY_BUFFER,
-      BufferKind::ElementBuffer => WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER_BINDING,
+      BufferKind::ArrayBuffer => gl::ARRAY_BUFFER,
+      BufferKind::ElementBuffer => gl::ELEMENT_ARRAY_BUFFER,
+      BufferKind::UniformBuffer => gl::UNIFORM_BUFFER,
     }
   }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
 pub enum ViewPrecision {
   Byte,
   Short,
   UnsignedByte,
   UnsignedShort,
+  UnsignedInt,
   Float,
+  Int,
+  HalfFloat,
 }
 
 pub trait HasViewPrecision {
@@ -106,16 +316,71 @@ pub trait HasViewPrecision {
 impl HasViewPrecision for ViewPrecision {
   fn view_precision_constant(&self) -> u32 {
     match self {
-      ViewPrecision::Byte => WebGl2RenderingContext::BYTE,
-      ViewPrecision::Short => WebGl2RenderingContext::SHORT,
-      ViewPrecision::UnsignedByte => WebGl2RenderingContext::UNSIGNED_BYTE,
-      ViewPrecision::UnsignedShort => WebGl2RenderingContext::UNSIGNED_SHORT,
-      ViewPrecision::Float => WebGl2RenderingContext::FLOAT,
+      ViewPrecision::Byte => gl::BYTE,
+      ViewPrecision::Short => gl::SHORT,
+      ViewPrecision::UnsignedByte => gl::UNSIGNED_BYTE,
+      ViewPrecision::UnsignedShort => gl::UNSIGNED_SHORT,
+      ViewPrecision::UnsignedInt => gl::UNSIGNED_INT,
+      ViewPrecision::Float => gl::FLOAT,
+      ViewPrecision::Int => gl::INT,
+      ViewPrecision::HalfFloat => gl::HALF_FLOAT,
     }
   }
 }
 
-#[derive(Clone, Copy, Debug)]
+impl ViewPrecision {
+  /**
+   * The size of a single element in bytes.
+   */
+  pub fn size(&self) -> usize {
+    match self {
+      ViewPrecision::Byte | ViewPrecision::UnsignedByte => 1,
+      ViewPrecision::Short | ViewPrecision::UnsignedShort | ViewPrecision::HalfFloat => 2,
+      ViewPrecision::Int | ViewPrecision::UnsignedInt | ViewPrecision::Float => 4,
+    }
+  }
+}
+
+/**
+ * The type of the indices in an element buffer.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum IndexKind {
+  UnsignedByte,
+  UnsignedShort,
+  UnsignedInt,
+}
+
+pub trait HasIndexKind {
+  fn index_kind_constant(&self) -> u32;
+}
+
+impl HasIndexKind for IndexKind {
+  fn index_kind_constant(&self) -> u32 {
+    match self {
+      IndexKind::UnsignedByte => gl::UNSIGNED_BYTE,
+      IndexKind::UnsignedShort => gl::UNSIGNED_SHORT,
+      IndexKind::UnsignedInt => gl::UNSIGNED_INT,
+    }
+  }
+}
+
+impl IndexKind {
+  /**
+   * The size of a single index in bytes.
+   */
+  pub fn size(&self) -> usize {
+    match self {
+      IndexKind::UnsignedByte => 1,
+      IndexKind::UnsignedShort => 2,
+      IndexKind::UnsignedInt => 4,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
 pub enum BlendFuncFactor {
   /**
    * Multiplies all colors by 0.
@@ -188,21 +453,459 @@ pub trait HasBlendFuncFactor {
 impl HasBlendFuncFactor for BlendFuncFactor {
   fn blend_func_factor_constant(&self) -> u32 {
     match self {
-      BlendFuncFactor::One => WebGl2RenderingContext::ONE,
-      BlendFuncFactor::Zero => WebGl2RenderingContext::ZERO,
-      BlendFuncFactor::SrcColor => WebGl2RenderingContext::SRC_COLOR,
-      BlendFuncFactor::OneMinusSrcColor => WebGl2RenderingContext::ONE_MINUS_SRC_COLOR,
-      BlendFuncFactor::DstColor => WebGl2RenderingContext::DST_COLOR,
-      BlendFuncFactor::OneMinusDstColor => WebGl2RenderingContext::ONE_MINUS_DST_COLOR,
-      BlendFuncFactor::SrcAlpha => WebGl2RenderingContext::SRC_ALPHA,
-      BlendFuncFactor::OneMinusSrcAlpha => WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
-      BlendFuncFactor::DstAlpha => WebGl2RenderingContext::DST_ALPHA,
-      BlendFuncFactor::OneMinusDstAlpha => WebGl2RenderingContext::ONE_MINUS_DST_ALPHA,
-      BlendFuncFactor::ConstantColor => WebGl2RenderingContext::CONSTANT_COLOR,
-      BlendFuncFactor::OneMinusConstantColor => WebGl2RenderingContext::ONE_MINUS_CONSTANT_COLOR,
-      BlendFuncFactor::ConstantAlpha => WebGl2RenderingContext::CONSTANT_ALPHA,
-      BlendFuncFactor::OneMinusConstantAlpha => WebGl2RenderingContext::ONE_MINUS_CONSTANT_ALPHA,
-      BlendFuncFactor::SrcAlphaSaturate => WebGl2RenderingContext::SRC_ALPHA_SATURATE,
+      BlendFuncFactor::One => gl::ONE,
+      BlendFuncFactor::Zero => gl::ZERO,
+      BlendFuncFactor::SrcColor => gl::SRC_COLOR,
+      BlendFuncFactor::OneMinusSrcColor => gl::ONE_MINUS_SRC_COLOR,
+      BlendFuncFactor::DstColor => gl::DST_COLOR,
+      BlendFuncFactor::OneMinusDstColor => gl::ONE_MINUS_DST_COLOR,
+      BlendFuncFactor::SrcAlpha => gl::SRC_ALPHA,
+      BlendFuncFactor::OneMinusSrcAlpha => gl::ONE_MINUS_SRC_ALPHA,
+      BlendFuncFactor::DstAlpha => gl::DST_ALPHA,
+      BlendFuncFactor::OneMinusDstAlpha => gl::ONE_MINUS_DST_ALPHA,
+      BlendFuncFactor::ConstantColor => gl::CONSTANT_COLOR,
+      BlendFuncFactor::OneMinusConstantColor => gl::ONE_MINUS_CONSTANT_COLOR,
+      BlendFuncFactor::ConstantAlpha => gl::CONSTANT_ALPHA,
+      BlendFuncFactor::OneMinusConstantAlpha => gl::ONE_MINUS_CONSTANT_ALPHA,
+      BlendFuncFactor::SrcAlphaSaturate => gl::SRC_ALPHA_SATURATE,
+    }
+  }
+}
+
+/**
+ * A texture image unit, which is where a texture is bound
+ * for a sampler uniform to read from, where the sampler is
+ * set to the index of the unit with `uniform1i`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub struct TextureUnit(pub u32);
+
+pub trait HasTextureUnit {
+  fn texture_unit_constant(&self) -> u32;
+}
+
+impl HasTextureUnit for TextureUnit {
+  fn texture_unit_constant(&self) -> u32 {
+    gl::TEXTURE0 + self.0
+  }
+}
+
+/**
+ * How a texture is sampled when it's drawn larger than its
+ * size.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum TextureMagFilter {
+  Nearest,
+  Linear,
+}
+
+pub trait HasTextureMagFilter {
+  fn texture_mag_filter_constant(&self) -> u32;
+}
+
+impl HasTextureMagFilter for TextureMagFilter {
+  fn texture_mag_filter_constant(&self) -> u32 {
+    match self {
+      TextureMagFilter::Nearest => gl::NEAREST,
+      TextureMagFilter::Linear => gl::LINEAR,
+    }
+  }
+}
+
+/**
+ * How a texture is sampled when it's drawn smaller than its
+ * size, the mipmap filters require the texture to have had
+ * its mipmaps generated, otherwise it's incomplete & samples
+ * as black.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum TextureMinFilter {
+  Nearest,
+  Linear,
+  NearestMipmapNearest,
+  LinearMipmapNearest,
+  NearestMipmapLinear,
+  LinearMipmapLinear,
+}
+
+pub trait HasTextureMinFilter {
+  fn texture_min_filter_constant(&self) -> u32;
+}
+
+impl HasTextureMinFilter for TextureMinFilter {
+  fn texture_min_filter_constant(&self) -> u32 {
+    match self {
+      TextureMinFilter::Nearest => gl::NEAREST,
+      TextureMinFilter::Linear => gl::LINEAR,
+      TextureMinFilter::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
+      TextureMinFilter::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
+      TextureMinFilter::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
+      TextureMinFilter::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
+    }
+  }
+}
+
+impl TextureMinFilter {
+  pub fn uses_mipmaps(&self) -> bool {
+    !matches!(self, TextureMinFilter::Nearest | TextureMinFilter::Linear)
+  }
+}
+
+/**
+ * How texture coordinates outside of 0 to 1 are handled.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum TextureWrap {
+  Repeat,
+  ClampToEdge,
+  MirroredRepeat,
+}
+
+pub trait HasTextureWrap {
+  fn texture_wrap_constant(&self) -> u32;
+}
+
+impl HasTextureWrap for TextureWrap {
+  fn texture_wrap_constant(&self) -> u32 {
+    match self {
+      TextureWrap::Repeat => gl::REPEAT,
+      TextureWrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+      TextureWrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+    }
+  }
+}
+
+/**
+ * A sampler parameter of a texture along with its value, so
+ * each parameter can only be given the values it accepts.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum TextureParameter {
+  MagFilter(TextureMagFilter),
+  MinFilter(TextureMinFilter),
+  WrapS(TextureWrap),
+  WrapT(TextureWrap),
+}
+
+pub trait HasTextureParameter {
+  /**
+   * The name of the parameter & the value for it.
+   */
+  fn texture_parameter_constants(&self) -> (u32, u32);
+}
+
+impl HasTextureParameter for TextureParameter {
+  fn texture_parameter_constants(&self) -> (u32, u32) {
+    match self {
+      TextureParameter::MagFilter(f) => (gl::TEXTURE_MAG_FILTER, f.texture_mag_filter_constant()),
+      TextureParameter::MinFilter(f) => (gl::TEXTURE_MIN_FILTER, f.texture_min_filter_constant()),
+      TextureParameter::WrapS(w) => (gl::TEXTURE_WRAP_S, w.texture_wrap_constant()),
+      TextureParameter::WrapT(w) => (gl::TEXTURE_WRAP_T, w.texture_wrap_constant()),
+    }
+  }
+}
+
+/**
+ * Where an image is attached to a framebuffer, `Color` takes
+ * the index of the colour attachment.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum FramebufferAttachment {
+  Color(u32),
+  Depth,
+  Stencil,
+  DepthStencil,
+}
+
+pub trait HasFramebufferAttachment {
+  fn framebuffer_attachment_constant(&self) -> u32;
+}
+
+impl HasFramebufferAttachment for FramebufferAttachment {
+  fn framebuffer_attachment_constant(&self) -> u32 {
+    match self {
+      FramebufferAttachment::Color(i) => gl::COLOR_ATTACHMENT0 + i,
+      FramebufferAttachment::Depth => gl::DEPTH_ATTACHMENT,
+      FramebufferAttachment::Stencil => gl::STENCIL_ATTACHMENT,
+      FramebufferAttachment::DepthStencil => gl::DEPTH_STENCIL_ATTACHMENT,
+    }
+  }
+}
+
+/**
+ * The internal format of a renderbuffer's storage.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub enum RenderbufferFormat {
+  Rgba4,
+  Rgba8,
+  Rgb565,
+  DepthComponent16,
+  DepthComponent24,
+  StencilIndex8,
+  Depth24Stencil8,
+}
+
+pub trait HasRenderbufferFormat {
+  fn renderbuffer_format_constant(&self) -> u32;
+}
+
+impl HasRenderbufferFormat for RenderbufferFormat {
+  fn renderbuffer_format_constant(&self) -> u32 {
+    match self {
+      RenderbufferFormat::Rgba4 => gl::RGBA4,
+      RenderbufferFormat::Rgba8 => gl::RGBA8,
+      RenderbufferFormat::Rgb565 => gl::RGB565,
+      RenderbufferFormat::DepthComponent16 => gl::DEPTH_COMPONENT16,
+      RenderbufferFormat::DepthComponent24 => gl::DEPTH_COMPONENT24,
+      RenderbufferFormat::StencilIndex8 => gl::STENCIL_INDEX8,
+      RenderbufferFormat::Depth24Stencil8 => gl::DEPTH24_STENCIL8,
+    }
+  }
+}
+
+impl RenderbufferFormat {
+  /**
+   * If storage of this format can be attached at `attachment`.
+   */
+  pub fn can_attach_to(&self, attachment: FramebufferAttachment) -> bool {
+    use RenderbufferFormat::*;
+    matches!(
+      (self, attachment),
+      (Rgba4 | Rgba8 | Rgb565, FramebufferAttachment::Color(_))
+        | (DepthComponent16 | DepthComponent24, FramebufferAttachment::Depth)
+        | (StencilIndex8, FramebufferAttachment::Stencil)
+        | (Depth24Stencil8, FramebufferAttachment::DepthStencil)
+    )
+  }
+}
+
+/**
+ * Why a framebuffer can't be drawn to or read from, as
+ * returned by `checkFramebufferStatus`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FramebufferStatus {
+  /**
+   * An attachment is missing its image, or its format can't
+   * be used at the point it's attached.
+   */
+  IncompleteAttachment,
+  /**
+   * Nothing is attached to the framebuffer.
+   */
+  IncompleteMissingAttachment,
+  /**
+   * The attachments don't all have the same size.
+   */
+  IncompleteDimensions,
+  /**
+   * The attachments don't all have the same number of samples.
+   */
+  IncompleteMultisample,
+  /**
+   * The combination of formats isn't supported by the driver.
+   */
+  Unsupported,
+  /**
+   * A status this doesn't know about.
+   */
+  Unknown(u32),
+}
+
+pub trait HasFramebufferStatus {
+  fn framebuffer_status_constant(&self) -> u32;
+}
+
+impl HasFramebufferStatus for FramebufferStatus {
+  fn framebuffer_status_constant(&self) -> u32 {
+    match self {
+      FramebufferStatus::IncompleteAttachment => gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT,
+      FramebufferStatus::IncompleteMissingAttachment => gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT,
+      FramebufferStatus::IncompleteDimensions => gl::FRAMEBUFFER_INCOMPLETE_DIMENSIONS,
+      FramebufferStatus::IncompleteMultisample => gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE,
+      FramebufferStatus::Unsupported => gl::FRAMEBUFFER_UNSUPPORTED,
+      FramebufferStatus::Unknown(status) => *status,
+    }
+  }
+}
+
+impl FramebufferStatus {
+  /**
+   * Converts the result of `checkFramebufferStatus`, where
+   * anything but `FRAMEBUFFER_COMPLETE` is an error.
+   */
+  pub fn check(status: u32) -> Result<(), FramebufferStatus> {
+    match status {
+      gl::FRAMEBUFFER_COMPLETE => Ok(()),
+      gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => Err(FramebufferStatus::IncompleteAttachment),
+      gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => Err(FramebufferStatus::IncompleteMissingAttachment),
+      gl::FRAMEBUFFER_INCOMPLETE_DIMENSIONS => Err(FramebufferStatus::IncompleteDimensions),
+      gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => Err(FramebufferStatus::IncompleteMultisample),
+      gl::FRAMEBUFFER_UNSUPPORTED => Err(FramebufferStatus::Unsupported),
+      other => Err(FramebufferStatus::Unknown(other)),
+    }
+  }
+}
+
+/**
+ * The type of an active attribute or uniform, as returned by
+ * `getActiveAttrib` & `getActiveUniform`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderType {
+  Float,
+  FloatVec2,
+  FloatVec3,
+  FloatVec4,
+  Int,
+  IntVec2,
+  IntVec3,
+  IntVec4,
+  UnsignedInt,
+  UnsignedIntVec2,
+  UnsignedIntVec3,
+  UnsignedIntVec4,
+  Bool,
+  BoolVec2,
+  BoolVec3,
+  BoolVec4,
+  FloatMat2,
+  FloatMat3,
+  FloatMat4,
+  Sampler2D,
+  SamplerCube,
+  /**
+   * A type this doesn't know about, such as the other kinds
+   * of sampler.
+   */
+  Unknown(u32),
+}
+
+pub trait HasShaderType {
+  fn shader_type_constant(&self) -> u32;
+}
+
+impl HasShaderType for ShaderType {
+  fn shader_type_constant(&self) -> u32 {
+    match self {
+      ShaderType::Float => gl::FLOAT,
+      ShaderType::FloatVec2 => gl::FLOAT_VEC2,
+      ShaderType::FloatVec3 => gl::FLOAT_VEC3,
+      ShaderType::FloatVec4 => gl::FLOAT_VEC4,
+      ShaderType::Int => gl::INT,
+      ShaderType::IntVec2 => gl::INT_VEC2,
+      ShaderType::IntVec3 => gl::INT_VEC3,
+      ShaderType::IntVec4 => gl::INT_VEC4,
+      ShaderType::UnsignedInt => gl::UNSIGNED_INT,
+      ShaderType::UnsignedIntVec2 => gl::UNSIGNED_INT_VEC2,
+      ShaderType::UnsignedIntVec3 => gl::UNSIGNED_INT_VEC3,
+      ShaderType::UnsignedIntVec4 => gl::UNSIGNED_INT_VEC4,
+      ShaderType::Bool => gl::BOOL,
+      ShaderType::BoolVec2 => gl::BOOL_VEC2,
+      ShaderType::BoolVec3 => gl::BOOL_VEC3,
+      ShaderType::BoolVec4 => gl::BOOL_VEC4,
+      ShaderType::FloatMat2 => gl::FLOAT_MAT2,
+      ShaderType::FloatMat3 => gl::FLOAT_MAT3,
+      ShaderType::FloatMat4 => gl::FLOAT_MAT4,
+      ShaderType::Sampler2D => gl::SAMPLER_2D,
+      ShaderType::SamplerCube => gl::SAMPLER_CUBE,
+      ShaderType::Unknown(constant) => *constant,
+    }
+  }
+}
+
+impl ShaderType {
+  pub fn from_constant(constant: u32) -> Self {
+    match constant {
+      gl::FLOAT => ShaderType::Float,
+      gl::FLOAT_VEC2 => ShaderType::FloatVec2,
+      gl::FLOAT_VEC3 => ShaderType::FloatVec3,
+      gl::FLOAT_VEC4 => ShaderType::FloatVec4,
+      gl::INT => ShaderType::Int,
+      gl::INT_VEC2 => ShaderType::IntVec2,
+      gl::INT_VEC3 => ShaderType::IntVec3,
+      gl::INT_VEC4 => ShaderType::IntVec4,
+      gl::UNSIGNED_INT => ShaderType::UnsignedInt,
+      gl::UNSIGNED_INT_VEC2 => ShaderType::UnsignedIntVec2,
+      gl::UNSIGNED_INT_VEC3 => ShaderType::UnsignedIntVec3,
+      gl::UNSIGNED_INT_VEC4 => ShaderType::UnsignedIntVec4,
+      gl::BOOL => ShaderType::Bool,
+      gl::BOOL_VEC2 => ShaderType::BoolVec2,
+      gl::BOOL_VEC3 => ShaderType::BoolVec3,
+      gl::BOOL_VEC4 => ShaderType::BoolVec4,
+      gl::FLOAT_MAT2 => ShaderType::FloatMat2,
+      gl::FLOAT_MAT3 => ShaderType::FloatMat3,
+      gl::FLOAT_MAT4 => ShaderType::FloatMat4,
+      gl::SAMPLER_2D => ShaderType::Sampler2D,
+      gl::SAMPLER_CUBE => ShaderType::SamplerCube,
+      other => ShaderType::Unknown(other),
+    }
+  }
+
+  /**
+   * The name of the type in GLSL.
+   */
+  pub fn glsl_name(&self) -> String {
+    match self {
+      ShaderType::Float => "float".to_string(),
+      ShaderType::FloatVec2 => "vec2".to_string(),
+      ShaderType::FloatVec3 => "vec3".to_string(),
+      ShaderType::FloatVec4 => "vec4".to_string(),
+      ShaderType::Int => "int".to_string(),
+      ShaderType::IntVec2 => "ivec2".to_string(),
+      ShaderType::IntVec3 => "ivec3".to_string(),
+      ShaderType::IntVec4 => "ivec4".to_string(),
+      ShaderType::UnsignedInt => "uint".to_string(),
+      ShaderType::UnsignedIntVec2 => "uvec2".to_string(),
+      ShaderType::UnsignedIntVec3 => "uvec3".to_string(),
+      ShaderType::UnsignedIntVec4 => "uvec4".to_string(),
+      ShaderType::Bool => "bool".to_string(),
+      ShaderType::BoolVec2 => "bvec2".to_string(),
+      ShaderType::BoolVec3 => "bvec3".to_string(),
+      ShaderType::BoolVec4 => "bvec4".to_string(),
+      ShaderType::FloatMat2 => "mat2".to_string(),
+      ShaderType::FloatMat3 => "mat3".to_string(),
+      ShaderType::FloatMat4 => "mat4".to_string(),
+      ShaderType::Sampler2D => "sampler2D".to_string(),
+      ShaderType::SamplerCube => "samplerCube".to_string(),
+      ShaderType::Unknown(constant) => format!("unknown type {:#x}", constant),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+  Vertex,
+  Fragment,
+}
+
+pub trait HasShaderStage {
+  fn shader_stage_constant(&self) -> u32;
+}
+
+impl HasShaderStage for ShaderStage {
+  fn shader_stage_constant(&self) -> u32 {
+    match self {
+      ShaderStage::Vertex => gl::VERTEX_SHADER,
+      ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+    }
+  }
+}
+
+impl ShaderStage {
+  pub fn name(&self) -> &'static str {
+    match self {
+      ShaderStage::Vertex => "vertex",
+      ShaderStage::Fragment => "fragment",
     }
   }
 }
@@ -1,7 +1,23 @@
+#[cfg(feature = "web")]
+use std::cell::OnceCell;
+use std::fmt::Debug;
+#[cfg(feature = "web")]
 use wasm_bindgen::{JsCast, JsValue};
-use js_sys::{Object, Float32Array, WebAssembly};
+#[cfg(feature = "web")]
+use js_sys::{
+  Object,
+  Float32Array,
+  Int8Array,
+  Int16Array,
+  Int32Array,
+  Uint8Array,
+  Uint16Array,
+  Uint32Array,
+  WebAssembly,
+};
+#[cfg(feature = "web")]
 use web_sys::console::log_1;
-use super::constants::{HasBufferKind, ViewPrecision, HasViewPrecision};
+use super::constants::{HasBufferKind, IndexKind, ViewPrecision, HasViewPrecision};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Data<V: View, B: HasBufferKind> {
@@ -9,72 +25,399 @@ pub struct Data<V: View, B: HasBufferKind> {
   pub view: V,
 }
 
-#[derive(Clone, Debug)]
-pub struct Float32View {
-  size: usize,
-  data: Float32Array,
-}
-
 pub trait View: HasViewPrecision {
   fn length(&self) -> usize;
-  fn object(&self) -> &Object;
+
+  /**
+   * A javascript array of the contents, which for views over
+   * wasm memory is only valid until memory next grows, so it
+   * should be used straight away rather than kept.
+   */
+  #[cfg(feature = "web")]
+  fn object(&self) -> Result<Object, DataViewError>;
   fn get_precision(&self) -> ViewPrecision;
+
+  /**
+   * The contents of the view as little endian bytes, for
+   * backends that don't consume a javascript object.
+   */
+  fn to_bytes(&self) -> Vec<u8>;
+}
+
+/**
+ * A view that can be used as the indices of an element buffer.
+ */
+pub trait IndexView: View {
+  fn get_index_kind(&self) -> IndexKind;
+}
+
+/**
+ * A type a `TypedView` can hold, which is any type GL reads
+ * buffer data as.
+ */
+pub trait ViewElement: Copy + Debug {
+  const PRECISION: ViewPrecision;
+
+  fn write_le_bytes(&self, bytes: &mut Vec<u8>);
+
+  /**
+   * A javascript array over `length` elements of `memory`,
+   * from the element at `start`.
+   */
+  #[cfg(feature = "web")]
+  fn memory_array(memory: &JsValue, start: u32, length: u32) -> Object;
+}
+
+/**
+ * Element types that can be used as indices.
+ */
+pub trait IndexElement: ViewElement {
+  const INDEX_KIND: IndexKind;
 }
 
-impl Float32View {
-  pub fn create(data_raw: &[f32]) -> Result<Self, DataViewError> {
-    let data = Float32View::build_data(data_raw)?;
-    Ok(Float32View { data, size: data_raw.len() })
+/**
+ * Owns a copy of the data so it can be inspected from rust,
+ * & so the memory the javascript array is over stays put for
+ * as long as the view does.
+ *
+ * The javascript array is rebuilt over the current wasm memory
+ * each time it's requested, rather than kept, as growing wasm
+ * memory detaches the `ArrayBuffer` of any array created
+ * before it grew.
+ */
+#[derive(Clone, Debug)]
+pub struct TypedView<T: ViewElement> {
+  raw: Vec<T>,
+}
+
+pub type Int8View = TypedView<i8>;
+pub type Uint8View = TypedView<u8>;
+pub type Int16View = TypedView<i16>;
+pub type Uint16View = TypedView<u16>;
+pub type HalfFloatView = TypedView<HalfFloat>;
+pub type Int32View = TypedView<i32>;
+pub type Uint32View = TypedView<u32>;
+pub type Float32View = TypedView<f32>;
+
+impl<T> TypedView<T> where T: ViewElement {
+  pub fn create(data_raw: &[T]) -> Result<Self, DataViewError> {
+    Ok(TypedView { raw: data_raw.to_vec() })
   }
 
-  pub fn update_data(&mut self, data_raw: &[f32]) -> Result<(), DataViewError> {
-    self.data = Float32View::build_data(data_raw)?;
-    self.size = data_raw.len();
-    return Ok(())
+  pub fn update_data(&mut self, data_raw: &[T]) -> Result<(), DataViewError> {
+    self.raw = data_raw.to_vec();
+    Ok(())
   }
 
-  pub fn log(&self) {
-    let value = JsValue::from(&self.data);
-    log_1(&value);
+  pub fn as_slice(&self) -> &[T] {
+    &self.raw
   }
 
-  fn build_data(data_raw: &[f32]) -> Result<js_sys::Float32Array, DataViewError> {
-    let memory_buffer = wasm_bindgen::memory()
-      .dyn_into::<WebAssembly::Memory>()
-      .map_err(|_| DataViewError::FailedToCreateMemory)?
-      .buffer();
+  #[cfg(feature = "web")]
+  pub fn log(&self) -> Result<(), DataViewError> {
+    log_1(&JsValue::from(self.object()?));
+    Ok(())
+  }
 
-    let data_location = data_raw.as_ptr() as u32 / 4;
-    let data = js_sys::Float32Array::new(&memory_buffer)
-      .subarray(data_location, data_location + data_raw.len() as u32);
-    Ok(data)
+  #[cfg(feature = "web")]
+  fn build_data(data_raw: &[T]) -> Result<Object, DataViewError> {
+    let memory_buffer = wasm_memory_buffer()?;
+    let start = data_raw.as_ptr() as usize;
+    let element_size = std::mem::size_of::<T>();
+    Ok(T::memory_array(&memory_buffer, (start / element_size) as u32, data_raw.len() as u32))
   }
 }
 
-impl HasViewPrecision for Float32View {
+impl<T> HasViewPrecision for TypedView<T> where T: ViewElement {
   fn view_precision_constant(&self) -> u32 {
     self.get_precision().view_precision_constant()
   }
 }
 
-impl View for Float32View {
-  fn length(&self) -> usize { self.size }
-  fn object(&self) -> &Object { self.data.as_ref() }
+impl<T> View for TypedView<T> where T: ViewElement {
+  fn length(&self) -> usize { self.raw.len() }
+
+  #[cfg(feature = "web")]
+  fn object(&self) -> Result<Object, DataViewError> {
+    TypedView::build_data(&self.raw)
+  }
+
+  fn get_precision(&self) -> ViewPrecision {
+    T::PRECISION
+  }
+
+  fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(self.raw.len() * T::PRECISION.size());
+    for element in self.raw.iter() {
+      element.write_le_bytes(&mut bytes);
+    }
+    bytes
+  }
+}
+
+impl<T> IndexView for TypedView<T> where T: IndexElement {
+  fn get_index_kind(&self) -> IndexKind {
+    T::INDEX_KIND
+  }
+}
+
+#[cfg(feature = "web")]
+fn wasm_memory_buffer() -> Result<JsValue, DataViewError> {
+  let memory = wasm_bindgen::memory()
+    .dyn_into::<WebAssembly::Memory>()
+    .map_err(|_| DataViewError::FailedToCreateMemory)?;
+  Ok(memory.buffer())
+}
+
+macro_rules! view_element {
+  ($element:ty, $precision:expr, $array:ident) => {
+    impl ViewElement for $element {
+      const PRECISION: ViewPrecision = $precision;
+
+      fn write_le_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+      }
+
+      #[cfg(feature = "web")]
+      fn memory_array(memory: &JsValue, start: u32, length: u32) -> Object {
+        $array::new(memory).subarray(start, start + length).into()
+      }
+    }
+  };
+}
+
+view_element!(i8, ViewPrecision::Byte, Int8Array);
+view_element!(u8, ViewPrecision::UnsignedByte, Uint8Array);
+view_element!(i16, ViewPrecision::Short, Int16Array);
+view_element!(u16, ViewPrecision::UnsignedShort, Uint16Array);
+view_element!(i32, ViewPrecision::Int, Int32Array);
+view_element!(u32, ViewPrecision::UnsignedInt, Uint32Array);
+view_element!(f32, ViewPrecision::Float, Float32Array);
+
+impl IndexElement for u8 {
+  const INDEX_KIND: IndexKind = IndexKind::UnsignedByte;
+}
+
+impl IndexElement for u16 {
+  const INDEX_KIND: IndexKind = IndexKind::UnsignedShort;
+}
+
+impl IndexElement for u32 {
+  const INDEX_KIND: IndexKind = IndexKind::UnsignedInt;
+}
+
+/**
+ * A 16 bit float stored as its bits, as rust doesn't have a
+ * stable type for them.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HalfFloat(pub u16);
+
+impl HalfFloat {
+  /**
+   * Rounds to the nearest half float with ties to even, like
+   * GPUs do, where values too large become infinity & values
+   * too small become zero.
+   */
+  pub fn from_f32(value: f32) -> Self {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+      let nan = if mantissa != 0 { 0x200 } else { 0 };
+      return HalfFloat(sign | 0x7c00 | nan);
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+      return HalfFloat(sign | 0x7c00);
+    }
+    if exponent <= 0 {
+      if exponent < -10 {
+        return HalfFloat(sign);
+      }
+      let subnormal = round_shift(mantissa | 0x80_0000, (14 - exponent) as u32);
+      return HalfFloat(sign | subnormal as u16);
+    }
+
+    // a carry from rounding moves into the exponent, which
+    // is still the nearest value.
+    let half = round_shift(((exponent as u32) << 23) | mantissa, 13);
+    HalfFloat(sign | half as u16)
+  }
+
+  pub fn to_f32(self) -> f32 {
+    let sign = if self.0 & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((self.0 >> 10) & 0x1f) as i32;
+    let mantissa = (self.0 & 0x3ff) as f32;
+
+    match exponent {
+      0 => sign * mantissa * 2f32.powi(-24),
+      0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+      0x1f => f32::NAN,
+      _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+  }
+}
+
+/**
+ * Shifts out the low bits of `value`, rounding to the nearest
+ * result & to the even one when exactly halfway.
+ */
+fn round_shift(value: u32, shift: u32) -> u32 {
+  let truncated = value >> shift;
+  let remainder = value & ((1 << shift) - 1);
+  let halfway = 1 << (shift - 1);
+  if remainder > halfway || (remainder == halfway && truncated & 1 == 1) {
+    truncated + 1
+  } else {
+    truncated
+  }
+}
+
+impl ViewElement for HalfFloat {
+  const PRECISION: ViewPrecision = ViewPrecision::HalfFloat;
+
+  fn write_le_bytes(&self, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&self.0.to_le_bytes());
+  }
+
+  #[cfg(feature = "web")]
+  fn memory_array(memory: &JsValue, start: u32, length: u32) -> Object {
+    Uint16Array::new(memory).subarray(start, start + length).into()
+  }
+}
+
+/**
+ * A view over raw little endian bytes, which are treated as
+ * elements of `precision`, for data that didn't start out as
+ * a rust slice of that type (such as a loaded trace).
+ *
+ * Unlike `Float32View` the javascript array is a copy that
+ * is owned by javascript, & is kept. Javascript can detach its
+ * buffer by transferring it, after which the view reports
+ * `DetachedBuffer` rather than uploading an empty array.
+ */
+#[derive(Clone, Debug)]
+pub struct ByteView {
+  precision: ViewPrecision,
+  bytes: Vec<u8>,
+  #[cfg(feature = "web")]
+  data: OnceCell<Uint8Array>,
+}
+
+impl ByteView {
+  pub fn create(precision: ViewPrecision, bytes: &[u8]) -> Self {
+    ByteView {
+      precision,
+      bytes: bytes.to_vec(),
+      #[cfg(feature = "web")]
+      data: OnceCell::new(),
+    }
+  }
+}
+
+impl HasViewPrecision for ByteView {
+  fn view_precision_constant(&self) -> u32 {
+    self.precision.view_precision_constant()
+  }
+}
+
+impl View for ByteView {
+  fn length(&self) -> usize {
+    self.bytes.len() / self.precision.size()
+  }
+
+  #[cfg(feature = "web")]
+  fn object(&self) -> Result<Object, DataViewError> {
+    let array = self.data.get_or_init(|| Uint8Array::from(&self.bytes[..]));
+    // a detached buffer has no bytes left.
+    if array.byte_length() as usize != self.bytes.len() {
+      return Err(DataViewError::DetachedBuffer);
+    }
+    Ok(array.clone().into())
+  }
 
   fn get_precision(&self) -> ViewPrecision {
-    ViewPrecision::Float
+    self.precision
+  }
+
+  fn to_bytes(&self) -> Vec<u8> {
+    self.bytes.clone()
   }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum DataViewError {
   FailedToCreateMemory,
+  /**
+   * The `ArrayBuffer` under the javascript array of a view was
+   * detached, such as by being transferred to a worker.
+   */
+  DetachedBuffer,
 }
 
 impl DataViewError {
   pub fn to_string(&self) -> String {
     match self {
       DataViewError::FailedToCreateMemory => "Failed to create memory".to_string(),
+      DataViewError::DetachedBuffer => "The view's buffer was detached".to_string(),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn typed_views_are_little_endian() {
+    let view = Int16View::create(&[-2, 258]).unwrap();
+    assert_eq!(view.get_precision(), ViewPrecision::Short);
+    assert_eq!(view.to_bytes(), vec![0xfe, 0xff, 0x02, 0x01]);
+
+    let view = Int32View::create(&[1]).unwrap();
+    assert_eq!(view.view_precision_constant(), super::super::gl::INT);
+    assert_eq!(view.to_bytes(), vec![1, 0, 0, 0]);
+  }
+
+  #[test]
+  fn index_views_have_an_index_kind() {
+    assert_eq!(Uint8View::create(&[0]).unwrap().get_index_kind(), IndexKind::UnsignedByte);
+    assert_eq!(Uint32View::create(&[0]).unwrap().get_index_kind(), IndexKind::UnsignedInt);
+  }
+
+  #[test]
+  fn half_floats_round_trip() {
+    for (value, bits) in [(1.0, 0x3c00), (-2.0, 0xc000), (0.5, 0x3800), (65504.0, 0x7bff), (2f32.powi(-24), 0x0001)] {
+      assert_eq!(HalfFloat::from_f32(value), HalfFloat(bits));
+      assert_eq!(HalfFloat(bits).to_f32(), value);
+    }
+    assert_eq!(HalfFloat::from_f32(1.0e6), HalfFloat(0x7c00));
+    assert_eq!(HalfFloat::from_f32(1.0e-9), HalfFloat(0));
+    assert!(HalfFloat::from_f32(f32::NAN).to_f32().is_nan());
+  }
+
+  #[test]
+  fn half_floats_round_ties_to_even() {
+    assert_eq!(HalfFloat::from_f32(2049.0), HalfFloat(0x6800));
+    assert_eq!(HalfFloat::from_f32(2051.0), HalfFloat(0x6802));
+    assert_eq!(HalfFloat::from_f32(2049.5), HalfFloat(0x6801));
+    assert_eq!(HalfFloat::from_f32(65519.0), HalfFloat(0x7bff));
+    assert_eq!(HalfFloat::from_f32(65520.0), HalfFloat(0x7c00));
+  }
+
+  #[test]
+  fn half_floats_round_subnormals() {
+    let smallest = 2f32.powi(-24);
+    assert_eq!(HalfFloat::from_f32(2f32.powi(-15)), HalfFloat(0x0200));
+    assert_eq!(HalfFloat::from_f32(smallest * 0.5), HalfFloat(0));
+    assert_eq!(HalfFloat::from_f32(smallest * 0.75), HalfFloat(0x0001));
+    assert_eq!(HalfFloat::from_f32(smallest * 1.5), HalfFloat(0x0002));
+    assert_eq!(HalfFloat::from_f32(smallest * 2.5), HalfFloat(0x0002));
+    assert_eq!(HalfFloat::from_f32(-smallest * 3.0), HalfFloat(0x8003));
+    assert_eq!(HalfFloat(0x03ff).to_f32(), smallest * 1023.0);
+  }
+}
@@ -0,0 +1,191 @@
+use super::constants::{ShaderStage};
+use super::preprocessor::{ProcessedShader};
+use std::fmt;
+
+/**
+ * The number of lines shown either side of a line the info
+ * log points at.
+ */
+const CONTEXT_LINES: usize = 2;
+
+/**
+ * A shader that failed to compile, with the lines of its
+ * source that the info log points at.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompileError {
+  pub stage: ShaderStage,
+  pub log: Option<String>,
+  pub errors: Vec<SourceError>,
+}
+
+/**
+ * An entry in the info log that names a line of the source,
+ * along with that line & the ones around it. Once they're
+ * mapped back through the preprocessor, the lines are in
+ * `file` rather than the compiled source.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceError {
+  pub file: Option<String>,
+  pub line: usize,
+  pub message: String,
+  pub context: Vec<SourceLine>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLine {
+  pub number: usize,
+  pub text: String,
+}
+
+impl CompileError {
+  pub fn create(stage: ShaderStage, source: &str, log: Option<String>) -> Self {
+    let errors = match &log {
+      Some(log) => source_errors(source, log),
+      None => vec![],
+    };
+    CompileError { stage, log, errors }
+  }
+
+  /**
+   * Maps the lines from the processed source back to the
+   * files they came from, dropping any context from other
+   * files.
+   */
+  pub fn map_lines(mut self, shader: &ProcessedShader) -> Self {
+    for error in self.errors.iter_mut() {
+      let location = match shader.original_line(error.line) {
+        Some(location) => location,
+        None => continue,
+      };
+      error.context = error.context.drain(..).filter_map(|line| {
+        let original = shader.original_line(line.number).filter(|o| o.file == location.file)?;
+        Some(SourceLine { number: original.line, text: line.text })
+      }).collect();
+      error.file = Some(location.file.clone());
+      error.line = location.line;
+    }
+    self
+  }
+}
+
+impl fmt::Display for CompileError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.log {
+      None => write!(f, "failed to compile {} shader, for an unknown reason", self.stage.name())?,
+      Some(log) => write!(f, "failed to compile {} shader: {}", self.stage.name(), log.trim_end())?,
+    };
+    for error in self.errors.iter() {
+      match &error.file {
+        Some(file) => write!(f, "\n\n{}:{}: {}", file, error.line, error.message)?,
+        None => write!(f, "\n\n{}", error.message)?,
+      }
+      for line in error.context.iter() {
+        let marker = if line.number == error.line { ">" } else { " " };
+        write!(f, "\n{} {:>4} | {}", marker, line.number, line.text)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/**
+ * Finds every entry in the log that points at a line of the
+ * source. Drivers differ in how they format these, but they
+ * generally start with `<source>:<line>`, like ANGLE's
+ * `ERROR: 0:12: ...` or mesa's `0:12(5): error: ...`.
+ */
+fn source_errors(source: &str, log: &str) -> Vec<SourceError> {
+  let lines: Vec<&str> = source.lines().collect();
+  log.lines().filter_map(|entry| {
+    let line = error_line(entry)?;
+    if line == 0 || line > lines.len() {
+      return None;
+    }
+
+    let first = line.saturating_sub(CONTEXT_LINES).max(1);
+    let last = (line + CONTEXT_LINES).min(lines.len());
+    let context = (first..=last)
+      .map(|number| SourceLine { number, text: lines[number - 1].to_string() })
+      .collect();
+    Some(SourceError { file: None, line, message: entry.trim().to_string(), context })
+  }).collect()
+}
+
+fn error_line(entry: &str) -> Option<usize> {
+  let entry = entry.trim_start();
+  let entry = entry.strip_prefix("ERROR: ").or_else(|| entry.strip_prefix("WARNING: ")).unwrap_or(entry);
+  let (source, rest) = entry.split_once(':')?;
+  if source.is_empty() || !source.chars().all(|c| c.is_ascii_digit()) {
+    return None;
+  }
+  let line: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+  line.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::preprocessor::{ShaderPreprocessor};
+
+  const SOURCE: &str = "#version 300 es\nin vec2 position;\nuniform vec2 resolution;\nvoid main() {\n  gl_Position = vec4(positon, 0, 1);\n}";
+
+  #[test]
+  fn finds_the_lines_in_angle_logs() {
+    let log = "ERROR: 0:5: 'positon' : undeclared identifier\nERROR: 0:5: 'constructor' : not enough data provided for construction\n";
+    let error = CompileError::create(ShaderStage::Vertex, SOURCE, Some(log.to_string()));
+
+    assert_eq!(error.errors.len(), 2);
+    assert_eq!(error.errors[0].line, 5);
+    assert_eq!(error.errors[0].message, "ERROR: 0:5: 'positon' : undeclared identifier");
+    assert_eq!(error.errors[0].context.iter().map(|l| l.number).collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+  }
+
+  #[test]
+  fn finds_the_lines_in_mesa_logs() {
+    let log = "0:1(10): error: syntax error, unexpected IDENTIFIER";
+    let error = CompileError::create(ShaderStage::Fragment, SOURCE, Some(log.to_string()));
+
+    assert_eq!(error.errors.len(), 1);
+    assert_eq!(error.errors[0].context.iter().map(|l| l.number).collect::<Vec<_>>(), vec![1, 2, 3]);
+  }
+
+  #[test]
+  fn ignores_entries_without_a_line() {
+    let log = "ERROR: too many uniforms\nERROR: 0:99: out of range";
+    let error = CompileError::create(ShaderStage::Vertex, SOURCE, Some(log.to_string()));
+    assert!(error.errors.is_empty());
+  }
+
+  #[test]
+  fn maps_lines_back_to_the_included_file() {
+    let preprocessor = ShaderPreprocessor::create().with_chunk("clip_space", "vec2 a;\nvec2 b;");
+    let shader = preprocessor.process("vertex", "#version 300 es\n#include \"clip_space\"\nvoid main() {}").unwrap();
+    let log = "ERROR: 0:3: 'b' : redefinition";
+    let error = CompileError::create(ShaderStage::Vertex, &shader.source, Some(log.to_string())).map_lines(&shader);
+
+    assert_eq!(error.errors[0].file, Some("clip_space".to_string()));
+    assert_eq!(error.errors[0].line, 2);
+    assert_eq!(error.errors[0].context, vec![
+      SourceLine { number: 1, text: "vec2 a;".to_string() },
+      SourceLine { number: 2, text: "vec2 b;".to_string() },
+    ]);
+  }
+
+  #[test]
+  fn message_marks_the_offending_line() {
+    let log = "ERROR: 0:2: 'vec2' : syntax error";
+    let error = CompileError::create(ShaderStage::Vertex, SOURCE, Some(log.to_string()));
+
+    assert_eq!(error.to_string(), [
+      "failed to compile vertex shader: ERROR: 0:2: 'vec2' : syntax error",
+      "",
+      "ERROR: 0:2: 'vec2' : syntax error",
+      "     1 | #version 300 es",
+      ">    2 | in vec2 position;",
+      "     3 | uniform vec2 resolution;",
+      "     4 | void main() {",
+    ].join("\n"));
+  }
+}
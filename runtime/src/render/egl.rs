@@ -0,0 +1,127 @@
+use std::ffi::{c_void, CString};
+use std::os::raw::{c_char, c_uint};
+use std::ptr;
+
+/**
+ * A surfaceless EGL context on Mesa, for tests of the native
+ * backend. There's no window, so everything is drawn into
+ * framebuffers the test creates, & the context is current on
+ * the thread that created it until it's dropped.
+ */
+pub struct EglContext {
+  display: *mut c_void,
+  context: *mut c_void,
+}
+
+type EglDisplay = *mut c_void;
+type EglConfig = *mut c_void;
+type EglContextHandle = *mut c_void;
+
+const EGL_PLATFORM_SURFACELESS_MESA: c_uint = 0x31DD;
+const EGL_OPENGL_ES_API: c_uint = 0x30A0;
+const EGL_SURFACE_TYPE: i32 = 0x3033;
+const EGL_PBUFFER_BIT: i32 = 0x0001;
+const EGL_RENDERABLE_TYPE: i32 = 0x3040;
+const EGL_OPENGL_ES3_BIT: i32 = 0x0040;
+const EGL_CONTEXT_MAJOR_VERSION: i32 = 0x3098;
+const EGL_NONE: i32 = 0x3038;
+
+#[link(name = "EGL")]
+extern "C" {
+  fn eglGetPlatformDisplay(platform: c_uint, native_display: *mut c_void, attribs: *const isize) -> EglDisplay;
+  fn eglInitialize(display: EglDisplay, major: *mut i32, minor: *mut i32) -> c_uint;
+  fn eglBindAPI(api: c_uint) -> c_uint;
+  fn eglChooseConfig(
+      display: EglDisplay,
+      attribs: *const i32,
+      configs: *mut EglConfig,
+      size: i32,
+      count: *mut i32,
+  ) -> c_uint;
+  fn eglCreateContext(
+      display: EglDisplay,
+      config: EglConfig,
+      share: EglContextHandle,
+      attribs: *const i32,
+  ) -> EglContextHandle;
+  fn eglMakeCurrent(
+      display: EglDisplay,
+      draw: *mut c_void,
+      read: *mut c_void,
+      context: EglContextHandle,
+  ) -> c_uint;
+  fn eglDestroyContext(display: EglDisplay, context: EglContextHandle) -> c_uint;
+  fn eglTerminate(display: EglDisplay) -> c_uint;
+  fn eglGetProcAddress(name: *const c_char) -> *const c_void;
+}
+
+impl EglContext {
+  /**
+   * Makes an OpenGL ES 3 context current, or `None` when the
+   * driver can't make one.
+   */
+  pub fn create() -> Option<Self> {
+    // Safety: every handle passed back to EGL is one it gave
+    // out, & the attribute lists are terminated by EGL_NONE.
+    unsafe {
+      let display = eglGetPlatformDisplay(EGL_PLATFORM_SURFACELESS_MESA, ptr::null_mut(), ptr::null());
+      if display.is_null() || eglInitialize(display, ptr::null_mut(), ptr::null_mut()) == 0 {
+        return None;
+      }
+      if eglBindAPI(EGL_OPENGL_ES_API) == 0 {
+        eglTerminate(display);
+        return None;
+      }
+
+      // the default surface type is a window, which there's
+      // none of without a display.
+      let config_attribs = [
+        EGL_SURFACE_TYPE, EGL_PBUFFER_BIT,
+        EGL_RENDERABLE_TYPE, EGL_OPENGL_ES3_BIT,
+        EGL_NONE,
+      ];
+      let (mut config, mut count) = (ptr::null_mut(), 0);
+      if eglChooseConfig(display, config_attribs.as_ptr(), &mut config, 1, &mut count) == 0 || count == 0 {
+        eglTerminate(display);
+        return None;
+      }
+
+      let context_attribs = [EGL_CONTEXT_MAJOR_VERSION, 3, EGL_NONE];
+      let context = eglCreateContext(display, config, ptr::null_mut(), context_attribs.as_ptr());
+      if context.is_null() {
+        eglTerminate(display);
+        return None;
+      }
+      if eglMakeCurrent(display, ptr::null_mut(), ptr::null_mut(), context) == 0 {
+        eglDestroyContext(display, context);
+        eglTerminate(display);
+        return None;
+      }
+      Some(EglContext { display, context })
+    }
+  }
+
+  /**
+   * Loads the GL functions of the context with `glow`.
+   */
+  pub fn load(&self) -> glow::Context {
+    // Safety: the context is current on this thread.
+    unsafe {
+      glow::Context::from_loader_function(|name| {
+        let name = CString::new(name).unwrap();
+        eglGetProcAddress(name.as_ptr())
+      })
+    }
+  }
+}
+
+impl Drop for EglContext {
+  fn drop(&mut self) {
+    // Safety: the handles are only destroyed here.
+    unsafe {
+      eglMakeCurrent(self.display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+      eglDestroyContext(self.display, self.context);
+      eglTerminate(self.display);
+    }
+  }
+}
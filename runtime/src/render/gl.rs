@@ -0,0 +1,281 @@
+// The values of the GL enums used by `constants`, these are
+// the same in WebGL 2, OpenGL ES 3 & desktop OpenGL, so every
+// backend can share them without depending on `web-sys`.
+
+pub const STATIC_DRAW: u32 = 0x88E4;
+pub const DYNAMIC_DRAW: u32 = 0x88E8;
+pub const STREAM_DRAW: u32 = 0x88E0;
+
+pub const POINTS: u32 = 0x0000;
+pub const LINES: u32 = 0x0001;
+pub const LINE_LOOP: u32 = 0x0002;
+pub const LINE_STRIP: u32 = 0x0003;
+pub const TRIANGLES: u32 = 0x0004;
+pub const TRIANGLE_STRIP: u32 = 0x0005;
+pub const TRIANGLE_FAN: u32 = 0x0006;
+
+pub const DEPTH_BUFFER_BIT: u32 = 0x0100;
+pub const STENCIL_BUFFER_BIT: u32 = 0x0400;
+pub const COLOR_BUFFER_BIT: u32 = 0x4000;
+
+pub const NEVER: u32 = 0x0200;
+pub const LESS: u32 = 0x0201;
+pub const EQUAL: u32 = 0x0202;
+pub const LEQUAL: u32 = 0x0203;
+pub const GREATER: u32 = 0x0204;
+pub const NOTEQUAL: u32 = 0x0205;
+pub const GEQUAL: u32 = 0x0206;
+pub const ALWAYS: u32 = 0x0207;
+
+pub const KEEP: u32 = 0x1E00;
+pub const REPLACE: u32 = 0x1E01;
+pub const INCR: u32 = 0x1E02;
+pub const DECR: u32 = 0x1E03;
+pub const INVERT: u32 = 0x150A;
+pub const INCR_WRAP: u32 = 0x8507;
+pub const DECR_WRAP: u32 = 0x8508;
+
+pub const FRONT: u32 = 0x0404;
+pub const BACK: u32 = 0x0405;
+pub const FRONT_AND_BACK: u32 = 0x0408;
+
+pub const BLEND: u32 = 0x0BE2;
+pub const CULL_FACE: u32 = 0x0B44;
+pub const DEPTH_TEST: u32 = 0x0B71;
+pub const SCISSOR_TEST: u32 = 0x0C11;
+pub const STENCIL_TEST: u32 = 0x0B90;
+
+pub const ARRAY_BUFFER: u32 = 0x8892;
+pub const ELEMENT_ARRAY_BUFFER: u32 = 0x8893;
+pub const UNIFORM_BUFFER: u32 = 0x8A11;
+pub const INVALID_INDEX: u32 = 0xFFFF_FFFF;
+
+pub const BYTE: u32 = 0x1400;
+pub const UNSIGNED_BYTE: u32 = 0x1401;
+pub const SHORT: u32 = 0x1402;
+pub const UNSIGNED_SHORT: u32 = 0x1403;
+pub const INT: u32 = 0x1404;
+pub const UNSIGNED_INT: u32 = 0x1405;
+pub const FLOAT: u32 = 0x1406;
+pub const HALF_FLOAT: u32 = 0x140B;
+
+pub const ZERO: u32 = 0x0000;
+pub const ONE: u32 = 0x0001;
+pub const SRC_COLOR: u32 = 0x0300;
+pub const ONE_MINUS_SRC_COLOR: u32 = 0x0301;
+pub const SRC_ALPHA: u32 = 0x0302;
+pub const ONE_MINUS_SRC_ALPHA: u32 = 0x0303;
+pub const DST_ALPHA: u32 = 0x0304;
+pub const ONE_MINUS_DST_ALPHA: u32 = 0x0305;
+pub const DST_COLOR: u32 = 0x0306;
+pub const ONE_MINUS_DST_COLOR: u32 = 0x0307;
+pub const SRC_ALPHA_SATURATE: u32 = 0x0308;
+pub const CONSTANT_COLOR: u32 = 0x8001;
+pub const ONE_MINUS_CONSTANT_COLOR: u32 = 0x8002;
+pub const CONSTANT_ALPHA: u32 = 0x8003;
+pub const ONE_MINUS_CONSTANT_ALPHA: u32 = 0x8004;
+
+pub const VERTEX_SHADER: u32 = 0x8B31;
+pub const FRAGMENT_SHADER: u32 = 0x8B30;
+
+pub const ACTIVE_UNIFORMS: u32 = 0x8B86;
+pub const ACTIVE_ATTRIBUTES: u32 = 0x8B89;
+
+pub const FLOAT_VEC2: u32 = 0x8B50;
+pub const FLOAT_VEC3: u32 = 0x8B51;
+pub const FLOAT_VEC4: u32 = 0x8B52;
+pub const INT_VEC2: u32 = 0x8B53;
+pub const INT_VEC3: u32 = 0x8B54;
+pub const INT_VEC4: u32 = 0x8B55;
+pub const UNSIGNED_INT_VEC2: u32 = 0x8DC6;
+pub const UNSIGNED_INT_VEC3: u32 = 0x8DC7;
+pub const UNSIGNED_INT_VEC4: u32 = 0x8DC8;
+pub const BOOL: u32 = 0x8B56;
+pub const BOOL_VEC2: u32 = 0x8B57;
+pub const BOOL_VEC3: u32 = 0x8B58;
+pub const BOOL_VEC4: u32 = 0x8B59;
+pub const FLOAT_MAT2: u32 = 0x8B5A;
+pub const FLOAT_MAT3: u32 = 0x8B5B;
+pub const FLOAT_MAT4: u32 = 0x8B5C;
+pub const SAMPLER_2D: u32 = 0x8B5E;
+pub const SAMPLER_CUBE: u32 = 0x8B60;
+
+pub const TEXTURE_2D: u32 = 0x0DE1;
+pub const TEXTURE_BINDING_2D: u32 = 0x8069;
+pub const TEXTURE0: u32 = 0x84C0;
+pub const RGBA: u32 = 0x1908;
+
+pub const TEXTURE_MAG_FILTER: u32 = 0x2800;
+pub const TEXTURE_MIN_FILTER: u32 = 0x2801;
+pub const TEXTURE_WRAP_S: u32 = 0x2802;
+pub const TEXTURE_WRAP_T: u32 = 0x2803;
+
+pub const NEAREST: u32 = 0x2600;
+pub const LINEAR: u32 = 0x2601;
+pub const NEAREST_MIPMAP_NEAREST: u32 = 0x2700;
+pub const LINEAR_MIPMAP_NEAREST: u32 = 0x2701;
+pub const NEAREST_MIPMAP_LINEAR: u32 = 0x2702;
+pub const LINEAR_MIPMAP_LINEAR: u32 = 0x2703;
+
+pub const REPEAT: u32 = 0x2901;
+pub const CLAMP_TO_EDGE: u32 = 0x812F;
+pub const MIRRORED_REPEAT: u32 = 0x8370;
+
+pub const FRAMEBUFFER: u32 = 0x8D40;
+pub const RENDERBUFFER: u32 = 0x8D41;
+
+pub const COLOR_ATTACHMENT0: u32 = 0x8CE0;
+pub const DEPTH_ATTACHMENT: u32 = 0x8D00;
+pub const STENCIL_ATTACHMENT: u32 = 0x8D20;
+pub const DEPTH_STENCIL_ATTACHMENT: u32 = 0x821A;
+
+pub const RGBA4: u32 = 0x8056;
+pub const RGBA8: u32 = 0x8058;
+pub const RGB565: u32 = 0x8D62;
+pub const DEPTH_COMPONENT16: u32 = 0x81A5;
+pub const DEPTH_COMPONENT24: u32 = 0x81A6;
+pub const STENCIL_INDEX8: u32 = 0x8D48;
+pub const DEPTH24_STENCIL8: u32 = 0x88F0;
+
+pub const FRAMEBUFFER_COMPLETE: u32 = 0x8CD5;
+pub const FRAMEBUFFER_INCOMPLETE_ATTACHMENT: u32 = 0x8CD6;
+pub const FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT: u32 = 0x8CD7;
+pub const FRAMEBUFFER_INCOMPLETE_DIMENSIONS: u32 = 0x8CD9;
+pub const FRAMEBUFFER_UNSUPPORTED: u32 = 0x8CDD;
+pub const FRAMEBUFFER_INCOMPLETE_MULTISAMPLE: u32 = 0x8D56;
+
+#[cfg(all(test, feature = "web"))]
+mod tests {
+  use super::*;
+  use web_sys::{WebGl2RenderingContext as Web};
+
+  #[test]
+  fn matches_web_sys() {
+    let pairs = [
+      (STATIC_DRAW, Web::STATIC_DRAW),
+      (DYNAMIC_DRAW, Web::DYNAMIC_DRAW),
+      (STREAM_DRAW, Web::STREAM_DRAW),
+      (POINTS, Web::POINTS),
+      (LINES, Web::LINES),
+      (LINE_LOOP, Web::LINE_LOOP),
+      (LINE_STRIP, Web::LINE_STRIP),
+      (TRIANGLES, Web::TRIANGLES),
+      (TRIANGLE_STRIP, Web::TRIANGLE_STRIP),
+      (TRIANGLE_FAN, Web::TRIANGLE_FAN),
+      (DEPTH_BUFFER_BIT, Web::DEPTH_BUFFER_BIT),
+      (STENCIL_BUFFER_BIT, Web::STENCIL_BUFFER_BIT),
+      (COLOR_BUFFER_BIT, Web::COLOR_BUFFER_BIT),
+      (NEVER, Web::NEVER),
+      (LESS, Web::LESS),
+      (EQUAL, Web::EQUAL),
+      (LEQUAL, Web::LEQUAL),
+      (GREATER, Web::GREATER),
+      (NOTEQUAL, Web::NOTEQUAL),
+      (GEQUAL, Web::GEQUAL),
+      (ALWAYS, Web::ALWAYS),
+      (KEEP, Web::KEEP),
+      (REPLACE, Web::REPLACE),
+      (INCR, Web::INCR),
+      (DECR, Web::DECR),
+      (INVERT, Web::INVERT),
+      (INCR_WRAP, Web::INCR_WRAP),
+      (DECR_WRAP, Web::DECR_WRAP),
+      (FRONT, Web::FRONT),
+      (BACK, Web::BACK),
+      (FRONT_AND_BACK, Web::FRONT_AND_BACK),
+      (BLEND, Web::BLEND),
+      (CULL_FACE, Web::CULL_FACE),
+      (DEPTH_TEST, Web::DEPTH_TEST),
+      (SCISSOR_TEST, Web::SCISSOR_TEST),
+      (STENCIL_TEST, Web::STENCIL_TEST),
+      (ARRAY_BUFFER, Web::ARRAY_BUFFER),
+      (ELEMENT_ARRAY_BUFFER, Web::ELEMENT_ARRAY_BUFFER),
+      (UNIFORM_BUFFER, Web::UNIFORM_BUFFER),
+      (INVALID_INDEX, Web::INVALID_INDEX),
+      (BYTE, Web::BYTE),
+      (UNSIGNED_BYTE, Web::UNSIGNED_BYTE),
+      (SHORT, Web::SHORT),
+      (UNSIGNED_SHORT, Web::UNSIGNED_SHORT),
+      (INT, Web::INT),
+      (UNSIGNED_INT, Web::UNSIGNED_INT),
+      (FLOAT, Web::FLOAT),
+      (HALF_FLOAT, Web::HALF_FLOAT),
+      (ZERO, Web::ZERO),
+      (ONE, Web::ONE),
+      (SRC_COLOR, Web::SRC_COLOR),
+      (ONE_MINUS_SRC_COLOR, Web::ONE_MINUS_SRC_COLOR),
+      (SRC_ALPHA, Web::SRC_ALPHA),
+      (ONE_MINUS_SRC_ALPHA, Web::ONE_MINUS_SRC_ALPHA),
+      (DST_ALPHA, Web::DST_ALPHA),
+      (ONE_MINUS_DST_ALPHA, Web::ONE_MINUS_DST_ALPHA),
+      (DST_COLOR, Web::DST_COLOR),
+      (ONE_MINUS_DST_COLOR, Web::ONE_MINUS_DST_COLOR),
+      (SRC_ALPHA_SATURATE, Web::SRC_ALPHA_SATURATE),
+      (CONSTANT_COLOR, Web::CONSTANT_COLOR),
+      (ONE_MINUS_CONSTANT_COLOR, Web::ONE_MINUS_CONSTANT_COLOR),
+      (CONSTANT_ALPHA, Web::CONSTANT_ALPHA),
+      (ONE_MINUS_CONSTANT_ALPHA, Web::ONE_MINUS_CONSTANT_ALPHA),
+      (VERTEX_SHADER, Web::VERTEX_SHADER),
+      (FRAGMENT_SHADER, Web::FRAGMENT_SHADER),
+      (ACTIVE_UNIFORMS, Web::ACTIVE_UNIFORMS),
+      (ACTIVE_ATTRIBUTES, Web::ACTIVE_ATTRIBUTES),
+      (FLOAT_VEC2, Web::FLOAT_VEC2),
+      (FLOAT_VEC3, Web::FLOAT_VEC3),
+      (FLOAT_VEC4, Web::FLOAT_VEC4),
+      (INT_VEC2, Web::INT_VEC2),
+      (INT_VEC3, Web::INT_VEC3),
+      (INT_VEC4, Web::INT_VEC4),
+      (UNSIGNED_INT_VEC2, Web::UNSIGNED_INT_VEC2),
+      (UNSIGNED_INT_VEC3, Web::UNSIGNED_INT_VEC3),
+      (UNSIGNED_INT_VEC4, Web::UNSIGNED_INT_VEC4),
+      (BOOL, Web::BOOL),
+      (BOOL_VEC2, Web::BOOL_VEC2),
+      (BOOL_VEC3, Web::BOOL_VEC3),
+      (BOOL_VEC4, Web::BOOL_VEC4),
+      (FLOAT_MAT2, Web::FLOAT_MAT2),
+      (FLOAT_MAT3, Web::FLOAT_MAT3),
+      (FLOAT_MAT4, Web::FLOAT_MAT4),
+      (SAMPLER_2D, Web::SAMPLER_2D),
+      (SAMPLER_CUBE, Web::SAMPLER_CUBE),
+      (TEXTURE_2D, Web::TEXTURE_2D),
+      (TEXTURE_BINDING_2D, Web::TEXTURE_BINDING_2D),
+      (TEXTURE0, Web::TEXTURE0),
+      (RGBA, Web::RGBA),
+      (TEXTURE_MAG_FILTER, Web::TEXTURE_MAG_FILTER),
+      (TEXTURE_MIN_FILTER, Web::TEXTURE_MIN_FILTER),
+      (TEXTURE_WRAP_S, Web::TEXTURE_WRAP_S),
+      (TEXTURE_WRAP_T, Web::TEXTURE_WRAP_T),
+      (NEAREST, Web::NEAREST),
+      (LINEAR, Web::LINEAR),
+      (NEAREST_MIPMAP_NEAREST, Web::NEAREST_MIPMAP_NEAREST),
+      (LINEAR_MIPMAP_NEAREST, Web::LINEAR_MIPMAP_NEAREST),
+      (NEAREST_MIPMAP_LINEAR, Web::NEAREST_MIPMAP_LINEAR),
+      (LINEAR_MIPMAP_LINEAR, Web::LINEAR_MIPMAP_LINEAR),
+      (REPEAT, Web::REPEAT),
+      (CLAMP_TO_EDGE, Web::CLAMP_TO_EDGE),
+      (MIRRORED_REPEAT, Web::MIRRORED_REPEAT),
+      (FRAMEBUFFER, Web::FRAMEBUFFER),
+      (RENDERBUFFER, Web::RENDERBUFFER),
+      (COLOR_ATTACHMENT0, Web::COLOR_ATTACHMENT0),
+      (DEPTH_ATTACHMENT, Web::DEPTH_ATTACHMENT),
+      (STENCIL_ATTACHMENT, Web::STENCIL_ATTACHMENT),
+      (DEPTH_STENCIL_ATTACHMENT, Web::DEPTH_STENCIL_ATTACHMENT),
+      (RGBA4, Web::RGBA4),
+      (RGBA8, Web::RGBA8),
+      (RGB565, Web::RGB565),
+      (DEPTH_COMPONENT16, Web::DEPTH_COMPONENT16),
+      (DEPTH_COMPONENT24, Web::DEPTH_COMPONENT24),
+      (STENCIL_INDEX8, Web::STENCIL_INDEX8),
+      (DEPTH24_STENCIL8, Web::DEPTH24_STENCIL8),
+      (FRAMEBUFFER_COMPLETE, Web::FRAMEBUFFER_COMPLETE),
+      (FRAMEBUFFER_INCOMPLETE_ATTACHMENT, Web::FRAMEBUFFER_INCOMPLETE_ATTACHMENT),
+      (FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT, Web::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT),
+      (FRAMEBUFFER_INCOMPLETE_DIMENSIONS, Web::FRAMEBUFFER_INCOMPLETE_DIMENSIONS),
+      (FRAMEBUFFER_UNSUPPORTED, Web::FRAMEBUFFER_UNSUPPORTED),
+      (FRAMEBUFFER_INCOMPLETE_MULTISAMPLE, Web::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE),
+    ];
+    for (i, (ours, web)) in pairs.iter().enumerate() {
+      assert_eq!(ours, web, "constant {} differs from web_sys", i);
+    }
+  }
+}
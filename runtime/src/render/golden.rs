@@ -0,0 +1,105 @@
+use std::fs::{self, File};
+use std::io::{BufWriter};
+use std::path::{Path, PathBuf};
+
+/**
+ * Compares RGBA8 pixels (top row first) against a checked in
+ * golden image in `tests/goldens`, where no channel of any
+ * pixel may differ from the golden by more than `tolerance`.
+ *
+ * On a mismatch the actual image and a diff image, with each
+ * failing pixel in red over a faded copy of the golden, are
+ * written to `target/golden_diffs` before panicking.
+ *
+ * Running the tests with `UPDATE_GOLDENS=1` rewrites the
+ * goldens with the actual output instead of comparing.
+ */
+pub fn assert_matches_golden(name: &str, width: u32, height: u32, pixels: &[u8], tolerance: u8) {
+  let golden_path = goldens_dir().join(format!("{}.png", name));
+
+  if std::env::var("UPDATE_GOLDENS").is_ok() {
+    fs::create_dir_all(goldens_dir()).unwrap();
+    write_png(&golden_path, width, height, pixels);
+    return;
+  }
+
+  let (golden_width, golden_height, golden) = match read_png(&golden_path) {
+    Some(golden) => golden,
+    None => panic!("missing golden {:?}, run with UPDATE_GOLDENS=1 to create it", golden_path),
+  };
+
+  assert_eq!(
+    (golden_width, golden_height),
+    (width, height),
+    "golden {} has different dimensions",
+    name,
+  );
+  assert_eq!(
+    pixels.len(),
+    golden.len(),
+    "pixels for golden {} aren't {}x{} RGBA8",
+    name,
+    width,
+    height,
+  );
+
+  let mut failures = 0;
+  let mut diff = Vec::with_capacity(golden.len());
+  for (expected, actual) in golden.chunks(4).zip(pixels.chunks(4)) {
+    let failed = expected.iter().zip(actual)
+      .any(|(e, a)| (*e as i16 - *a as i16).unsigned_abs() > tolerance as u16);
+
+    if failed {
+      failures += 1;
+      diff.extend_from_slice(&[255, 0, 0, 255]);
+    } else {
+      let [r, g, b] = [expected[0] / 4, expected[1] / 4, expected[2] / 4];
+      diff.extend_from_slice(&[r, g, b, 255]);
+    }
+  }
+
+  if failures > 0 {
+    let out_dir = diffs_dir();
+    fs::create_dir_all(&out_dir).unwrap();
+    let actual_path = out_dir.join(format!("{}.actual.png", name));
+    let diff_path = out_dir.join(format!("{}.diff.png", name));
+    write_png(&actual_path, width, height, pixels);
+    write_png(&diff_path, width, height, &diff);
+    panic!(
+      "{} pixels of {} differ from golden by more than {}, see {:?}",
+      failures,
+      name,
+      tolerance,
+      diff_path,
+    );
+  }
+}
+
+fn goldens_dir() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("goldens")
+}
+
+fn diffs_dir() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join("golden_diffs")
+}
+
+fn read_png(path: &Path) -> Option<(u32, u32, Vec<u8>)> {
+  let file = File::open(path).ok()?;
+  let mut decoder = png::Decoder::new(file);
+  decoder.set_transformations(png::Transformations::EXPAND);
+  let mut reader = decoder.read_info().ok()?;
+  let mut pixels = vec![0; reader.output_buffer_size()];
+  let info = reader.next_frame(&mut pixels).ok()?;
+  assert_eq!(info.color_type, png::ColorType::Rgba, "golden {:?} must be RGBA", path);
+  pixels.truncate(info.buffer_size());
+  Some((info.width, info.height, pixels))
+}
+
+fn write_png(path: &Path, width: u32, height: u32, pixels: &[u8]) {
+  let file = File::create(path).unwrap();
+  let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  let mut writer = encoder.write_header().unwrap();
+  writer.write_image_data(pixels).unwrap();
+}
@@ -0,0 +1,387 @@
+use super::constants::{ViewPrecision};
+use super::data::{ViewElement};
+use super::mesh::{MeshAttribute};
+
+pub use webpack_demo_derive::Vertex;
+
+/**
+ * Where an attribute is read from in a vertex buffer, where
+ * `stride` & `offset` are in bytes & a stride of 0 means the
+ * attribute is tightly packed.
+ *
+ * An `integer` attribute is read as an `int` or `uint` in the
+ * shader, rather than being converted to a float.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexAttribute<A> {
+  pub key: A,
+  pub size: i32,
+  pub precision: ViewPrecision,
+  pub normalized: bool,
+  pub integer: bool,
+  pub stride: i32,
+  pub offset: i32,
+}
+
+impl<A> VertexAttribute<A> {
+  /**
+   * An attribute read from a field of type `F`, which sets
+   * the size & precision from the type of the field.
+   */
+  pub fn of_field<F>(key: A, normalized: bool, stride: i32, offset: i32) -> Self where F: VertexField {
+    VertexAttribute { key, size: F::SIZE, precision: F::PRECISION, normalized, integer: false, stride, offset }
+  }
+
+  pub fn with_integer(mut self) -> Self {
+    self.integer = true;
+    self
+  }
+}
+
+/**
+ * Every attribute read from one vertex buffer, which is set
+ * in one call with `RenderAPI::set_vertex_layout`.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexLayout<A> {
+  pub attributes: Vec<VertexAttribute<A>>,
+}
+
+impl<A> Default for VertexLayout<A> {
+  fn default() -> Self {
+    VertexLayout { attributes: vec![] }
+  }
+}
+
+impl<A> VertexLayout<A> {
+  pub fn create() -> Self {
+    VertexLayout::default()
+  }
+
+  pub fn with_attribute(mut self, attribute: VertexAttribute<A>) -> Self {
+    self.attributes.push(attribute);
+    self
+  }
+
+  /**
+   * The layout of mesh attributes, which all have the
+   * precision of the view the vertices are uploaded from.
+   */
+  pub fn from_mesh_attributes(attributes: &[MeshAttribute<A>], precision: ViewPrecision) -> Self where A: Copy {
+    let attributes = attributes.iter().map(|attribute| {
+      let MeshAttribute { key, size, normalized, stride, offset } = *attribute;
+      VertexAttribute { key, size, precision, normalized, integer: false, stride, offset }
+    });
+    VertexLayout { attributes: attributes.collect() }
+  }
+
+  /**
+   * Adds an attribute after the attributes already in the
+   * layout, aligned to the size of its precision as WebGL
+   * requires, then updates the stride of every attribute to
+   * include it. The stride is a multiple of the largest
+   * element in the layout, which WebGL also requires.
+   * The layout is expected to only contain attributes added
+   * this way.
+   */
+  pub fn with_interleaved(self, key: A, size: i32, precision: ViewPrecision, normalized: bool) -> Self {
+    self.interleave(key, size, precision, normalized, false)
+  }
+
+  /**
+   * Like `with_interleaved` for an integer attribute.
+   */
+  pub fn with_interleaved_integer(self, key: A, size: i32, precision: ViewPrecision) -> Self {
+    self.interleave(key, size, precision, false, true)
+  }
+
+  fn interleave(mut self, key: A, size: i32, precision: ViewPrecision, normalized: bool, integer: bool) -> Self {
+    let element_size = precision.size() as i32;
+    let end = self.attributes.last().map(|a| a.offset + a.size * a.precision.size() as i32).unwrap_or(0);
+    let offset = (end + element_size - 1) / element_size * element_size;
+    let alignment = self.attributes.iter()
+      .map(|a| a.precision.size() as i32)
+      .fold(element_size, i32::max);
+    let stride = (offset + size * element_size + alignment - 1) / alignment * alignment;
+
+    self.attributes.push(VertexAttribute { key, size, precision, normalized, integer, stride, offset });
+    for attribute in self.attributes.iter_mut() {
+      attribute.stride = stride;
+    }
+    self
+  }
+
+  /**
+   * The size of a vertex in bytes, which is the stride of the
+   * first attribute or its size if it's tightly packed.
+   */
+  pub fn vertex_size(&self) -> usize {
+    match self.attributes.first() {
+      Some(attribute) if attribute.stride > 0 => attribute.stride as usize,
+      Some(attribute) => attribute.size.max(0) as usize * attribute.precision.size(),
+      None => 0,
+    }
+  }
+}
+
+/**
+ * A vertex with a layout, normally implemented with
+ * `#[derive(Vertex)]` on a `#[repr(C)]` struct where each
+ * attribute is a field tagged with its key:
+ *
+ * ```ignore
+ * #[derive(Clone, Copy, Vertex)]
+ * #[repr(C)]
+ * struct ColoredVertex {
+ *   #[vertex(Attributes::Position)]
+ *   position: [f32; 2],
+ *   #[vertex(Attributes::Color, normalized)]
+ *   color: [u8; 4],
+ * }
+ * ```
+ *
+ * Fields without a key are left out of the layout, but are
+ * still counted in the stride.
+ */
+pub trait Vertex: Sized {
+  type Key: Copy;
+
+  fn layout() -> VertexLayout<Self::Key>;
+
+  /**
+   * Writes the fields in the layout into `bytes`, which is
+   * the size of the struct, at the offset of each field.
+   */
+  fn write_le_bytes(&self, bytes: &mut [u8]);
+
+  /**
+   * The vertices as they'd be laid out in memory, to upload
+   * with a `Uint8View`. Padding is left as zeros.
+   */
+  fn to_bytes(vertices: &[Self]) -> Vec<u8> {
+    let size = std::mem::size_of::<Self>();
+    let mut bytes = vec![0; std::mem::size_of_val(vertices)];
+    for (vertex, chunk) in vertices.iter().zip(bytes.chunks_mut(size)) {
+      vertex.write_le_bytes(chunk);
+    }
+    bytes
+  }
+}
+
+/**
+ * A type that can be the field of a vertex, which is either
+ * an element of a view or an array of them.
+ */
+pub trait VertexField {
+  const SIZE: i32;
+  const PRECISION: ViewPrecision;
+
+  fn write_le_bytes(&self, bytes: &mut Vec<u8>);
+}
+
+impl<T> VertexField for T where T: ViewElement {
+  const SIZE: i32 = 1;
+  const PRECISION: ViewPrecision = T::PRECISION;
+
+  fn write_le_bytes(&self, bytes: &mut Vec<u8>) {
+    ViewElement::write_le_bytes(self, bytes);
+  }
+}
+
+impl<T, const N: usize> VertexField for [T; N] where T: ViewElement {
+  const SIZE: i32 = N as i32;
+  const PRECISION: ViewPrecision = T::PRECISION;
+
+  fn write_le_bytes(&self, bytes: &mut Vec<u8>) {
+    for element in self.iter() {
+      element.write_le_bytes(bytes);
+    }
+  }
+}
+
+/**
+ * Used by `#[derive(Vertex)]` to write each field.
+ */
+pub fn write_field<F>(bytes: &mut [u8], offset: usize, field: &F) where F: VertexField {
+  let mut field_bytes = Vec::with_capacity(F::SIZE as usize * F::PRECISION.size());
+  field.write_le_bytes(&mut field_bytes);
+  bytes[offset..offset + field_bytes.len()].copy_from_slice(&field_bytes);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::api::{AttributeKey, RenderAPI};
+  use super::super::recording::{Command, RecordingRenderAPI};
+
+  #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+  enum Attributes {
+    Position,
+    Color,
+  }
+
+  impl AttributeKey for Attributes {
+    fn name(&self) -> &str {
+      match self {
+        Attributes::Position => "position",
+        Attributes::Color => "color",
+      }
+    }
+  }
+
+  #[derive(Clone, Copy, Vertex)]
+  #[repr(C)]
+  struct ColoredVertex {
+    #[vertex(Attributes::Position)]
+    position: [f32; 3],
+    #[vertex(Attributes::Color, normalized)]
+    color: [u8; 4],
+    unused: u16,
+  }
+
+  #[derive(Clone, Copy, Vertex)]
+  #[repr(C)]
+  struct CellVertex {
+    #[vertex(Attributes::Position)]
+    position: [u16; 2],
+    #[vertex(Attributes::Color, integer)]
+    color: [u8; 4],
+  }
+
+  #[derive(Clone, Copy, Vertex)]
+  #[repr(C, align(16))]
+  struct AlignedVertex {
+    #[vertex(Attributes::Position)]
+    position: [f32; 3],
+  }
+
+  #[test]
+  fn derives_layout_from_fields() {
+    let layout = ColoredVertex::layout();
+    assert_eq!(layout.vertex_size(), 20);
+    assert_eq!(layout.attributes, vec![
+      VertexAttribute {
+        key: Attributes::Position,
+        size: 3,
+        precision: ViewPrecision::Float,
+        normalized: false,
+        integer: false,
+        stride: 20,
+        offset: 0,
+      },
+      VertexAttribute {
+        key: Attributes::Color,
+        size: 4,
+        precision: ViewPrecision::UnsignedByte,
+        normalized: true,
+        integer: false,
+        stride: 20,
+        offset: 12,
+      },
+    ]);
+  }
+
+  #[test]
+  fn derives_layout_with_aligned_repr() {
+    let layout = AlignedVertex::layout();
+    assert_eq!(layout.vertex_size(), 16);
+    assert_eq!(layout.attributes[0].stride, 16);
+  }
+
+  #[test]
+  fn interleaved_layout_matches_derived_layout() {
+    let layout = VertexLayout::create()
+      .with_interleaved(Attributes::Position, 3, ViewPrecision::Float, false)
+      .with_interleaved(Attributes::Color, 4, ViewPrecision::UnsignedByte, true);
+    let mut derived = ColoredVertex::layout();
+    // the unused field is only in the stride of the struct.
+    for attribute in derived.attributes.iter_mut() {
+      attribute.stride = 16;
+    }
+    assert_eq!(layout, derived);
+  }
+
+  #[test]
+  fn interleaved_attributes_are_aligned() {
+    let layout = VertexLayout::create()
+      .with_interleaved(Attributes::Color, 3, ViewPrecision::UnsignedByte, true)
+      .with_interleaved(Attributes::Position, 2, ViewPrecision::Float, false);
+    assert_eq!(layout.attributes[1].offset, 4);
+    assert_eq!(layout.vertex_size(), 12);
+
+    let layout = VertexLayout::create()
+      .with_interleaved(Attributes::Position, 3, ViewPrecision::Float, false)
+      .with_interleaved(Attributes::Color, 3, ViewPrecision::UnsignedByte, true);
+    assert_eq!(layout.attributes[1].offset, 12);
+    assert!(layout.attributes.iter().all(|a| a.stride == 16));
+  }
+
+  #[test]
+  fn writes_vertices_with_padding() {
+    let vertex = ColoredVertex { position: [1.0, 2.0, 3.0], color: [4, 5, 6, 7], unused: 9 };
+    let bytes = ColoredVertex::to_bytes(&[vertex, vertex]);
+    let mut expected = vec![];
+    for value in [1.0f32, 2.0, 3.0].iter() {
+      expected.extend_from_slice(&value.to_le_bytes());
+    }
+    expected.extend_from_slice(&[4, 5, 6, 7, 0, 0, 0, 0]);
+    assert_eq!(bytes.len(), 40);
+    assert_eq!(&bytes[..20], &expected[..]);
+    assert_eq!(&bytes[20..], &expected[..]);
+  }
+
+  #[test]
+  fn sets_every_attribute_in_one_call() {
+    let api = RecordingRenderAPI::create(&["position", "color"], &[]);
+    api.set_vertex_layout(&ColoredVertex::layout(), 1).unwrap();
+    assert_eq!(api.take_commands(), vec![
+      Command::VertexAttribPointer {
+        index: 0,
+        size: 3,
+        precision: ViewPrecision::Float,
+        normalized: false,
+        stride: 20,
+        offset: 0,
+      },
+      Command::EnableVertexAttribArray(0),
+      Command::VertexAttribDivisor { index: 0, divisor: 1 },
+      Command::VertexAttribPointer {
+        index: 1,
+        size: 4,
+        precision: ViewPrecision::UnsignedByte,
+        normalized: true,
+        stride: 20,
+        offset: 12,
+      },
+      Command::EnableVertexAttribArray(1),
+      Command::VertexAttribDivisor { index: 1, divisor: 1 },
+    ]);
+  }
+
+  #[test]
+  fn integer_attributes_are_read_as_integers() {
+    let api = RecordingRenderAPI::create(&["position", "color"], &[]);
+    api.set_vertex_layout(&CellVertex::layout(), 0).unwrap();
+    assert_eq!(api.take_commands(), vec![
+      Command::VertexAttribPointer {
+        index: 0,
+        size: 2,
+        precision: ViewPrecision::UnsignedShort,
+        normalized: false,
+        stride: 8,
+        offset: 0,
+      },
+      Command::EnableVertexAttribArray(0),
+      Command::VertexAttribDivisor { index: 0, divisor: 0 },
+      Command::VertexAttribIPointer {
+        index: 1,
+        size: 4,
+        precision: ViewPrecision::UnsignedByte,
+        stride: 8,
+        offset: 4,
+      },
+      Command::EnableVertexAttribArray(1),
+      Command::VertexAttribDivisor { index: 1, divisor: 0 },
+    ]);
+  }
+}
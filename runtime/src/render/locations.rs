@@ -0,0 +1,215 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use super::api::{
+  AttributeIndex,
+  AttributeKey,
+  RenderAPI,
+  RenderApiError,
+  UniformBlockKey,
+  UniformKey,
+};
+use super::constants::{ShaderType};
+
+/**
+ * The locations looked up in a linked program, so each key
+ * is only looked up by name the first time it's used.
+ *
+ * Keys are stored by their name, which each variant of a key
+ * enum has exactly one of. Names that aren't in the program
+ * aren't stored, so looking them up again fails again.
+ */
+#[derive(Debug)]
+pub struct LocationCache<U> {
+  attributes: RefCell<HashMap<String, AttributeIndex>>,
+  uniforms: RefCell<HashMap<String, U>>,
+  uniform_blocks: RefCell<HashMap<String, u32>>,
+}
+
+impl<U> LocationCache<U> where U: Clone {
+  pub fn create() -> Self {
+    LocationCache {
+      attributes: RefCell::new(HashMap::new()),
+      uniforms: RefCell::new(HashMap::new()),
+      uniform_blocks: RefCell::new(HashMap::new()),
+    }
+  }
+
+  pub fn attribute<F>(&self, name: &str, lookup: F) -> Result<AttributeIndex, RenderApiError>
+      where F: FnOnce() -> Option<AttributeIndex> {
+    cached(&self.attributes, name, lookup)
+      .ok_or_else(|| RenderApiError::InvalidAttributeName(name.to_string()))
+  }
+
+  pub fn uniform<F>(&self, name: &str, lookup: F) -> Result<U, RenderApiError> where F: FnOnce() -> Option<U> {
+    cached(&self.uniforms, name, lookup)
+      .ok_or_else(|| RenderApiError::InvalidUniformName(name.to_string()))
+  }
+
+  pub fn uniform_block<F>(&self, name: &str, lookup: F) -> Result<u32, RenderApiError> where F: FnOnce() -> Option<u32> {
+    cached(&self.uniform_blocks, name, lookup)
+      .ok_or_else(|| RenderApiError::InvalidUniformBlockName(name.to_string()))
+  }
+}
+
+fn cached<T, F>(cache: &RefCell<HashMap<String, T>>, name: &str, lookup: F) -> Option<T>
+    where T: Clone, F: FnOnce() -> Option<T> {
+  if let Some(location) = cache.borrow().get(name) {
+    return Some(location.clone());
+  }
+
+  let location = lookup()?;
+  cache.borrow_mut().insert(name.to_string(), location.clone());
+  Some(location)
+}
+
+/**
+ * The attributes, uniforms & uniform blocks a program is
+ * expected to have, which are checked once it's linked
+ * rather than on the first draw that uses them.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProgramKeys {
+  attributes: Vec<DeclaredKey>,
+  uniforms: Vec<DeclaredKey>,
+  uniform_blocks: Vec<String>,
+}
+
+/**
+ * The name of a key, & the type it's declared as if it has
+ * one.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeclaredKey {
+  pub name: String,
+  pub kind: Option<ShaderType>,
+}
+
+impl ProgramKeys {
+  pub fn create() -> Self {
+    ProgramKeys::default()
+  }
+
+  pub fn with_attributes<A>(mut self, keys: &[A]) -> Self where A: AttributeKey {
+    self.attributes.extend(keys.iter().map(|key| {
+      DeclaredKey { name: key.name().to_string(), kind: key.shader_type() }
+    }));
+    self
+  }
+
+  pub fn with_uniforms<U>(mut self, keys: &[U]) -> Self where U: UniformKey {
+    self.uniforms.extend(keys.iter().map(|key| {
+      DeclaredKey { name: key.name().to_string(), kind: key.shader_type() }
+    }));
+    self
+  }
+
+  pub fn with_uniform_blocks<B>(mut self, keys: &[B]) -> Self where B: UniformBlockKey {
+    self.uniform_blocks.extend(keys.iter().map(|key| key.name().to_string()));
+    self
+  }
+
+  pub fn attributes(&self) -> &[DeclaredKey] {
+    &self.attributes
+  }
+
+  pub fn uniforms(&self) -> &[DeclaredKey] {
+    &self.uniforms
+  }
+
+  /**
+   * Looks up every key, failing on the first one the program
+   * doesn't have. For APIs with a `LocationCache` this also
+   * means no key is looked up by name after this.
+   */
+  pub fn resolve<R>(&self, api: &R) -> Result<(), RenderApiError> where R: RenderAPI {
+    for key in self.attributes.iter() {
+      api.get_attribute(KeyName(&key.name))?;
+    }
+    for key in self.uniforms.iter() {
+      api.get_uniform(KeyName(&key.name))?;
+    }
+    for name in self.uniform_blocks.iter() {
+      api.get_uniform_block(KeyName(name))?;
+    }
+    Ok(())
+  }
+}
+
+struct KeyName<'a>(&'a str);
+
+impl AttributeKey for KeyName<'_> {
+  fn name(&self) -> &str {
+    self.0
+  }
+}
+
+impl UniformKey for KeyName<'_> {
+  fn name(&self) -> &str {
+    self.0
+  }
+}
+
+impl UniformBlockKey for KeyName<'_> {
+  fn name(&self) -> &str {
+    self.0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::Cell;
+  use super::*;
+  use super::super::recording::{RecordingRenderAPI};
+
+  #[derive(Clone, Copy)]
+  enum Attributes { Position, Normal }
+
+  impl AttributeKey for Attributes {
+    fn name(&self) -> &str {
+      match self {
+        Attributes::Position => "position",
+        Attributes::Normal => "normal",
+      }
+    }
+  }
+
+  #[test]
+  fn looks_up_each_name_once() {
+    let cache: LocationCache<usize> = LocationCache::create();
+    let lookups = Cell::new(0);
+    let lookup = || { lookups.set(lookups.get() + 1); Some(3) };
+
+    assert_eq!(cache.uniform("color", lookup).unwrap(), 3);
+    assert_eq!(cache.uniform("color", lookup).unwrap(), 3);
+    assert_eq!(lookups.get(), 1);
+  }
+
+  #[test]
+  fn missing_names_are_not_cached() {
+    let cache: LocationCache<usize> = LocationCache::create();
+    let lookups = Cell::new(0);
+    let lookup = || { lookups.set(lookups.get() + 1); None };
+
+    assert!(cache.attribute("normal", lookup).is_err());
+    assert!(cache.attribute("normal", lookup).is_err());
+    assert_eq!(lookups.get(), 2);
+  }
+
+  #[test]
+  fn resolve_fails_on_the_first_missing_key() {
+    let api = RecordingRenderAPI::create(&["position"], &[]);
+    let keys = ProgramKeys::create().with_attributes(&[Attributes::Position, Attributes::Normal]);
+
+    match keys.resolve(&api) {
+      Err(RenderApiError::InvalidAttributeName(name)) => assert_eq!(name, "normal"),
+      other => panic!("expected invalid attribute name, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn resolve_passes_when_every_key_is_present() {
+    let api = RecordingRenderAPI::create(&["position", "normal"], &[]);
+    let keys = ProgramKeys::create().with_attributes(&[Attributes::Position, Attributes::Normal]);
+    assert!(keys.resolve(&api).is_ok());
+  }
+}
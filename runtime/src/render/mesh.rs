@@ -0,0 +1,465 @@
+use super::api::{IntoAttributeIndex, RenderAPI, RenderApiError};
+use super::buffer::{DynamicBuffer};
+use super::constants::{BufferKind, DrawArrayKind, DrawKind, IndexKind};
+use super::data::{IndexView, View};
+use super::layout::{VertexLayout};
+
+/**
+ * Where an attribute is read from in the vertex buffer of a
+ * mesh, the precision is taken from the view the vertices
+ * are uploaded from.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct MeshAttribute<A> {
+  pub key: A,
+  pub size: i32,
+  pub normalized: bool,
+  pub stride: i32,
+  pub offset: i32,
+}
+
+impl<A> MeshAttribute<A> {
+  /**
+   * An attribute of `size` components that is the only thing
+   * in the vertex buffer.
+   */
+  pub fn packed(key: A, size: i32) -> Self {
+    MeshAttribute { key, size, normalized: false, stride: 0, offset: 0 }
+  }
+}
+
+/**
+ * A buffer of per instance attributes, where the number of
+ * instances is worked out like the vertices of a `Mesh`.
+ */
+#[derive(Debug)]
+pub struct InstanceBuffer<R: RenderAPI> {
+  buffer: DynamicBuffer<R>,
+  instance_size: usize,
+  count: i32,
+}
+
+impl<R> InstanceBuffer<R> where R: RenderAPI {
+  pub fn count(&self) -> i32 {
+    self.count
+  }
+}
+
+/**
+ * A vertex array along with the buffers it reads from, so the
+ * attributes of a mesh are specified once when it's created
+ * instead of every time it's drawn.
+ *
+ * Without indices the number of vertices drawn is worked out
+ * from the stride of the first attribute, so every attribute
+ * is expected to be interleaved in the one vertex buffer.
+ * Once a mesh has instances it's always drawn instanced.
+ *
+ * Vertices & instances are updated in place when they fit in
+ * the buffers they're replacing, & can be partly updated
+ * with `write_vertices` & `write_instances`.
+ */
+#[derive(Debug)]
+pub struct Mesh<R: RenderAPI> {
+  vertex_array: R::VertexArray,
+  vertex_buffer: DynamicBuffer<R>,
+  // only bound once in `create_indexed`, but owned here so it
+  // lives as long as the vertex array that refers to it.
+  index_buffer: Option<(R::Buffer, IndexKind)>,
+  instances: Option<InstanceBuffer<R>>,
+  mode: DrawArrayKind,
+  vertex_size: usize,
+  count: i32,
+}
+
+impl<R> Mesh<R> where R: RenderAPI {
+  pub fn create<A, V>(
+      context: &R,
+      mode: DrawArrayKind,
+      vertices: &V,
+      attributes: &[MeshAttribute<A>],
+  ) -> Result<Self, RenderApiError> where A: IntoAttributeIndex + Copy, V: View {
+    let layout = VertexLayout::from_mesh_attributes(attributes, vertices.get_precision());
+    Mesh::create_with_layout(context, mode, vertices, &layout)
+  }
+
+  /**
+   * Creates a mesh from vertices with the layout, which can
+   * mix precisions unlike the attributes of `create`.
+   */
+  pub fn create_with_layout<A, V>(
+      context: &R,
+      mode: DrawArrayKind,
+      vertices: &V,
+      layout: &VertexLayout<A>,
+  ) -> Result<Self, RenderApiError> where A: IntoAttributeIndex + Copy, V: View {
+    let vertex_array = context.create_vertex_array()?;
+    let mut vertex_buffer = DynamicBuffer::create(context, BufferKind::ArrayBuffer, DrawKind::StaticDraw)?;
+
+    context.bind_vertex_array(Some(&vertex_array));
+    let result = vertex_buffer.upload(context, vertices)
+      .and_then(|_| context.set_vertex_layout(layout, 0));
+    context.bind_vertex_array(None);
+    result?;
+
+    let vertex_size = layout.vertex_size();
+    let count = vertex_count(&vertex_buffer, vertex_size);
+    Ok(Mesh { vertex_array, vertex_buffer, index_buffer: None, instances: None, mode, vertex_size, count })
+  }
+
+  pub fn create_indexed<A, V, I>(
+      context: &R,
+      mode: DrawArrayKind,
+      vertices: &V,
+      indices: &I,
+      attributes: &[MeshAttribute<A>],
+  ) -> Result<Self, RenderApiError> where A: IntoAttributeIndex + Copy, V: View, I: IndexView {
+    let layout = VertexLayout::from_mesh_attributes(attributes, vertices.get_precision());
+    Mesh::create_indexed_with_layout(context, mode, vertices, indices, &layout)
+  }
+
+  pub fn create_indexed_with_layout<A, V, I>(
+      context: &R,
+      mode: DrawArrayKind,
+      vertices: &V,
+      indices: &I,
+      layout: &VertexLayout<A>,
+  ) -> Result<Self, RenderApiError> where A: IntoAttributeIndex + Copy, V: View, I: IndexView {
+    let vertex_array = context.create_vertex_array()?;
+    let mut vertex_buffer = DynamicBuffer::create(context, BufferKind::ArrayBuffer, DrawKind::StaticDraw)?;
+    let index_buffer = context.create_buffer(BufferKind::ElementBuffer)?;
+
+    context.bind_vertex_array(Some(&vertex_array));
+    let result = vertex_buffer.upload(context, vertices)
+      .and_then(|_| context.buffer_data(&index_buffer, indices, DrawKind::StaticDraw))
+      .and_then(|_| context.set_vertex_layout(layout, 0));
+    context.bind_vertex_array(None);
+    result?;
+
+    Ok(Mesh {
+      vertex_array,
+      vertex_buffer,
+      index_buffer: Some((index_buffer, indices.get_index_kind())),
+      instances: None,
+      mode,
+      vertex_size: layout.vertex_size(),
+      count: indices.length() as i32,
+    })
+  }
+
+  /**
+   * Replaces the vertices, which must have the same layout as
+   * the vertices the mesh was created with.
+   */
+  pub fn update_vertices<V>(&mut self, context: &R, vertices: &V) -> Result<(), RenderApiError> where V: View {
+    self.vertex_buffer.upload(context, vertices)?;
+    if self.index_buffer.is_none() {
+      self.count = vertex_count(&self.vertex_buffer, self.vertex_size);
+    }
+    Ok(())
+  }
+
+  /**
+   * Replaces some of the vertices from `offset` in bytes,
+   * which must fit in the vertex buffer.
+   */
+  pub fn write_vertices<V>(&mut self, context: &R, offset: usize, vertices: &V) -> Result<(), RenderApiError> where V: View {
+    self.vertex_buffer.write(context, offset, vertices)?;
+    if self.index_buffer.is_none() {
+      self.count = vertex_count(&self.vertex_buffer, self.vertex_size);
+    }
+    Ok(())
+  }
+
+  /**
+   * Uploads the instances to a new instance buffer, where the
+   * attributes advance once per instance instead of once per
+   * vertex, replacing any instances the mesh already had.
+   */
+  pub fn set_instances<A, V>(
+      &mut self,
+      context: &R,
+      instances: &V,
+      attributes: &[MeshAttribute<A>],
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex + Copy, V: View {
+    let layout = VertexLayout::from_mesh_attributes(attributes, instances.get_precision());
+    self.set_instances_with_layout(context, instances, &layout)
+  }
+
+  pub fn set_instances_with_layout<A, V>(
+      &mut self,
+      context: &R,
+      instances: &V,
+      layout: &VertexLayout<A>,
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex + Copy, V: View {
+    let mut buffer = DynamicBuffer::create(context, BufferKind::ArrayBuffer, DrawKind::StaticDraw)?;
+
+    context.bind_vertex_array(Some(&self.vertex_array));
+    let result = buffer.upload(context, instances)
+      .and_then(|_| context.set_vertex_layout(layout, 1));
+    context.bind_vertex_array(None);
+    result?;
+
+    let instance_size = layout.vertex_size();
+    let count = vertex_count(&buffer, instance_size);
+    self.instances = Some(InstanceBuffer { buffer, instance_size, count });
+    Ok(())
+  }
+
+  /**
+   * Replaces the instances, which must have the same layout as
+   * the instances set with `set_instances`.
+   */
+  pub fn update_instances<V>(&mut self, context: &R, instances: &V) -> Result<(), RenderApiError> where V: View {
+    if let Some(buffer) = self.instances.as_mut() {
+      buffer.buffer.upload(context, instances)?;
+      buffer.count = vertex_count(&buffer.buffer, buffer.instance_size);
+    }
+    Ok(())
+  }
+
+  /**
+   * Replaces some of the instances from `offset` in bytes,
+   * which must fit in the instance buffer. Does nothing when
+   * the mesh has no instances.
+   */
+  pub fn write_instances<V>(&mut self, context: &R, offset: usize, instances: &V) -> Result<(), RenderApiError> where V: View {
+    if let Some(buffer) = self.instances.as_mut() {
+      buffer.buffer.write(context, offset, instances)?;
+      buffer.count = vertex_count(&buffer.buffer, buffer.instance_size);
+    }
+    Ok(())
+  }
+
+  /**
+   * Binds the vertex array to draw the mesh, then unbinds it
+   * so the mesh can't be changed by later buffer bindings.
+   */
+  pub fn draw(&self, context: &R) {
+    context.bind_vertex_array(Some(&self.vertex_array));
+    let instance_count = self.instances.as_ref().map(|i| i.count);
+    match (&self.index_buffer, instance_count) {
+      (Some((_, kind)), None) => context.draw_elements(self.mode, self.count, *kind, 0),
+      (Some((_, kind)), Some(n)) => context.draw_elements_instanced(self.mode, self.count, *kind, 0, n),
+      (None, None) => context.draw_arrays(self.mode, 0, self.count),
+      (None, Some(n)) => context.draw_arrays_instanced(self.mode, 0, self.count, n),
+    }
+    context.bind_vertex_array(None);
+  }
+
+  /**
+   * The number of indices, or vertices without indices.
+   */
+  pub fn count(&self) -> i32 {
+    self.count
+  }
+
+  pub fn instances(&self) -> Option<&InstanceBuffer<R>> {
+    self.instances.as_ref()
+  }
+}
+
+fn vertex_count<R>(buffer: &DynamicBuffer<R>, vertex_size: usize) -> i32 where R: RenderAPI {
+  match vertex_size {
+    0 => 0,
+    size => (buffer.length() / size) as i32,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::api::{AttributeKey, UniformKey};
+  use super::super::constants::{ViewPrecision};
+  use super::super::data::{Float32View, Uint16View};
+  use super::super::recording::{
+    Command,
+    RecordingBuffer,
+    RecordingRenderAPI,
+    RecordingVertexArray,
+  };
+  use super::super::software::{GridShader, SoftwareRenderAPI};
+
+  #[derive(Clone, Copy, Debug)]
+  struct Key(&'static str);
+
+  impl AttributeKey for Key {
+    fn name(&self) -> &str { self.0 }
+  }
+
+  impl UniformKey for Key {
+    fn name(&self) -> &str { self.0 }
+  }
+
+  fn interleaved(key: &'static str) -> MeshAttribute<Key> {
+    MeshAttribute { key: Key(key), size: 3, normalized: false, stride: 20, offset: 0 }
+  }
+
+  #[test]
+  fn create_indexed_specifies_attributes_inside_vertex_array() {
+    let api = RecordingRenderAPI::create(&["position"], &[]);
+    let vertices = Float32View::create(&[0.0; 12]).unwrap();
+    let indices = Uint16View::create(&[0, 1, 2, 3, 2, 1]).unwrap();
+    let position = MeshAttribute::packed(Key("position"), 3);
+    let mesh = Mesh::create_indexed(&api, DrawArrayKind::Triangles, &vertices, &indices, &[position]).unwrap();
+    let vertex_array = RecordingVertexArray(0);
+    let vertex_buffer = RecordingBuffer { id: 0, kind: BufferKind::ArrayBuffer };
+    let index_buffer = RecordingBuffer { id: 1, kind: BufferKind::ElementBuffer };
+
+    assert_eq!(mesh.count(), 6);
+    assert_eq!(api.take_commands(), vec![
+      Command::CreateVertexArray(vertex_array),
+      Command::CreateBuffer(vertex_buffer),
+      Command::CreateBuffer(index_buffer),
+      Command::BindVertexArray(Some(vertex_array)),
+      Command::BufferData {
+        buffer: vertex_buffer,
+        precision: ViewPrecision::Float,
+        data: vertices.to_bytes(),
+        draw_kind: DrawKind::StaticDraw,
+      },
+      Command::BufferData {
+        buffer: index_buffer,
+        precision: ViewPrecision::UnsignedShort,
+        data: indices.to_bytes(),
+        draw_kind: DrawKind::StaticDraw,
+      },
+      Command::VertexAttribPointer {
+        index: 0,
+        size: 3,
+        precision: ViewPrecision::Float,
+        normalized: false,
+        stride: 0,
+        offset: 0,
+      },
+      Command::EnableVertexAttribArray(0),
+      Command::VertexAttribDivisor { index: 0, divisor: 0 },
+      Command::BindVertexArray(None),
+    ]);
+  }
+
+  #[test]
+  fn create_unbinds_vertex_array_on_error() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    let vertices = Float32View::create(&[0.0; 9]).unwrap();
+    let position = MeshAttribute::packed(Key("position"), 3);
+
+    match Mesh::create(&api, DrawArrayKind::Triangles, &vertices, &[position]) {
+      Err(RenderApiError::InvalidAttributeName(name)) => assert_eq!(name, "position"),
+      other => panic!("expected invalid attribute name, got {:?}", other.map(|_| ())),
+    }
+    assert_eq!(api.commands().last(), Some(&Command::BindVertexArray(None)));
+  }
+
+  #[test]
+  fn draw_binds_own_vertex_array() {
+    let api = RecordingRenderAPI::create(&["position"], &[]);
+    let vertices = Float32View::create(&[0.0; 25]).unwrap();
+    let mesh = Mesh::create(&api, DrawArrayKind::TriangleStrip, &vertices, &[interleaved("position")]).unwrap();
+    api.take_commands();
+    mesh.draw(&api);
+
+    assert_eq!(api.take_commands(), vec![
+      Command::BindVertexArray(Some(RecordingVertexArray(0))),
+      Command::DrawArrays { mode: DrawArrayKind::TriangleStrip, first: 0, count: 5 },
+      Command::BindVertexArray(None),
+    ]);
+  }
+
+  #[test]
+  fn draws_one_instance_per_instance_vertex() {
+    let api = RecordingRenderAPI::create(&["position", "offset"], &[]);
+    let vertices = Float32View::create(&[0.0; 9]).unwrap();
+    let position = MeshAttribute::packed(Key("position"), 3);
+    let mut mesh = Mesh::create(&api, DrawArrayKind::Triangles, &vertices, &[position]).unwrap();
+    let offsets = Float32View::create(&[0.0; 8]).unwrap();
+    mesh.set_instances(&api, &offsets, &[MeshAttribute::packed(Key("offset"), 2)]).unwrap();
+    assert_eq!(&api.take_commands()[12..], &[
+      Command::EnableVertexAttribArray(1),
+      Command::VertexAttribDivisor { index: 1, divisor: 1 },
+      Command::BindVertexArray(None),
+    ]);
+
+    mesh.update_instances(&api, &Float32View::create(&[0.0; 6]).unwrap()).unwrap();
+    api.take_commands();
+    mesh.draw(&api);
+    assert_eq!(mesh.instances().map(|i| i.count()), Some(3));
+    assert_eq!(api.take_commands()[1], Command::DrawArraysInstanced {
+      mode: DrawArrayKind::Triangles,
+      first: 0,
+      count: 3,
+      instance_count: 3,
+    });
+  }
+
+  #[test]
+  fn write_instances_updates_in_place() {
+    let api = RecordingRenderAPI::create(&["position", "offset"], &[]);
+    let vertices = Float32View::create(&[0.0; 9]).unwrap();
+    let position = MeshAttribute::packed(Key("position"), 3);
+    let mut mesh = Mesh::create(&api, DrawArrayKind::Triangles, &vertices, &[position]).unwrap();
+    let offsets = Float32View::create(&[0.0; 8]).unwrap();
+    mesh.set_instances(&api, &offsets, &[MeshAttribute::packed(Key("offset"), 2)]).unwrap();
+    api.take_commands();
+
+    let offset = Float32View::create(&[1.0, 2.0]).unwrap();
+    mesh.write_instances(&api, 8, &offset).unwrap();
+    assert!(mesh.write_instances(&api, 32, &offset).is_err());
+    assert_eq!(mesh.instances().map(|i| i.count()), Some(4));
+    assert_eq!(api.take_commands(), vec![
+      Command::BufferSubData {
+        buffer: RecordingBuffer { id: 1, kind: BufferKind::ArrayBuffer },
+        offset: 8,
+        precision: ViewPrecision::Float,
+        data: offset.to_bytes(),
+      },
+    ]);
+  }
+
+  #[test]
+  fn update_vertices_recounts_vertices() {
+    let api = RecordingRenderAPI::create(&["position"], &[]);
+    let vertices = Float32View::create(&[0.0; 10]).unwrap();
+    let mut mesh = Mesh::create(&api, DrawArrayKind::Points, &vertices, &[interleaved("position")]).unwrap();
+    assert_eq!(mesh.count(), 2);
+
+    mesh.update_vertices(&api, &Float32View::create(&[0.0; 30]).unwrap()).unwrap();
+    assert_eq!(mesh.count(), 6);
+  }
+
+  #[test]
+  fn draws_meshes_with_different_layouts() {
+    let api = SoftwareRenderAPI::create(GridShader, 4, 2);
+    api.uniform2f(Key("resolution"), 4.0, 2.0).unwrap();
+    api.uniform2f(Key("origin"), 0.0, 0.0).unwrap();
+    api.uniform1f(Key("spacing"), 0.0).unwrap();
+    api.uniform1f(Key("size"), 1.0).unwrap();
+
+    // the left half of the canvas, with 2 unused floats after
+    // each position, then the right half tightly packed.
+    let left = Float32View::create(&[
+      0.0, 0.0, 0.0, 9.0, 9.0,
+      2.0, 0.0, 0.0, 9.0, 9.0,
+      0.0, 2.0, 0.0, 9.0, 9.0,
+      2.0, 2.0, 0.0, 9.0, 9.0,
+    ]).unwrap();
+    let right = Float32View::create(&[
+      2.0, 0.0, 0.0,
+      4.0, 0.0, 0.0,
+      2.0, 2.0, 0.0,
+      4.0, 2.0, 0.0,
+    ]).unwrap();
+    let position = MeshAttribute::packed(Key("position"), 3);
+    let strip = DrawArrayKind::TriangleStrip;
+    let left = Mesh::create(&api, strip, &left, &[interleaved("position")]).unwrap();
+    let right = Mesh::create(&api, strip, &right, &[position]).unwrap();
+
+    left.draw(&api);
+    right.draw(&api);
+    for x in 0..4 {
+      for y in 0..2 {
+        assert_ne!(api.pixel(x, y), [0, 0, 0, 0], "pixel ({}, {}) wasn't drawn", x, y);
+      }
+    }
+  }
+}
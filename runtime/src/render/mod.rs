@@ -1,5 +1,32 @@
 pub mod api;
+pub mod buffer;
 pub mod builder;
+pub mod cache;
 pub mod constants;
 pub mod data;
+pub mod diagnostics;
+#[cfg(all(test, feature = "egl"))]
+pub mod egl;
+#[cfg(feature = "web")]
 pub mod drawwable;
+pub mod gl;
+pub mod layout;
+pub mod locations;
+#[cfg(test)]
+pub mod golden;
+pub mod mesh;
+#[cfg(feature = "native")]
+pub mod native;
+pub mod pipeline;
+pub mod preprocessor;
+pub mod recording;
+pub mod reflection;
+pub mod software;
+pub mod std140;
+pub mod stream;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(feature = "validation")]
+pub mod validation;
+#[cfg(feature = "web")]
+pub mod web;
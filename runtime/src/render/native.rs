@@ -0,0 +1,667 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use glow::{HasContext, PixelPackData, PixelUnpackData};
+use super::api::{
+  check_texture_data,
+  AttributeIndex,
+  AttributeKey,
+  IntoAttributeIndex,
+  IntoUniformBlockIndex,
+  IntoUniformIndex,
+  RenderAPI,
+  RenderApiError,
+  UniformBlockKey,
+  UniformKey,
+  UniformValue,
+};
+use super::builder::{BuildError};
+use super::constants::{
+  BufferKind,
+  ShaderStage,
+  ShaderType,
+  BlendFuncFactor,
+  Capability,
+  ClearMasks,
+  DrawArrayKind,
+  DrawKind,
+  FramebufferAttachment,
+  FramebufferStatus,
+  IndexKind,
+  RenderbufferFormat,
+  TextureParameter,
+  TextureUnit,
+  ViewPrecision,
+  HasBufferKind,
+  HasBlendFuncFactor,
+  HasViewPrecision,
+  HasCapability,
+  HasClearMaskKind,
+  HasCompareFunc,
+  HasCullFace,
+  HasDrawArrayKind,
+  HasDrawKind,
+  HasFramebufferAttachment,
+  HasIndexKind,
+  HasRenderbufferFormat,
+  HasShaderStage,
+  HasStencilOp,
+  HasTextureParameter,
+  HasTextureUnit,
+};
+use super::data::{Uint8View, View};
+use super::diagnostics::{CompileError};
+use super::gl;
+use super::locations::{LocationCache, ProgramKeys};
+use super::pipeline::{ColorMask, DepthState, PipelineState, ScissorRect, StencilState};
+use super::reflection::{ActiveVariable, ProgramReflection};
+
+/**
+ * The native OpenGL binding for the interface, on top of
+ * any `glow` context, such as one created with glutin, EGL
+ * (surfaceless or pbuffer) or OSMesa. This allows the same
+ * `RenderLoop` to be used in native binaries & tests.
+ *
+ * The shaders are written for WebGL 2, so the context needs
+ * to either be OpenGL ES 3, or desktop OpenGL that accepts
+ * `#version 300 es` (such as mesa's llvmpipe).
+ *
+ * The context has to be current on the calling thread for as
+ * long as this is used, as every call goes straight to GL.
+ */
+#[derive(Debug)]
+pub struct NativeRenderAPI<G: HasContext> {
+  gl: G,
+  program: RefCell<NativeProgram<G::Program, G::UniformLocation>>,
+  default_vertex_array: Option<G::VertexArray>,
+}
+
+impl<G> NativeRenderAPI<G> where G: HasContext {
+  /**
+   * Expects `program` to already be in use.
+   */
+  pub fn create(gl: G, program: G::Program) -> Self {
+    let program = RefCell::new(NativeProgram::create(&gl, program));
+    NativeRenderAPI { gl, program, default_vertex_array: None }
+  }
+
+  /**
+   * Compiles & links the shaders into a program then uses
+   * it, the native equivalent of `RenderBuilder`.
+   *
+   * A vertex array is also created & bound, as unlike WebGL 2
+   * a core profile context has no default vertex array, which
+   * is what binding `None` as the vertex array goes back to.
+   */
+  pub fn build(gl: G, vert_source: &str, frag_source: &str) -> Result<Self, BuildError> {
+    let program = link_program(&gl, vert_source, frag_source)?;
+
+    // Safety: the context is current per the docs on the type.
+    unsafe {
+      let vertex_array = gl.create_vertex_array().map_err(|_| BuildError::CannotCreateVertexArray)?;
+      gl.bind_vertex_array(Some(vertex_array));
+      gl.use_program(Some(program));
+      let program = RefCell::new(NativeProgram::create(&gl, program));
+      Ok(NativeRenderAPI {
+        gl,
+        program,
+        default_vertex_array: Some(vertex_array),
+      })
+    }
+  }
+
+  /**
+   * The program that's in use.
+   */
+  pub fn program(&self) -> NativeProgram<G::Program, G::UniformLocation> {
+    self.program.borrow().clone()
+  }
+
+  /**
+   * Compiles & links another program from the same context,
+   * which fails if it's missing any of `keys`. The program
+   * in use doesn't change.
+   */
+  pub fn build_program(
+      &self,
+      vert_source: &str,
+      frag_source: &str,
+      keys: &ProgramKeys,
+  ) -> Result<NativeProgram<G::Program, G::UniformLocation>, BuildError> {
+    let program = NativeProgram::create(&self.gl, link_program(&self.gl, vert_source, frag_source)?);
+    program.reflection().check_keys(keys).map_err(BuildError::MismatchedProgramKeys)?;
+
+    // lookups don't need the program to be in use, so it's
+    // only swapped in here while the keys are resolved.
+    let previous = self.program.replace(program.clone());
+    let resolved = keys.resolve(self);
+    self.program.replace(previous);
+    resolved.map_err(BuildError::MissingProgramKey)?;
+    Ok(program)
+  }
+
+  /**
+   * The underlying context, for anything the interface
+   * doesn't cover.
+   */
+  pub fn context(&self) -> &G {
+    &self.gl
+  }
+
+  fn set_capability(&self, capability: Capability, enabled: bool) {
+    // Safety: the context is current per the docs on the type.
+    unsafe {
+      if enabled {
+        self.gl.enable(capability.capability_constant());
+      } else {
+        self.gl.disable(capability.capability_constant());
+      }
+    }
+  }
+
+  /**
+   * Binds the texture to the active unit for the edit, then
+   * binds back what was there so the unit draws with the same
+   * texture as before.
+   */
+  fn edit_texture<T>(&self, texture: G::Texture, edit: impl FnOnce() -> T) -> T {
+    // Safety: the context is current per the docs on the type.
+    unsafe {
+      let previous = self.gl.get_parameter_texture(gl::TEXTURE_BINDING_2D);
+      self.gl.bind_texture(gl::TEXTURE_2D, Some(texture));
+      let result = edit();
+      self.gl.bind_texture(gl::TEXTURE_2D, previous);
+      result
+    }
+  }
+}
+
+fn link_program<G>(gl: &G, vert_source: &str, frag_source: &str) -> Result<G::Program, BuildError> where G: HasContext {
+  let vert_shader = compile_shader(gl, ShaderStage::Vertex, vert_source)?;
+  let frag_shader = compile_shader(gl, ShaderStage::Fragment, frag_source)?;
+
+  // Safety: the context is current per the docs on `NativeRenderAPI`.
+  unsafe {
+    let program = gl.create_program().map_err(|_| BuildError::CannotCreateProgram)?;
+    gl.attach_shader(program, vert_shader);
+    gl.attach_shader(program, frag_shader);
+    gl.link_program(program);
+
+    if gl.get_program_link_status(program) {
+      Ok(program)
+    } else {
+      Err(BuildError::FailedToLinkProgram(Some(gl.get_program_info_log(program))))
+    }
+  }
+}
+
+fn compile_shader<G>(gl: &G, stage: ShaderStage, source: &str) -> Result<G::Shader, BuildError> where G: HasContext {
+  // Safety: the context is current per the docs on `NativeRenderAPI`.
+  unsafe {
+    let shader = gl.create_shader(stage.shader_stage_constant()).map_err(|_| BuildError::CannotCreateShader)?;
+    gl.shader_source(shader, source);
+    gl.compile_shader(shader);
+
+    if gl.get_shader_compile_status(shader) {
+      Ok(shader)
+    } else {
+      let log = Some(gl.get_shader_info_log(shader));
+      Err(BuildError::FailedToCompileShader(CompileError::create(stage, source, log)))
+    }
+  }
+}
+
+// Safety: every call below is made on a context that's
+// current, per the docs on `NativeRenderAPI`, and only with
+// objects created by that same context.
+impl<G> RenderAPI for NativeRenderAPI<G> where G: HasContext {
+  type Buffer = NativeRenderBuffer<G::Buffer>;
+  type Program = NativeProgram<G::Program, G::UniformLocation>;
+  type UniformIndex = G::UniformLocation;
+  type UniformBlockIndex = u32;
+  type Texture = G::Texture;
+  type Framebuffer = G::Framebuffer;
+  type Renderbuffer = G::Renderbuffer;
+  type VertexArray = G::VertexArray;
+
+  fn bind_buffer(&self, buffer: &Self::Buffer) {
+    unsafe { self.gl.bind_buffer(buffer.buffer_kind_constant(), Some(buffer.internal)) }
+  }
+
+  fn bind_buffer_base(&self, buffer: &Self::Buffer, index: u32) {
+    let kind = buffer.buffer_kind_constant();
+    unsafe { self.gl.bind_buffer_base(kind, index, Some(buffer.internal)) }
+  }
+
+  fn bind_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>) {
+    unsafe { self.gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer.copied()) }
+  }
+
+  fn bind_texture(&self, unit: TextureUnit, texture: &Self::Texture) {
+    unsafe {
+      self.gl.active_texture(unit.texture_unit_constant());
+      self.gl.bind_texture(gl::TEXTURE_2D, Some(*texture));
+    }
+  }
+
+  fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>) {
+    let vertex_array = vertex_array.copied().or(self.default_vertex_array);
+    unsafe { self.gl.bind_vertex_array(vertex_array) }
+  }
+
+  fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    unsafe { self.gl.blend_color(red, green, blue, alpha) }
+  }
+
+  fn blend_func(&self, src: BlendFuncFactor, dst: BlendFuncFactor) {
+    unsafe { self.gl.blend_func(src.blend_func_factor_constant(), dst.blend_func_factor_constant()) }
+  }
+
+  fn buffer_data<V>(
+      &self,
+      buffer: &Self::Buffer,
+      view: &V,
+      draw_kind: DrawKind,
+  ) -> Result<(), RenderApiError> where V: View {
+    let kind = buffer.buffer_kind_constant();
+    let draw = draw_kind.draw_kind_constant();
+    unsafe {
+      self.gl.bind_buffer(kind, Some(buffer.internal));
+      self.gl.buffer_data_u8_slice(kind, &view.to_bytes(), draw);
+    }
+    Ok(())
+  }
+
+  fn buffer_data_with_size(&self, buffer: &Self::Buffer, size: usize, draw_kind: DrawKind) {
+    let kind = buffer.buffer_kind_constant();
+    unsafe {
+      self.gl.bind_buffer(kind, Some(buffer.internal));
+      self.gl.buffer_data_size(kind, size as i32, draw_kind.draw_kind_constant());
+    }
+  }
+
+  fn buffer_sub_data<V>(&self, buffer: &Self::Buffer, offset: usize, view: &V) -> Result<(), RenderApiError> where V: View {
+    let kind = buffer.buffer_kind_constant();
+    unsafe {
+      self.gl.bind_buffer(kind, Some(buffer.internal));
+      self.gl.buffer_sub_data_u8_slice(kind, offset as i32, &view.to_bytes());
+    }
+    Ok(())
+  }
+
+  fn check_framebuffer_status(&self, framebuffer: &Self::Framebuffer) -> Result<(), RenderApiError> {
+    let status = unsafe {
+      self.gl.bind_framebuffer(gl::FRAMEBUFFER, Some(*framebuffer));
+      self.gl.check_framebuffer_status(gl::FRAMEBUFFER)
+    };
+    FramebufferStatus::check(status).map_err(RenderApiError::IncompleteFramebuffer)
+  }
+
+  fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    unsafe { self.gl.clear_color(red, green, blue, alpha) }
+  }
+
+  fn clear<M>(&self, mask: M) where M: Into<ClearMasks> {
+    unsafe { self.gl.clear(mask.into().clear_mask_constant()) }
+  }
+
+  fn create_buffer(&self, kind: BufferKind) -> Result<Self::Buffer, RenderApiError> {
+    let internal = unsafe { self.gl.create_buffer() };
+    internal.map_err(|_| RenderApiError::FailedToCreateBuffer).map(|internal| {
+      NativeRenderBuffer { kind, internal }
+    })
+  }
+
+  fn create_framebuffer(&self) -> Result<Self::Framebuffer, RenderApiError> {
+    unsafe { self.gl.create_framebuffer() }.map_err(|_| RenderApiError::FailedToCreateFramebuffer)
+  }
+
+  fn create_renderbuffer(&self) -> Result<Self::Renderbuffer, RenderApiError> {
+    unsafe { self.gl.create_renderbuffer() }.map_err(|_| RenderApiError::FailedToCreateRenderbuffer)
+  }
+
+  fn create_texture(&self) -> Result<Self::Texture, RenderApiError> {
+    unsafe { self.gl.create_texture() }.map_err(|_| RenderApiError::FailedToCreateTexture)
+  }
+
+  fn create_vertex_array(&self) -> Result<Self::VertexArray, RenderApiError> {
+    unsafe { self.gl.create_vertex_array() }.map_err(|_| RenderApiError::FailedToCreateVertexArray)
+  }
+
+  fn draw_arrays(&self, mode: DrawArrayKind, first: i32, count: i32) {
+    unsafe { self.gl.draw_arrays(mode.draw_array_kind_constant(), first, count) }
+  }
+
+  fn draw_arrays_instanced(&self, mode: DrawArrayKind, first: i32, count: i32, instance_count: i32) {
+    let mode = mode.draw_array_kind_constant();
+    unsafe { self.gl.draw_arrays_instanced(mode, first, count, instance_count) }
+  }
+
+  fn draw_elements(&self, mode: DrawArrayKind, count: i32, kind: IndexKind, offset: i32) {
+    let mode = mode.draw_array_kind_constant();
+    unsafe { self.gl.draw_elements(mode, count, kind.index_kind_constant(), offset) }
+  }
+
+  fn draw_elements_instanced(
+      &self,
+      mode: DrawArrayKind,
+      count: i32,
+      kind: IndexKind,
+      offset: i32,
+      instance_count: i32,
+  ) {
+    let mode = mode.draw_array_kind_constant();
+    let kind = kind.index_kind_constant();
+    unsafe { self.gl.draw_elements_instanced(mode, count, kind, offset, instance_count) }
+  }
+
+  fn enable_vertex_attrib_array<A>(&self, key: A) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|i| unsafe { self.gl.enable_vertex_attrib_array(i) })
+  }
+
+  fn framebuffer_renderbuffer(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      renderbuffer: &Self::Renderbuffer,
+  ) {
+    unsafe {
+      self.gl.bind_framebuffer(gl::FRAMEBUFFER, Some(*framebuffer));
+      self.gl.framebuffer_renderbuffer(
+          gl::FRAMEBUFFER,
+          attachment.framebuffer_attachment_constant(),
+          gl::RENDERBUFFER,
+          Some(*renderbuffer),
+      );
+    }
+  }
+
+  fn framebuffer_texture_2d(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      texture: &Self::Texture,
+  ) {
+    unsafe {
+      self.gl.bind_framebuffer(gl::FRAMEBUFFER, Some(*framebuffer));
+      self.gl.framebuffer_texture_2d(
+          gl::FRAMEBUFFER,
+          attachment.framebuffer_attachment_constant(),
+          gl::TEXTURE_2D,
+          Some(*texture),
+          0,
+      );
+    }
+  }
+
+  fn generate_mipmap(&self, texture: &Self::Texture) {
+    self.edit_texture(*texture, || unsafe { self.gl.generate_mipmap(gl::TEXTURE_2D) });
+  }
+
+  fn get_attribute<AK>(&self, key: AK) -> Result<AttributeIndex, RenderApiError> where AK: AttributeKey {
+    let program = self.program.borrow();
+    program.locations.attribute(key.name(), || unsafe { self.gl.get_attrib_location(program.program, key.name()) })
+  }
+
+  fn get_uniform<UK>(&self, key: UK) -> Result<Self::UniformIndex, RenderApiError> where UK: UniformKey {
+    let program = self.program.borrow();
+    program.locations.uniform(key.name(), || unsafe { self.gl.get_uniform_location(program.program, key.name()) })
+  }
+
+  fn get_uniform_block<BK>(&self, key: BK) -> Result<Self::UniformBlockIndex, RenderApiError> where BK: UniformBlockKey {
+    let program = self.program.borrow();
+    program.locations.uniform_block(key.name(), || unsafe { self.gl.get_uniform_block_index(program.program, key.name()) })
+  }
+
+  fn read_pixels(&self, x: i32, y: i32, width: i32, height: i32) -> Result<Vec<u8>, RenderApiError> {
+    let mut pixels = vec![0; width.max(0) as usize * height.max(0) as usize * 4];
+    unsafe {
+      self.gl.read_pixels(
+          x,
+          y,
+          width,
+          height,
+          gl::RGBA,
+          gl::UNSIGNED_BYTE,
+          PixelPackData::Slice(Some(&mut pixels)),
+      );
+    }
+    Ok(pixels)
+  }
+
+  fn renderbuffer_storage(
+      &self,
+      renderbuffer: &Self::Renderbuffer,
+      format: RenderbufferFormat,
+      width: i32,
+      height: i32,
+  ) {
+    let format = format.renderbuffer_format_constant();
+    unsafe {
+      self.gl.bind_renderbuffer(gl::RENDERBUFFER, Some(*renderbuffer));
+      self.gl.renderbuffer_storage(gl::RENDERBUFFER, format, width, height);
+    }
+  }
+
+  fn set_pipeline_state(&self, state: &PipelineState) {
+    let PipelineState { depth, stencil, cull, scissor, blend, color_mask } = *state;
+    self.set_capability(Capability::DepthTest, depth.is_some());
+    self.set_capability(Capability::StencilTest, stencil.is_some());
+    self.set_capability(Capability::CullFace, cull.is_some());
+    self.set_capability(Capability::ScissorTest, scissor.is_some());
+    self.set_capability(Capability::Blend, blend);
+
+    unsafe {
+      match depth {
+        Some(DepthState { func, write }) => {
+          self.gl.depth_func(func.compare_func_constant());
+          self.gl.depth_mask(write);
+        },
+        None => self.gl.depth_mask(true),
+      }
+      match stencil {
+        Some(StencilState { func, reference, mask, fail, depth_fail, pass, write_mask }) => {
+          self.gl.stencil_func(func.compare_func_constant(), reference, mask);
+          self.gl.stencil_op(fail.stencil_op_constant(), depth_fail.stencil_op_constant(), pass.stencil_op_constant());
+          self.gl.stencil_mask(write_mask);
+        },
+        None => self.gl.stencil_mask(!0),
+      }
+      if let Some(face) = cull {
+        self.gl.cull_face(face.cull_face_constant());
+      }
+      if let Some(ScissorRect { x, y, width, height }) = scissor {
+        self.gl.scissor(x, y, width, height);
+      }
+      let ColorMask { red, green, blue, alpha } = color_mask;
+      self.gl.color_mask(red, green, blue, alpha);
+    }
+  }
+
+  fn set_uniform<U>(&self, key: U, value: UniformValue) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    let index = key.with_context(self)?;
+    let location = Some(&index);
+    unsafe {
+      match value {
+        UniformValue::Float(x) => self.gl.uniform_1_f32(location, x),
+        UniformValue::Vec2([x, y]) => self.gl.uniform_2_f32(location, x, y),
+        UniformValue::Vec3([x, y, z]) => self.gl.uniform_3_f32(location, x, y, z),
+        UniformValue::Vec4([x, y, z, w]) => self.gl.uniform_4_f32(location, x, y, z, w),
+        UniformValue::Int(x) => self.gl.uniform_1_i32(location, x),
+        UniformValue::IVec2([x, y]) => self.gl.uniform_2_i32(location, x, y),
+        UniformValue::IVec3([x, y, z]) => self.gl.uniform_3_i32(location, x, y, z),
+        UniformValue::IVec4([x, y, z, w]) => self.gl.uniform_4_i32(location, x, y, z, w),
+        UniformValue::FloatArray(v) => self.gl.uniform_1_f32_slice(location, &v),
+        UniformValue::Vec2Array(v) => self.gl.uniform_2_f32_slice(location, &v.concat()),
+        UniformValue::Vec3Array(v) => self.gl.uniform_3_f32_slice(location, &v.concat()),
+        UniformValue::Vec4Array(v) => self.gl.uniform_4_f32_slice(location, &v.concat()),
+        UniformValue::IntArray(v) => self.gl.uniform_1_i32_slice(location, &v),
+        UniformValue::IVec2Array(v) => self.gl.uniform_2_i32_slice(location, &v.concat()),
+        UniformValue::IVec3Array(v) => self.gl.uniform_3_i32_slice(location, &v.concat()),
+        UniformValue::IVec4Array(v) => self.gl.uniform_4_i32_slice(location, &v.concat()),
+        UniformValue::Mat2(m) => self.gl.uniform_matrix_2_f32_slice(location, false, &m),
+        UniformValue::Mat3(m) => self.gl.uniform_matrix_3_f32_slice(location, false, &m),
+        UniformValue::Mat4(m) => self.gl.uniform_matrix_4_f32_slice(location, false, &m),
+      }
+    }
+    Ok(())
+  }
+
+  fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+    unsafe { self.gl.viewport(x, y, width, height) }
+  }
+
+  fn tex_image_2d(
+      &self,
+      texture: &Self::Texture,
+      width: i32,
+      height: i32,
+      pixels: &Uint8View,
+  ) -> Result<(), RenderApiError> {
+    check_texture_data(width, height, pixels)?;
+    self.edit_texture(*texture, || unsafe {
+      self.gl.tex_image_2d(
+          gl::TEXTURE_2D,
+          0,
+          gl::RGBA as i32,
+          width,
+          height,
+          0,
+          gl::RGBA,
+          pixels.view_precision_constant(),
+          PixelUnpackData::Slice(Some(pixels.as_slice())),
+      );
+    });
+    Ok(())
+  }
+
+  fn tex_parameter(&self, texture: &Self::Texture, parameter: TextureParameter) {
+    let (name, value) = parameter.texture_parameter_constants();
+    self.edit_texture(*texture, || unsafe { self.gl.tex_parameter_i32(gl::TEXTURE_2D, name, value as i32) });
+  }
+
+  fn uniform_block_binding<B>(&self, key: B, binding: u32) -> Result<(), RenderApiError> where B: IntoUniformBlockIndex {
+    key.with_context(self).map(|index| unsafe {
+      self.gl.uniform_block_binding(self.program.borrow().program, index, binding)
+    })
+  }
+
+  fn use_program(&self, program: &Self::Program) {
+    unsafe { self.gl.use_program(Some(program.program)) };
+    self.program.replace(program.clone());
+  }
+
+  fn vertex_attrib_divisor<A>(&self, key: A, divisor: u32) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| unsafe { self.gl.vertex_attrib_divisor(index, divisor) })
+  }
+
+  fn vertex_attrib_i_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| unsafe {
+      self.gl.vertex_attrib_pointer_i32(index, size, precision.view_precision_constant(), stride, offset)
+    })
+  }
+
+  fn vertex_attrib_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      normalized: bool,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| unsafe {
+      self.gl.vertex_attrib_pointer_f32(
+          index,
+          size,
+          precision.view_precision_constant(),
+          normalized,
+          stride,
+          offset,
+      )
+    })
+  }
+}
+
+/**
+ * A linked program, its active variables & the locations
+ * looked up in it, which are shared by every copy of the
+ * handle.
+ */
+#[derive(Clone, Debug)]
+pub struct NativeProgram<P, U> {
+  pub program: P,
+  locations: Rc<LocationCache<U>>,
+  reflection: Rc<ProgramReflection<U>>,
+}
+
+impl<P, U> NativeProgram<P, U> where P: Copy, U: Clone {
+  /**
+   * Expects `program` to be linked & `gl` to be current, as
+   * its active variables are queried here.
+   */
+  pub fn create<G>(gl: &G, program: P) -> Self where G: HasContext<Program = P, UniformLocation = U> {
+    let reflection = Rc::new(reflect_program(gl, program));
+    NativeProgram { program, locations: Rc::new(LocationCache::create()), reflection }
+  }
+
+  pub fn reflection(&self) -> &ProgramReflection<U> {
+    &self.reflection
+  }
+}
+
+fn reflect_program<G>(gl: &G, program: G::Program) -> ProgramReflection<G::UniformLocation> where G: HasContext {
+  // Safety: the context is current per the docs on `create`.
+  unsafe {
+    // built in attributes like `gl_VertexID` are active but
+    // have no location, so they're left out.
+    let attributes = (0..gl.get_active_attributes(program))
+      .filter_map(|index| gl.get_active_attribute(program, index))
+      .filter_map(|info| {
+        let location = gl.get_attrib_location(program, &info.name)?;
+        Some(ActiveVariable {
+          kind: ShaderType::from_constant(info.atype),
+          size: info.size,
+          name: info.name,
+          location,
+        })
+      })
+      .collect();
+
+    let uniforms = (0..gl.get_active_uniforms(program))
+      .filter_map(|index| gl.get_active_uniform(program, index))
+      .map(|info| ActiveVariable {
+        location: gl.get_uniform_location(program, &info.name),
+        kind: ShaderType::from_constant(info.utype),
+        size: info.size,
+        name: info.name,
+      })
+      .collect();
+
+    ProgramReflection { attributes, uniforms }
+  }
+}
+
+impl<P, U> PartialEq for NativeProgram<P, U> where P: PartialEq {
+  fn eq(&self, other: &Self) -> bool {
+    self.program == other.program
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct NativeRenderBuffer<B> {
+  pub kind: BufferKind,
+  pub internal: B,
+}
+
+impl<B> HasBufferKind for NativeRenderBuffer<B> {
+  fn buffer_kind_constant(&self) -> u32 {
+    self.kind.buffer_kind_constant()
+  }
+}
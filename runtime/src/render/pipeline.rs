@@ -0,0 +1,133 @@
+#[cfg(feature = "trace")]
+use serde::{Serialize, Deserialize};
+use super::constants::{CompareFunc, CullFace, StencilOp};
+
+/**
+ * The fixed function state used by draws & clears, which is
+ * applied as a unit with `RenderAPI::set_pipeline_state`.
+ *
+ * Each test is enabled when it's `Some`, & the default is the
+ * same as a new GL context, where everything is disabled &
+ * every colour channel is written.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub struct PipelineState {
+  pub depth: Option<DepthState>,
+  pub stencil: Option<StencilState>,
+  pub cull: Option<CullFace>,
+  pub scissor: Option<ScissorRect>,
+  /**
+   * If colours are combined with `blend_func`, rather than
+   * replacing the colour in the framebuffer.
+   */
+  pub blend: bool,
+  pub color_mask: ColorMask,
+}
+
+impl PipelineState {
+  pub fn with_depth(self, depth: DepthState) -> Self {
+    PipelineState { depth: Some(depth), ..self }
+  }
+
+  pub fn with_stencil(self, stencil: StencilState) -> Self {
+    PipelineState { stencil: Some(stencil), ..self }
+  }
+
+  pub fn with_cull(self, cull: CullFace) -> Self {
+    PipelineState { cull: Some(cull), ..self }
+  }
+
+  pub fn with_scissor(self, scissor: ScissorRect) -> Self {
+    PipelineState { scissor: Some(scissor), ..self }
+  }
+
+  pub fn with_blend(self, blend: bool) -> Self {
+    PipelineState { blend, ..self }
+  }
+
+  pub fn with_color_mask(self, color_mask: ColorMask) -> Self {
+    PipelineState { color_mask, ..self }
+  }
+}
+
+/**
+ * Passes fragments whose depth compares with `func` against
+ * the stored depth, where `write` stores the depth of the
+ * fragments that pass.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub struct DepthState {
+  pub func: CompareFunc,
+  pub write: bool,
+}
+
+impl Default for DepthState {
+  fn default() -> Self {
+    DepthState { func: CompareFunc::Less, write: true }
+  }
+}
+
+/**
+ * Passes fragments where `reference & mask` compares with
+ * `func` against `stored & mask`, then updates the stored
+ * value with the op for the outcome of the stencil & depth
+ * tests, only changing the bits in `write_mask`.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub struct StencilState {
+  pub func: CompareFunc,
+  pub reference: i32,
+  pub mask: u32,
+  pub fail: StencilOp,
+  pub depth_fail: StencilOp,
+  pub pass: StencilOp,
+  pub write_mask: u32,
+}
+
+impl Default for StencilState {
+  fn default() -> Self {
+    StencilState {
+      func: CompareFunc::Always,
+      reference: 0,
+      mask: !0,
+      fail: StencilOp::Keep,
+      depth_fail: StencilOp::Keep,
+      pass: StencilOp::Keep,
+      write_mask: !0,
+    }
+  }
+}
+
+/**
+ * The only pixels that can be drawn or cleared, with the
+ * origin at the bottom left like the viewport.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub struct ScissorRect {
+  pub x: i32,
+  pub y: i32,
+  pub width: i32,
+  pub height: i32,
+}
+
+/**
+ * The colour channels that draws & clears write to.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize, Deserialize))]
+pub struct ColorMask {
+  pub red: bool,
+  pub green: bool,
+  pub blue: bool,
+  pub alpha: bool,
+}
+
+impl Default for ColorMask {
+  fn default() -> Self {
+    ColorMask { red: true, green: true, blue: true, alpha: true }
+  }
+}
@@ -0,0 +1,237 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/**
+ * The file the injected `#define`s are reported as being in.
+ */
+pub const DEFINES_FILE: &str = "<defines>";
+
+/**
+ * Resolves `#include "name"` against a library of chunks &
+ * adds a `#define` for each define & feature, so shaders can
+ * share code without the driver supporting either.
+ *
+ * Each chunk is only included once per shader, like with
+ * `#pragma once`, so chunks can include what they depend on.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShaderPreprocessor {
+  chunks: HashMap<String, String>,
+  defines: Vec<(String, Option<String>)>,
+}
+
+/**
+ * The source that's compiled, & for each of its lines, the
+ * file & line it came from.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessedShader {
+  pub source: String,
+  pub lines: Vec<SourceLocation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+  pub file: String,
+  pub line: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PreprocessError {
+  UnknownChunk { location: SourceLocation, name: String },
+  MalformedInclude(SourceLocation),
+  /**
+   * The files that include each other, starting & ending
+   * with the same file.
+   */
+  RecursiveInclude(Vec<String>),
+}
+
+impl fmt::Display for PreprocessError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PreprocessError::UnknownChunk { location, name } =>
+        write!(f, "{}:{}: there's no chunk named \"{}\" to include", location.file, location.line, name),
+      PreprocessError::MalformedInclude(location) =>
+        write!(f, "{}:{}: expected #include \"name\"", location.file, location.line),
+      PreprocessError::RecursiveInclude(files) =>
+        write!(f, "recursive include: {}", files.join(" -> ")),
+    }
+  }
+}
+
+impl ShaderPreprocessor {
+  pub fn create() -> Self {
+    ShaderPreprocessor::default()
+  }
+
+  pub fn with_chunk(mut self, name: &str, source: &str) -> Self {
+    self.chunks.insert(name.to_string(), source.to_string());
+    self
+  }
+
+  pub fn with_define(mut self, name: &str, value: &str) -> Self {
+    self.defines.push((name.to_string(), Some(value.to_string())));
+    self
+  }
+
+  /**
+   * Defines each feature without a value, for `#ifdef`.
+   */
+  pub fn with_features(mut self, features: &[&str]) -> Self {
+    self.defines.extend(features.iter().map(|feature| (feature.to_string(), None)));
+    self
+  }
+
+  /**
+   * The defines go after `#version`, as it has to be the
+   * first line of the shader.
+   */
+  pub fn process(&self, file: &str, source: &str) -> Result<ProcessedShader, PreprocessError> {
+    let mut shader = ProcessedShader { source: String::new(), lines: vec![] };
+    let mut lines = source.lines().enumerate().peekable();
+
+    if let Some((_, first)) = lines.peek() {
+      if first.trim_start().starts_with("#version") {
+        shader.push(first, file, 1);
+        lines.next();
+      }
+    }
+
+    for (index, (name, value)) in self.defines.iter().enumerate() {
+      let define = match value {
+        Some(value) => format!("#define {} {}", name, value),
+        None => format!("#define {}", name),
+      };
+      shader.push(&define, DEFINES_FILE, index + 1);
+    }
+
+    let mut stack = vec![file.to_string()];
+    self.append(&mut shader, file, lines, &mut stack, &mut HashSet::new())?;
+    Ok(shader)
+  }
+
+  fn append<'a, I>(
+      &self,
+      shader: &mut ProcessedShader,
+      file: &str,
+      lines: I,
+      stack: &mut Vec<String>,
+      included: &mut HashSet<String>,
+  ) -> Result<(), PreprocessError> where I: Iterator<Item = (usize, &'a str)> {
+    for (index, line) in lines {
+      let location = || SourceLocation { file: file.to_string(), line: index + 1 };
+      let name = match include_name(line) {
+        None => {
+          shader.push(line, file, index + 1);
+          continue;
+        },
+        Some(name) => name.ok_or_else(|| PreprocessError::MalformedInclude(location()))?,
+      };
+
+      if stack.iter().any(|f| f == name) {
+        let mut files = stack.clone();
+        files.push(name.to_string());
+        return Err(PreprocessError::RecursiveInclude(files));
+      }
+      if !included.insert(name.to_string()) {
+        continue;
+      }
+
+      let chunk = self.chunks.get(name).ok_or_else(|| {
+        PreprocessError::UnknownChunk { location: location(), name: name.to_string() }
+      })?;
+      stack.push(name.to_string());
+      self.append(shader, name, chunk.lines().enumerate(), stack, included)?;
+      stack.pop();
+    }
+    Ok(())
+  }
+}
+
+impl ProcessedShader {
+  /**
+   * Where a line of the processed source came from, where
+   * both are numbered from 1 like in an info log.
+   */
+  pub fn original_line(&self, line: usize) -> Option<&SourceLocation> {
+    line.checked_sub(1).and_then(|index| self.lines.get(index))
+  }
+
+  fn push(&mut self, line: &str, file: &str, number: usize) {
+    self.source.push_str(line);
+    self.source.push('\n');
+    self.lines.push(SourceLocation { file: file.to_string(), line: number });
+  }
+}
+
+/**
+ * `None` if the line isn't an include, otherwise the name
+ * of the chunk if it's well formed.
+ */
+fn include_name(line: &str) -> Option<Option<&str>> {
+  let rest = line.trim().strip_prefix("#include")?;
+  if !rest.is_empty() && !rest.starts_with(|c: char| c.is_whitespace() || c == '"') {
+    return None;
+  }
+  let name = rest.trim().strip_prefix('"').and_then(|r| r.strip_suffix('"'));
+  Some(name.filter(|name| !name.is_empty() && !name.contains('"')))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn location(file: &str, line: usize) -> SourceLocation {
+    SourceLocation { file: file.to_string(), line }
+  }
+
+  #[test]
+  fn includes_chunks_and_maps_their_lines() {
+    let preprocessor = ShaderPreprocessor::create()
+      .with_chunk("clip_space", "vec2 to_clip(vec2 p) {\n  return p;\n}");
+    let shader = preprocessor.process("vertex", "#version 300 es\n#include \"clip_space\"\nvoid main() {}").unwrap();
+
+    assert_eq!(shader.source, "#version 300 es\nvec2 to_clip(vec2 p) {\n  return p;\n}\nvoid main() {}\n");
+    assert_eq!(shader.original_line(1), Some(&location("vertex", 1)));
+    assert_eq!(shader.original_line(3), Some(&location("clip_space", 2)));
+    assert_eq!(shader.original_line(5), Some(&location("vertex", 3)));
+    assert_eq!(shader.original_line(6), None);
+  }
+
+  #[test]
+  fn defines_go_after_the_version() {
+    let preprocessor = ShaderPreprocessor::create().with_define("SIZE", "2.0").with_features(&["GRID"]);
+    let shader = preprocessor.process("vertex", "#version 300 es\nvoid main() {}").unwrap();
+
+    assert_eq!(shader.source, "#version 300 es\n#define SIZE 2.0\n#define GRID\nvoid main() {}\n");
+    assert_eq!(shader.original_line(3), Some(&location(DEFINES_FILE, 2)));
+    assert_eq!(shader.original_line(4), Some(&location("vertex", 2)));
+  }
+
+  #[test]
+  fn chunks_are_included_once() {
+    let preprocessor = ShaderPreprocessor::create()
+      .with_chunk("a", "#include \"common\"\nfloat a;")
+      .with_chunk("common", "float common;");
+    let shader = preprocessor.process("vertex", "#include \"common\"\n#include \"a\"").unwrap();
+    assert_eq!(shader.source, "float common;\nfloat a;\n");
+  }
+
+  #[test]
+  fn reports_bad_includes() {
+    let preprocessor = ShaderPreprocessor::create()
+      .with_chunk("a", "#include \"b\"")
+      .with_chunk("b", "#include \"a\"");
+
+    assert_eq!(preprocessor.process("vertex", "\n#include \"missing\""), Err(PreprocessError::UnknownChunk {
+      location: location("vertex", 2),
+      name: "missing".to_string(),
+    }));
+    assert_eq!(preprocessor.process("vertex", "#include <a>"), Err(PreprocessError::MalformedInclude(location("vertex", 1))));
+    assert_eq!(
+      preprocessor.process("vertex", "#include \"a\"").map_err(|e| e.to_string()),
+      Err("recursive include: vertex -> a -> b -> a".to_string()),
+    );
+  }
+}
@@ -0,0 +1,711 @@
+use std::cell::{Cell, RefCell};
+use super::api::{
+  check_texture_data,
+  AttributeIndex,
+  AttributeKey,
+  IntoAttributeIndex,
+  IntoUniformBlockIndex,
+  IntoUniformIndex,
+  RenderAPI,
+  RenderApiError,
+  UniformBlockKey,
+  UniformKey,
+  UniformValue,
+};
+use super::constants::{
+  BufferKind,
+  BlendFuncFactor,
+  ClearMasks,
+  DrawArrayKind,
+  DrawKind,
+  FramebufferAttachment,
+  IndexKind,
+  RenderbufferFormat,
+  TextureParameter,
+  TextureUnit,
+  ViewPrecision,
+  HasBufferKind,
+};
+use super::data::{Uint8View, View};
+use super::pipeline::{PipelineState};
+
+/**
+ * A `RenderAPI` that doesn't render anything, instead it
+ * records every call made against it so the calls can be
+ * inspected afterwards, which makes it possible to test
+ * code using the `RenderAPI` outside of a browser.
+ *
+ * Attributes & uniforms are resolved against the name
+ * tables of the program in use, where the location is the
+ * position of the name in the table, so a missing name
+ * can be used to simulate a lookup failure.
+ */
+#[derive(Debug)]
+pub struct RecordingRenderAPI {
+  programs: RefCell<Vec<ProgramNames>>,
+  program: Cell<usize>,
+  commands: RefCell<Vec<Command>>,
+  next_buffer_id: Cell<usize>,
+  next_texture_id: Cell<usize>,
+  next_framebuffer_id: Cell<usize>,
+  next_renderbuffer_id: Cell<usize>,
+  next_vertex_array_id: Cell<usize>,
+}
+
+/**
+ * The name tables of a program.
+ */
+#[derive(Debug)]
+struct ProgramNames {
+  attributes: Vec<String>,
+  uniforms: Vec<String>,
+  uniform_blocks: Vec<String>,
+}
+
+impl ProgramNames {
+  fn create(attributes: &[&str], uniforms: &[&str]) -> Self {
+    ProgramNames {
+      attributes: attributes.iter().map(|s| s.to_string()).collect(),
+      uniforms: uniforms.iter().map(|s| s.to_string()).collect(),
+      uniform_blocks: vec![],
+    }
+  }
+}
+
+impl RecordingRenderAPI {
+  /**
+   * Starts out using `RecordingProgram(0)`, which has the
+   * given name tables.
+   */
+  pub fn create(attributes: &[&str], uniforms: &[&str]) -> Self {
+    RecordingRenderAPI {
+      programs: RefCell::new(vec![ProgramNames::create(attributes, uniforms)]),
+      program: Cell::new(0),
+      commands: RefCell::new(vec![]),
+      next_buffer_id: Cell::new(0),
+      next_texture_id: Cell::new(0),
+      next_framebuffer_id: Cell::new(0),
+      next_renderbuffer_id: Cell::new(0),
+      next_vertex_array_id: Cell::new(0),
+    }
+  }
+
+  /**
+   * Sets the name table uniform blocks are resolved against
+   * in the first program, which is empty by default.
+   */
+  pub fn with_uniform_blocks(mut self, uniform_blocks: &[&str]) -> Self {
+    self.programs.get_mut()[0].uniform_blocks = uniform_blocks.iter().map(|s| s.to_string()).collect();
+    self
+  }
+
+  /**
+   * Adds a program with its own name tables, which isn't
+   * used until it's passed to `use_program`.
+   */
+  pub fn add_program(&self, attributes: &[&str], uniforms: &[&str]) -> RecordingProgram {
+    let mut programs = self.programs.borrow_mut();
+    programs.push(ProgramNames::create(attributes, uniforms));
+    RecordingProgram(programs.len() - 1)
+  }
+
+  /**
+   * A copy of every command recorded so far.
+   */
+  pub fn commands(&self) -> Vec<Command> {
+    self.commands.borrow().clone()
+  }
+
+  /**
+   * Removes & returns every command recorded so far.
+   */
+  pub fn take_commands(&self) -> Vec<Command> {
+    self.commands.replace(vec![])
+  }
+
+  fn record(&self, command: Command) {
+    self.commands.borrow_mut().push(command);
+  }
+}
+
+impl RenderAPI for RecordingRenderAPI {
+  type Buffer = RecordingBuffer;
+  type Program = RecordingProgram;
+  type UniformIndex = RecordingUniformIndex;
+  type UniformBlockIndex = RecordingUniformBlockIndex;
+  type Texture = RecordingTexture;
+  type Framebuffer = RecordingFramebuffer;
+  type Renderbuffer = RecordingRenderbuffer;
+  type VertexArray = RecordingVertexArray;
+
+  fn bind_buffer(&self, buffer: &Self::Buffer) {
+    self.record(Command::BindBuffer(*buffer));
+  }
+
+  fn bind_buffer_base(&self, buffer: &Self::Buffer, index: u32) {
+    self.record(Command::BindBufferBase { buffer: *buffer, index });
+  }
+
+  fn bind_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>) {
+    self.record(Command::BindFramebuffer(framebuffer.copied()));
+  }
+
+  fn bind_texture(&self, unit: TextureUnit, texture: &Self::Texture) {
+    self.record(Command::BindTexture { unit, texture: *texture });
+  }
+
+  fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>) {
+    self.record(Command::BindVertexArray(vertex_array.copied()));
+  }
+
+  fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    self.record(Command::BlendColor { red, green, blue, alpha });
+  }
+
+  fn blend_func(&self, src: BlendFuncFactor, dst: BlendFuncFactor) {
+    self.record(Command::BlendFunc { src, dst });
+  }
+
+  fn buffer_data<V>(
+      &self,
+      buffer: &Self::Buffer,
+      view: &V,
+      draw_kind: DrawKind,
+  ) -> Result<(), RenderApiError> where V: View {
+    let precision = view.get_precision();
+    let data = view.to_bytes();
+    self.record(Command::BufferData { buffer: *buffer, precision, data, draw_kind });
+    Ok(())
+  }
+
+  fn buffer_data_with_size(&self, buffer: &Self::Buffer, size: usize, draw_kind: DrawKind) {
+    self.record(Command::BufferDataWithSize { buffer: *buffer, size, draw_kind });
+  }
+
+  fn buffer_sub_data<V>(&self, buffer: &Self::Buffer, offset: usize, view: &V) -> Result<(), RenderApiError> where V: View {
+    let precision = view.get_precision();
+    let data = view.to_bytes();
+    self.record(Command::BufferSubData { buffer: *buffer, offset, precision, data });
+    Ok(())
+  }
+
+  /**
+   * Nothing is rendered, so every framebuffer is complete.
+   */
+  fn check_framebuffer_status(&self, framebuffer: &Self::Framebuffer) -> Result<(), RenderApiError> {
+    self.record(Command::CheckFramebufferStatus(*framebuffer));
+    Ok(())
+  }
+
+  fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    self.record(Command::ClearColor { red, green, blue, alpha });
+  }
+
+  fn clear<M>(&self, mask: M) where M: Into<ClearMasks> {
+    self.record(Command::Clear(mask.into()));
+  }
+
+  fn create_buffer(&self, kind: BufferKind) -> Result<Self::Buffer, RenderApiError> {
+    let id = self.next_buffer_id.get();
+    let buffer = RecordingBuffer { id, kind };
+    self.next_buffer_id.set(id + 1);
+    self.record(Command::CreateBuffer(buffer));
+    Ok(buffer)
+  }
+
+  fn create_framebuffer(&self) -> Result<Self::Framebuffer, RenderApiError> {
+    let framebuffer = RecordingFramebuffer(self.next_framebuffer_id.get());
+    self.next_framebuffer_id.set(framebuffer.0 + 1);
+    self.record(Command::CreateFramebuffer(framebuffer));
+    Ok(framebuffer)
+  }
+
+  fn create_renderbuffer(&self) -> Result<Self::Renderbuffer, RenderApiError> {
+    let renderbuffer = RecordingRenderbuffer(self.next_renderbuffer_id.get());
+    self.next_renderbuffer_id.set(renderbuffer.0 + 1);
+    self.record(Command::CreateRenderbuffer(renderbuffer));
+    Ok(renderbuffer)
+  }
+
+  fn create_texture(&self) -> Result<Self::Texture, RenderApiError> {
+    let texture = RecordingTexture(self.next_texture_id.get());
+    self.next_texture_id.set(texture.0 + 1);
+    self.record(Command::CreateTexture(texture));
+    Ok(texture)
+  }
+
+  fn create_vertex_array(&self) -> Result<Self::VertexArray, RenderApiError> {
+    let vertex_array = RecordingVertexArray(self.next_vertex_array_id.get());
+    self.next_vertex_array_id.set(vertex_array.0 + 1);
+    self.record(Command::CreateVertexArray(vertex_array));
+    Ok(vertex_array)
+  }
+
+  fn draw_arrays(&self, mode: DrawArrayKind, first: i32, count: i32) {
+    self.record(Command::DrawArrays { mode, first, count });
+  }
+
+  fn draw_arrays_instanced(&self, mode: DrawArrayKind, first: i32, count: i32, instance_count: i32) {
+    self.record(Command::DrawArraysInstanced { mode, first, count, instance_count });
+  }
+
+  fn draw_elements(&self, mode: DrawArrayKind, count: i32, kind: IndexKind, offset: i32) {
+    self.record(Command::DrawElements { mode, count, kind, offset });
+  }
+
+  fn draw_elements_instanced(
+      &self,
+      mode: DrawArrayKind,
+      count: i32,
+      kind: IndexKind,
+      offset: i32,
+      instance_count: i32,
+  ) {
+    self.record(Command::DrawElementsInstanced { mode, count, kind, offset, instance_count });
+  }
+
+  fn enable_vertex_attrib_array<A>(&self, key: A) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|i| self.record(Command::EnableVertexAttribArray(i)))
+  }
+
+  fn framebuffer_renderbuffer(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      renderbuffer: &Self::Renderbuffer,
+  ) {
+    self.record(Command::FramebufferRenderbuffer {
+      framebuffer: *framebuffer,
+      attachment,
+      renderbuffer: *renderbuffer,
+    });
+  }
+
+  fn framebuffer_texture_2d(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      texture: &Self::Texture,
+  ) {
+    self.record(Command::FramebufferTexture2D {
+      framebuffer: *framebuffer,
+      attachment,
+      texture: *texture,
+    });
+  }
+
+  fn generate_mipmap(&self, texture: &Self::Texture) {
+    self.record(Command::GenerateMipmap(*texture));
+  }
+
+  fn get_attribute<AK>(&self, key: AK) -> Result<AttributeIndex, RenderApiError> where AK: AttributeKey {
+    let name = key.name();
+    self.programs.borrow()[self.program.get()].attributes.iter()
+      .position(|a| a == name)
+      .map(|i| i as AttributeIndex)
+      .ok_or_else(|| RenderApiError::InvalidAttributeName(name.to_string()))
+  }
+
+  fn get_uniform<UK>(&self, key: UK) -> Result<Self::UniformIndex, RenderApiError> where UK: UniformKey {
+    let name = key.name();
+    self.programs.borrow()[self.program.get()].uniforms.iter()
+      .position(|u| u == name)
+      .map(RecordingUniformIndex)
+      .ok_or_else(|| RenderApiError::InvalidUniformName(name.to_string()))
+  }
+
+  /**
+   * Nothing is rendered, so the pixels are always zeroed.
+   */
+  fn get_uniform_block<BK>(&self, key: BK) -> Result<Self::UniformBlockIndex, RenderApiError> where BK: UniformBlockKey {
+    let name = key.name();
+    self.programs.borrow()[self.program.get()].uniform_blocks.iter()
+      .position(|b| b == name)
+      .map(RecordingUniformBlockIndex)
+      .ok_or_else(|| RenderApiError::InvalidUniformBlockName(name.to_string()))
+  }
+
+  fn read_pixels(&self, x: i32, y: i32, width: i32, height: i32) -> Result<Vec<u8>, RenderApiError> {
+    self.record(Command::ReadPixels { x, y, width, height });
+    Ok(vec![0; width.max(0) as usize * height.max(0) as usize * 4])
+  }
+
+  fn renderbuffer_storage(
+      &self,
+      renderbuffer: &Self::Renderbuffer,
+      format: RenderbufferFormat,
+      width: i32,
+      height: i32,
+  ) {
+    self.record(Command::RenderbufferStorage { renderbuffer: *renderbuffer, format, width, height });
+  }
+
+  fn set_pipeline_state(&self, state: &PipelineState) {
+    self.record(Command::SetPipelineState(*state));
+  }
+
+  fn set_uniform<U>(&self, key: U, value: UniformValue) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    key.with_context(self).map(|index| self.record(Command::SetUniform { index, value }))
+  }
+
+  fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+    self.record(Command::SetViewport { x, y, width, height });
+  }
+
+  fn tex_image_2d(
+      &self,
+      texture: &Self::Texture,
+      width: i32,
+      height: i32,
+      pixels: &Uint8View,
+  ) -> Result<(), RenderApiError> {
+    check_texture_data(width, height, pixels)?;
+    let data = pixels.to_bytes();
+    self.record(Command::TexImage2D { texture: *texture, width, height, data });
+    Ok(())
+  }
+
+  fn tex_parameter(&self, texture: &Self::Texture, parameter: TextureParameter) {
+    self.record(Command::TexParameter { texture: *texture, parameter });
+  }
+
+  fn uniform_block_binding<B>(&self, key: B, binding: u32) -> Result<(), RenderApiError> where B: IntoUniformBlockIndex {
+    key.with_context(self).map(|block| self.record(Command::UniformBlockBinding { block, binding }))
+  }
+
+  fn use_program(&self, program: &Self::Program) {
+    self.program.set(program.0);
+    self.record(Command::UseProgram(*program));
+  }
+
+  fn vertex_attrib_divisor<A>(&self, key: A, divisor: u32) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| self.record(Command::VertexAttribDivisor { index, divisor }))
+  }
+
+  fn vertex_attrib_i_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| {
+      self.record(Command::VertexAttribIPointer { index, size, precision, stride, offset })
+    })
+  }
+
+  fn vertex_attrib_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      normalized: bool,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| {
+      self.record(Command::VertexAttribPointer {
+        index,
+        size,
+        precision,
+        normalized,
+        stride,
+        offset,
+      })
+    })
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingBuffer {
+  pub id: usize,
+  pub kind: BufferKind,
+}
+
+impl HasBufferKind for RecordingBuffer {
+  fn buffer_kind_constant(&self) -> u32 {
+    self.kind.buffer_kind_constant()
+  }
+}
+
+/**
+ * The order the texture was created in.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingTexture(pub usize);
+
+/**
+ * The order the framebuffer was created in.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingFramebuffer(pub usize);
+
+/**
+ * The order the renderbuffer was created in.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingRenderbuffer(pub usize);
+
+/**
+ * The order the program was added in, where the program the
+ * API is created with is the first.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingProgram(pub usize);
+
+/**
+ * The order the vertex array was created in.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingVertexArray(pub usize);
+
+/**
+ * The position of the uniform in the uniform name table.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingUniformIndex(pub usize);
+
+/**
+ * The position of the uniform block in the uniform block
+ * name table.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingUniformBlockIndex(pub usize);
+
+/**
+ * A call made against the `RecordingRenderAPI`, lookups of
+ * attributes and uniforms are not recorded.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+  BindBuffer(RecordingBuffer),
+  BindBufferBase { buffer: RecordingBuffer, index: u32 },
+  BindFramebuffer(Option<RecordingFramebuffer>),
+  BindTexture { unit: TextureUnit, texture: RecordingTexture },
+  BindVertexArray(Option<RecordingVertexArray>),
+  BlendColor { red: f32, green: f32, blue: f32, alpha: f32 },
+  BlendFunc { src: BlendFuncFactor, dst: BlendFuncFactor },
+  BufferData {
+    buffer: RecordingBuffer,
+    precision: ViewPrecision,
+    data: Vec<u8>,
+    draw_kind: DrawKind,
+  },
+  BufferDataWithSize { buffer: RecordingBuffer, size: usize, draw_kind: DrawKind },
+  BufferSubData {
+    buffer: RecordingBuffer,
+    offset: usize,
+    precision: ViewPrecision,
+    data: Vec<u8>,
+  },
+  CheckFramebufferStatus(RecordingFramebuffer),
+  ClearColor { red: f32, green: f32, blue: f32, alpha: f32 },
+  Clear(ClearMasks),
+  CreateBuffer(RecordingBuffer),
+  CreateFramebuffer(RecordingFramebuffer),
+  CreateRenderbuffer(RecordingRenderbuffer),
+  CreateTexture(RecordingTexture),
+  CreateVertexArray(RecordingVertexArray),
+  DrawArrays { mode: DrawArrayKind, first: i32, count: i32 },
+  DrawArraysInstanced { mode: DrawArrayKind, first: i32, count: i32, instance_count: i32 },
+  DrawElements { mode: DrawArrayKind, count: i32, kind: IndexKind, offset: i32 },
+  DrawElementsInstanced {
+    mode: DrawArrayKind,
+    count: i32,
+    kind: IndexKind,
+    offset: i32,
+    instance_count: i32,
+  },
+  EnableVertexAttribArray(AttributeIndex),
+  FramebufferRenderbuffer {
+    framebuffer: RecordingFramebuffer,
+    attachment: FramebufferAttachment,
+    renderbuffer: RecordingRenderbuffer,
+  },
+  FramebufferTexture2D {
+    framebuffer: RecordingFramebuffer,
+    attachment: FramebufferAttachment,
+    texture: RecordingTexture,
+  },
+  GenerateMipmap(RecordingTexture),
+  ReadPixels { x: i32, y: i32, width: i32, height: i32 },
+  RenderbufferStorage {
+    renderbuffer: RecordingRenderbuffer,
+    format: RenderbufferFormat,
+    width: i32,
+    height: i32,
+  },
+  SetPipelineState(PipelineState),
+  SetUniform { index: RecordingUniformIndex, value: UniformValue },
+  SetViewport { x: i32, y: i32, width: i32, height: i32 },
+  TexImage2D { texture: RecordingTexture, width: i32, height: i32, data: Vec<u8> },
+  TexParameter { texture: RecordingTexture, parameter: TextureParameter },
+  UniformBlockBinding { block: RecordingUniformBlockIndex, binding: u32 },
+  UseProgram(RecordingProgram),
+  VertexAttribDivisor { index: AttributeIndex, divisor: u32 },
+  VertexAttribIPointer {
+    index: AttributeIndex,
+    size: i32,
+    precision: ViewPrecision,
+    stride: i32,
+    offset: i32,
+  },
+  VertexAttribPointer {
+    index: AttributeIndex,
+    size: i32,
+    precision: ViewPrecision,
+    normalized: bool,
+    stride: i32,
+    offset: i32,
+  },
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::constants::{ClearMask};
+  use super::super::data::{Float32View};
+  use super::super::super::math::matrices::{Matrix};
+
+  #[derive(Clone, Copy, Debug)]
+  enum Keys {
+    Present,
+    Missing,
+  }
+
+  impl AttributeKey for Keys {
+    fn name(&self) -> &str {
+      match self {
+        Keys::Present => "present",
+        Keys::Missing => "missing",
+      }
+    }
+  }
+
+  impl UniformBlockKey for Keys {
+    fn name(&self) -> &str {
+      AttributeKey::name(self)
+    }
+  }
+
+  impl UniformKey for Keys {
+    fn name(&self) -> &str {
+      match self {
+        Keys::Present => "present",
+        Keys::Missing => "missing",
+      }
+    }
+  }
+
+  #[test]
+  fn resolves_names_from_tables() {
+    let api = RecordingRenderAPI::create(&["other", "present"], &["present"]);
+    assert_eq!(api.get_attribute(Keys::Present).ok(), Some(1));
+    assert_eq!(api.get_uniform(Keys::Present).ok(), Some(RecordingUniformIndex(0)));
+
+    match api.get_attribute(Keys::Missing) {
+      Err(RenderApiError::InvalidAttributeName(name)) => assert_eq!(name, "missing"),
+      other => panic!("expected invalid attribute, got {:?}", other),
+    }
+    match api.get_uniform(Keys::Missing) {
+      Err(RenderApiError::InvalidUniformName(name)) => assert_eq!(name, "missing"),
+      other => panic!("expected invalid uniform, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn records_calls_in_order() {
+    let api = RecordingRenderAPI::create(&["present"], &["present"]);
+    let buffer = api.create_buffer(BufferKind::ArrayBuffer).unwrap();
+    let view = Float32View::create(&[1.0]).unwrap();
+    api.buffer_data(&buffer, &view, DrawKind::StaticDraw).unwrap();
+    api.uniform2f(Keys::Present, 1.0, 2.0).unwrap();
+    api.clear(ClearMask::ColorBufferBit | ClearMask::DepthBufferBit);
+
+    assert_eq!(api.take_commands(), vec![
+      Command::CreateBuffer(buffer),
+      Command::BufferData {
+        buffer,
+        precision: ViewPrecision::Float,
+        data: 1.0f32.to_le_bytes().to_vec(),
+        draw_kind: DrawKind::StaticDraw,
+      },
+      Command::SetUniform { index: RecordingUniformIndex(0), value: UniformValue::Vec2([1.0, 2.0]) },
+      Command::Clear(ClearMasks { color: true, depth: true, stencil: false }),
+    ]);
+    assert_eq!(api.commands(), vec![]);
+  }
+
+  #[test]
+  fn rejects_texture_data_of_the_wrong_size() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    let texture = api.create_texture().unwrap();
+    let pixels = Uint8View::create(&[255; 12]).unwrap();
+
+    match api.tex_image_2d(&texture, 2, 2, &pixels) {
+      Err(RenderApiError::InvalidTextureData { expected, actual }) => {
+        assert_eq!((expected, actual), (16, 12));
+      },
+      other => panic!("expected invalid texture data, got {:?}", other),
+    }
+    assert_eq!(api.take_commands(), vec![Command::CreateTexture(texture)]);
+  }
+
+  #[test]
+  fn uniform_setters_record_typed_values() {
+    let api = RecordingRenderAPI::create(&[], &["present"]);
+    let index = RecordingUniformIndex(0);
+    api.uniform4f(Keys::Present, 1.0, 0.5, 0.0, 1.0).unwrap();
+    api.uniform2iv(Keys::Present, &[[1, 2], [3, 4]]).unwrap();
+    api.uniform_matrix2fv(Keys::Present, &Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0])).unwrap();
+    assert!(api.uniform_matrix4fv(Keys::Present, &Matrix::new(2, 2, vec![0.0; 4])).is_err());
+
+    assert_eq!(api.take_commands(), vec![
+      Command::SetUniform { index, value: UniformValue::Vec4([1.0, 0.5, 0.0, 1.0]) },
+      Command::SetUniform { index, value: UniformValue::IVec2Array(vec![[1, 2], [3, 4]]) },
+      Command::SetUniform { index, value: UniformValue::Mat2([1.0, 3.0, 2.0, 4.0]) },
+    ]);
+  }
+
+  #[test]
+  fn resolves_uniform_blocks_from_table() {
+    let api = RecordingRenderAPI::create(&[], &["present"]).with_uniform_blocks(&["other", "present"]);
+    let buffer = api.create_buffer(BufferKind::UniformBuffer).unwrap();
+    api.bind_buffer_base(&buffer, 2);
+    api.uniform_block_binding(Keys::Present, 2).unwrap();
+    assert!(api.uniform_block_binding(Keys::Missing, 2).is_err());
+
+    assert_eq!(api.take_commands(), vec![
+      Command::CreateBuffer(buffer),
+      Command::BindBufferBase { buffer, index: 2 },
+      Command::UniformBlockBinding { block: RecordingUniformBlockIndex(1), binding: 2 },
+    ]);
+  }
+
+  #[test]
+  fn lookups_use_the_tables_of_the_program_in_use() {
+    let api = RecordingRenderAPI::create(&["present"], &[]);
+    let program = api.add_program(&["other", "present"], &["present"]);
+    assert!(api.get_uniform(Keys::Present).is_err());
+
+    api.use_program(&program);
+    assert_eq!(api.get_attribute(Keys::Present).ok(), Some(1));
+    assert_eq!(api.get_uniform(Keys::Present).ok(), Some(RecordingUniformIndex(0)));
+
+    api.use_program(&RecordingProgram(0));
+    assert_eq!(api.get_attribute(Keys::Present).ok(), Some(0));
+    assert_eq!(api.take_commands(), vec![
+      Command::UseProgram(RecordingProgram(1)),
+      Command::UseProgram(RecordingProgram(0)),
+    ]);
+  }
+
+  #[test]
+  fn failed_lookups_are_not_recorded() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    assert!(api.enable_vertex_attrib_array(Keys::Missing).is_err());
+    assert!(api.uniform2f(Keys::Missing, 0.0, 0.0).is_err());
+    assert_eq!(api.commands(), vec![]);
+  }
+}
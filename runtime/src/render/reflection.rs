@@ -0,0 +1,233 @@
+use super::api::{AttributeIndex, UniformValue};
+use super::constants::{ShaderType};
+use super::locations::{DeclaredKey, ProgramKeys};
+use std::fmt;
+
+/**
+ * The active attributes & uniforms of a linked program, as
+ * reported by `getActiveAttrib` & `getActiveUniform`. Inactive
+ * variables are removed by the compiler, so they're missing
+ * even if they're declared in the source.
+ *
+ * Variables are named as GL reports them, where arrays are
+ * named after their first element, but are looked up by the
+ * name of the array. Uniforms in a uniform block have no
+ * location.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgramReflection<U> {
+  pub attributes: Vec<ActiveVariable<AttributeIndex>>,
+  pub uniforms: Vec<ActiveVariable<Option<U>>>,
+}
+
+/**
+ * An attribute or uniform, where `size` is the length of an
+ * array & 1 for anything else.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActiveVariable<L> {
+  pub name: String,
+  pub kind: ShaderType,
+  pub size: i32,
+  pub location: L,
+}
+
+impl<U> ProgramReflection<U> {
+  pub fn attribute(&self, name: &str) -> Option<&ActiveVariable<AttributeIndex>> {
+    self.attributes.iter().find(|a| variable_name(&a.name) == name)
+  }
+
+  pub fn uniform(&self, name: &str) -> Option<&ActiveVariable<Option<U>>> {
+    self.uniforms.iter().find(|u| variable_name(&u.name) == name)
+  }
+
+  /**
+   * Compares the keys with the active variables, returning
+   * every key that's missing or declared with another type.
+   * Keys without a declared type are only checked for.
+   */
+  pub fn check_keys(&self, keys: &ProgramKeys) -> Result<(), Vec<ReflectionMismatch>> {
+    let attributes = keys.attributes().iter().filter_map(|key| {
+      match self.attribute(&key.name) {
+        None => Some(ReflectionMismatch::MissingAttribute(key.name.clone())),
+        Some(active) => type_mismatch(key, active.kind).map(ReflectionMismatch::AttributeType),
+      }
+    });
+    let uniforms = keys.uniforms().iter().filter_map(|key| {
+      match self.uniform(&key.name) {
+        None => Some(ReflectionMismatch::MissingUniform(key.name.clone())),
+        Some(active) => type_mismatch(key, active.kind).map(ReflectionMismatch::UniformType),
+      }
+    });
+
+    let mismatches: Vec<ReflectionMismatch> = attributes.chain(uniforms).collect();
+    if mismatches.is_empty() { Ok(()) } else { Err(mismatches) }
+  }
+
+  /**
+   * Checks GL would accept `value` for the uniform, such as
+   * a `vec2` uniform only being set with `uniform2f`.
+   */
+  pub fn check_uniform_value(&self, name: &str, value: &UniformValue) -> Result<(), ReflectionMismatch> {
+    let active = self.uniform(name).ok_or_else(|| ReflectionMismatch::MissingUniform(name.to_string()))?;
+    if value.can_set(active.kind) {
+      Ok(())
+    } else {
+      Err(ReflectionMismatch::UniformValue(TypeMismatch {
+        name: name.to_string(),
+        declared: value.shader_type(),
+        active: active.kind,
+      }))
+    }
+  }
+}
+
+fn type_mismatch(key: &DeclaredKey, active: ShaderType) -> Option<TypeMismatch> {
+  match key.kind {
+    Some(declared) if declared != active => Some(TypeMismatch { name: key.name.clone(), declared, active }),
+    _ => None,
+  }
+}
+
+fn variable_name(name: &str) -> &str {
+  name.strip_suffix("[0]").unwrap_or(name)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeMismatch {
+  pub name: String,
+  pub declared: ShaderType,
+  pub active: ShaderType,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReflectionMismatch {
+  MissingAttribute(String),
+  MissingUniform(String),
+  AttributeType(TypeMismatch),
+  UniformType(TypeMismatch),
+  /**
+   * The uniform was set with a value of the `declared` type.
+   */
+  UniformValue(TypeMismatch),
+}
+
+impl fmt::Display for ReflectionMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ReflectionMismatch::MissingAttribute(name) => write!(f, "attribute {} isn't active", name),
+      ReflectionMismatch::MissingUniform(name) => write!(f, "uniform {} isn't active", name),
+      ReflectionMismatch::AttributeType(m) => write!(
+        f, "attribute {} is declared {} but is {} in the shader",
+        m.name, m.declared.glsl_name(), m.active.glsl_name(),
+      ),
+      ReflectionMismatch::UniformType(m) => write!(
+        f, "uniform {} is declared {} but is {} in the shader",
+        m.name, m.declared.glsl_name(), m.active.glsl_name(),
+      ),
+      ReflectionMismatch::UniformValue(m) => write!(
+        f, "uniform {} is {} in the shader but was set with a {}",
+        m.name, m.active.glsl_name(), m.declared.glsl_name(),
+      ),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::api::{AttributeKey, UniformKey};
+
+  #[derive(Clone, Copy)]
+  enum Keys {
+    Position,
+    Resolution,
+    Sampler,
+  }
+
+  impl AttributeKey for Keys {
+    fn name(&self) -> &str {
+      UniformKey::name(self)
+    }
+
+    fn shader_type(&self) -> Option<ShaderType> {
+      UniformKey::shader_type(self)
+    }
+  }
+
+  impl UniformKey for Keys {
+    fn name(&self) -> &str {
+      match self {
+        Keys::Position => "position",
+        Keys::Resolution => "resolution",
+        Keys::Sampler => "sampler",
+      }
+    }
+
+    fn shader_type(&self) -> Option<ShaderType> {
+      match self {
+        Keys::Position => Some(ShaderType::FloatVec2),
+        Keys::Resolution => Some(ShaderType::FloatVec2),
+        Keys::Sampler => None,
+      }
+    }
+  }
+
+  fn variable<L>(name: &str, kind: ShaderType, location: L) -> ActiveVariable<L> {
+    ActiveVariable { name: name.to_string(), kind, size: 1, location }
+  }
+
+  fn reflection() -> ProgramReflection<usize> {
+    ProgramReflection {
+      attributes: vec![variable("position", ShaderType::FloatVec2, 0)],
+      uniforms: vec![
+        variable("resolution", ShaderType::FloatVec3, Some(0)),
+        variable("sampler", ShaderType::Sampler2D, Some(1)),
+      ],
+    }
+  }
+
+  #[test]
+  fn check_keys_reports_types_that_differ() {
+    let keys = ProgramKeys::create()
+      .with_attributes(&[Keys::Position])
+      .with_uniforms(&[Keys::Resolution, Keys::Sampler]);
+
+    assert_eq!(reflection().check_keys(&keys), Err(vec![
+      ReflectionMismatch::UniformType(TypeMismatch {
+        name: "resolution".to_string(),
+        declared: ShaderType::FloatVec2,
+        active: ShaderType::FloatVec3,
+      }),
+    ]));
+  }
+
+  #[test]
+  fn check_keys_reports_inactive_keys() {
+    let keys = ProgramKeys::create().with_attributes(&[Keys::Sampler]);
+    assert_eq!(reflection().check_keys(&keys), Err(vec![
+      ReflectionMismatch::MissingAttribute("sampler".to_string()),
+    ]));
+  }
+
+  #[test]
+  fn uniform_values_have_to_match_the_setter() {
+    let reflection = reflection();
+    assert!(reflection.check_uniform_value("resolution", &UniformValue::Vec3([0.0; 3])).is_ok());
+    assert!(reflection.check_uniform_value("sampler", &UniformValue::Int(0)).is_ok());
+    assert!(reflection.check_uniform_value("sampler", &UniformValue::Float(0.0)).is_err());
+
+    let mismatch = reflection.check_uniform_value("resolution", &UniformValue::Vec2([0.0; 2])).unwrap_err();
+    assert_eq!(mismatch.to_string(), "uniform resolution is vec3 in the shader but was set with a vec2");
+  }
+
+  #[test]
+  fn arrays_are_looked_up_without_their_index() {
+    let reflection: ProgramReflection<usize> = ProgramReflection {
+      attributes: vec![],
+      uniforms: vec![ActiveVariable { name: "lights[0]".to_string(), kind: ShaderType::FloatVec3, size: 4, location: None }],
+    };
+    assert_eq!(reflection.uniform("lights").map(|u| u.size), Some(4));
+    assert!(reflection.uniform("lights[0]").is_none());
+  }
+}
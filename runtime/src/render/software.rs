@@ -0,0 +1,2236 @@
+use std::cell::RefCell;
+use super::api::{
+  check_texture_data,
+  AttributeIndex,
+  AttributeKey,
+  IntoAttributeIndex,
+  IntoUniformBlockIndex,
+  IntoUniformIndex,
+  RenderAPI,
+  RenderApiError,
+  UniformBlockKey,
+  UniformKey,
+  UniformValue,
+};
+use super::constants::{
+  BufferKind,
+  BlendFuncFactor,
+  ClearMasks,
+  CompareFunc,
+  CullFace,
+  DrawArrayKind,
+  DrawKind,
+  FramebufferAttachment,
+  FramebufferStatus,
+  IndexKind,
+  RenderbufferFormat,
+  StencilOp,
+  TextureMagFilter,
+  TextureMinFilter,
+  TextureParameter,
+  TextureUnit,
+  TextureWrap,
+  ViewPrecision,
+  HasBufferKind,
+};
+use super::data::{HalfFloat, Uint8View, View};
+use super::pipeline::{ColorMask, PipelineState, StencilState};
+
+/**
+ * The programmable part of the `SoftwareRenderAPI`, fills the
+ * role of a linked vertex & fragment shader.
+ *
+ * Attribute & uniform locations are the position of their
+ * name in `attributes` & `uniforms`, and their values are
+ * passed to the stages in that same order. Uniforms that
+ * were never set are zeroed, like they would be in GL, and
+ * every uniform is passed as floats with matrices in column
+ * major order.
+ */
+pub trait SoftwareShader {
+  fn attributes(&self) -> Vec<&'static str>;
+
+  fn uniforms(&self) -> Vec<&'static str>;
+
+  /**
+   * Uniform blocks are passed after the uniforms, as the
+   * floats of the std140 data in the uniform buffer bound to
+   * the binding point of the block.
+   */
+  fn uniform_blocks(&self) -> Vec<&'static str> {
+    vec![]
+  }
+
+  /**
+   * Runs once per vertex, each attribute is padded out to 4
+   * components with the defaults of `(0, 0, 0, 1)`.
+   */
+  fn vertex(&self, attributes: &[[f32; 4]], uniforms: &[Vec<f32>]) -> ShadedVertex;
+
+  /**
+   * Runs once per covered pixel with the varyings of the
+   * vertex stage interpolated across the primitive, returns
+   * the RGBA colour in the range of 0 to 1.
+   */
+  fn fragment(&self, varyings: &[f32], uniforms: &[Vec<f32>], samplers: &Samplers) -> [f32; 4];
+}
+
+/**
+ * The output of `SoftwareShader::vertex`, `position` is the
+ * equivalent of `gl_Position` in clip space.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShadedVertex {
+  pub position: [f32; 4],
+  pub varyings: Vec<f32>,
+}
+
+/**
+ * Rust equivalent of `shaders/vert_shader.glsl` and
+ * `shaders/frag_shader.glsl`, these need to be kept in sync.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct GridShader;
+
+impl SoftwareShader for GridShader {
+  fn attributes(&self) -> Vec<&'static str> {
+    vec!["position", "cell"]
+  }
+
+  fn uniforms(&self) -> Vec<&'static str> {
+    vec!["resolution", "origin", "spacing", "size"]
+  }
+
+  fn vertex(&self, attributes: &[[f32; 4]], uniforms: &[Vec<f32>]) -> ShadedVertex {
+    let (position, cell) = (attributes[0], attributes[1]);
+    let (resolution, origin) = (&uniforms[0], &uniforms[1]);
+    let (spacing, size) = (uniforms[2][0], uniforms[3][0]);
+
+    // the corner of the square this instance is in pixels.
+    let pixels_x = (origin[0] + (cell[0] * spacing)) + (position[0] * size);
+    let pixels_y = (origin[1] + (cell[1] * spacing)) + (position[1] * size);
+
+    // pixels to 0->1, then to -1->+1 with y flipped.
+    let clip_x = ((pixels_x / resolution[0]) * 2.0) - 1.0;
+    let clip_y = 1.0 - ((pixels_y / resolution[1]) * 2.0);
+    let position = [clip_x, clip_y, 0.0, 1.0];
+    let varyings = position.iter().map(|c| c * 0.5 + 0.5).collect();
+    ShadedVertex { position, varyings }
+  }
+
+  fn fragment(&self, varyings: &[f32], _: &[Vec<f32>], _: &Samplers) -> [f32; 4] {
+    let mut color = [0.0; 4];
+    for (out, v_color) in color.iter_mut().zip(varyings) {
+      *out = v_color + 0.25;
+    }
+    color
+  }
+}
+
+/**
+ * The textures bound to each texture unit, which is what a
+ * `SoftwareShader` reads from in place of `sampler2D`s.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct Samplers<'a> {
+  textures: &'a [TextureStore],
+  units: &'a [Option<usize>],
+}
+
+impl<'a> Samplers<'a> {
+  /**
+   * The equivalent of `texture(sampler, coord)` in GLSL, where
+   * `unit` is the value of the sampler uniform. The bottom row
+   * of the texture is the first row of the uploaded pixels.
+   *
+   * As there are no derivatives to pick a mipmap level with,
+   * the magnification filter is always used. The minification
+   * filter only decides if the texture is complete, incomplete
+   * & unbound textures sample as opaque black like in GL.
+   */
+  pub fn texture(&self, unit: usize, coord: [f32; 2]) -> [f32; 4] {
+    let texture = match self.units.get(unit).cloned().flatten() {
+      Some(id) => &self.textures[id],
+      None => return [0.0, 0.0, 0.0, 1.0],
+    };
+    if !texture.is_complete() {
+      return [0.0, 0.0, 0.0, 1.0];
+    }
+
+    let x = coord[0] * texture.width as f32;
+    let y = coord[1] * texture.height as f32;
+    match texture.mag_filter {
+      TextureMagFilter::Nearest => texture.texel(x.floor() as i64, y.floor() as i64),
+      TextureMagFilter::Linear => {
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor() as i64, y.floor() as i64);
+        let (fx, fy) = (x - x.floor(), y - y.floor());
+        let weights = [
+          (x0, y0, (1.0 - fx) * (1.0 - fy)),
+          (x0 + 1, y0, fx * (1.0 - fy)),
+          (x0, y0 + 1, (1.0 - fx) * fy),
+          (x0 + 1, y0 + 1, fx * fy),
+        ];
+
+        let mut color = [0.0; 4];
+        for (tx, ty, weight) in weights.iter() {
+          let texel = texture.texel(*tx, *ty);
+          for c in 0..4 {
+            color[c] += texel[c] * weight;
+          }
+        }
+        color
+      },
+    }
+  }
+}
+
+/**
+ * A `RenderAPI` that rasterizes on the CPU into an RGBA8
+ * framebuffer, so a scene can be rendered without a GPU.
+ *
+ * The canvas is stored top row first like an image, whereas
+ * viewport coordinates start at the bottom left like they do
+ * in GL. The canvas has an 8 bit stencil buffer & a depth
+ * buffer, which a framebuffer only has with a depth or stencil
+ * renderbuffer attached, and only the first colour attachment
+ * of a framebuffer is drawn to. There is no clipping against
+ * the near & far planes, so primitives with a vertex behind
+ * the camera are skipped.
+ *
+ * Each program is a `SoftwareShader`, where the API is
+ * created using the first one & more are added with
+ * `add_program`.
+ */
+#[derive(Debug)]
+pub struct SoftwareRenderAPI<S> {
+  shaders: RefCell<Vec<S>>,
+  state: RefCell<SoftwareState>,
+}
+
+/**
+ * The number of attribute locations every vertex array has,
+ * which is the least WebGL 2 supports.
+ */
+const MAX_VERTEX_ATTRIBS: usize = 16;
+
+/**
+ * Like GL, handles this context didn't create are ignored
+ * where they're passed in, so the ids kept here always refer
+ * to something in the state & can be indexed with.
+ */
+#[derive(Debug)]
+struct SoftwareState {
+  width: usize,
+  height: usize,
+  pixels: Vec<u8>,
+  viewport: (i32, i32, i32, i32),
+  clear_color: [f32; 4],
+  blend_color: [f32; 4],
+  blend_func: (BlendFuncFactor, BlendFuncFactor),
+  pipeline: PipelineState,
+  depth: Vec<f32>,
+  stencil: Vec<u8>,
+  buffers: Vec<BufferStore>,
+  array_buffer: Option<usize>,
+  vertex_arrays: Vec<VertexArrayStore>,
+  vertex_array: usize,
+  programs: Vec<ProgramStore>,
+  program: usize,
+  uniform_buffers: Vec<Option<usize>>,
+  textures: Vec<TextureStore>,
+  texture_units: Vec<Option<usize>>,
+  active_texture: usize,
+  framebuffers: Vec<FramebufferStore>,
+  renderbuffers: Vec<RenderbufferStore>,
+  framebuffer: Option<usize>,
+}
+
+/**
+ * Something that can be drawn to, every image other than the
+ * canvas is stored bottom row first as it is in GL.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Image {
+  Canvas,
+  Texture(usize),
+  Renderbuffer(usize),
+}
+
+/**
+ * The names a program's locations are looked up in, & the
+ * uniform values set while it was in use.
+ */
+#[derive(Clone, Debug)]
+struct ProgramStore {
+  attribute_names: Vec<&'static str>,
+  uniform_names: Vec<&'static str>,
+  uniform_block_names: Vec<&'static str>,
+  uniforms: Vec<Vec<f32>>,
+  uniform_block_bindings: Vec<u32>,
+}
+
+impl ProgramStore {
+  fn create<S>(shader: &S) -> Self where S: SoftwareShader {
+    let uniform_names = shader.uniforms();
+    let uniform_block_names = shader.uniform_blocks();
+    ProgramStore {
+      attribute_names: shader.attributes(),
+      uniforms: vec![vec![]; uniform_names.len()],
+      uniform_block_bindings: vec![0; uniform_block_names.len()],
+      uniform_names,
+      uniform_block_names,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+struct FramebufferStore {
+  attachments: Vec<(FramebufferAttachment, Image)>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct RenderbufferStore {
+  format: Option<RenderbufferFormat>,
+  width: usize,
+  height: usize,
+  pixels: Vec<u8>,
+  depth: Vec<f32>,
+  stencil: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct BufferStore {
+  precision: Option<ViewPrecision>,
+  data: Vec<u8>,
+}
+
+/**
+ * An RGBA8 texture with the sampler parameters defaulting
+ * to the same values as in GL.
+ */
+#[derive(Clone, Debug)]
+struct TextureStore {
+  width: usize,
+  height: usize,
+  pixels: Vec<u8>,
+  mipmapped: bool,
+  mag_filter: TextureMagFilter,
+  min_filter: TextureMinFilter,
+  wrap_s: TextureWrap,
+  wrap_t: TextureWrap,
+}
+
+impl Default for TextureStore {
+  fn default() -> Self {
+    TextureStore {
+      width: 0,
+      height: 0,
+      pixels: vec![],
+      mipmapped: false,
+      mag_filter: TextureMagFilter::Linear,
+      min_filter: TextureMinFilter::NearestMipmapLinear,
+      wrap_s: TextureWrap::Repeat,
+      wrap_t: TextureWrap::Repeat,
+    }
+  }
+}
+
+impl TextureStore {
+  fn is_complete(&self) -> bool {
+    let has_image = self.width > 0 && self.height > 0;
+    has_image && (self.mipmapped || !self.min_filter.uses_mipmaps())
+  }
+
+  fn texel(&self, x: i64, y: i64) -> [f32; 4] {
+    let x = wrap(x, self.width, self.wrap_s);
+    let y = wrap(y, self.height, self.wrap_t);
+    let i = (y * self.width + x) * 4;
+    let texel = &self.pixels[i..i + 4];
+    [
+      texel[0] as f32 / 255.0,
+      texel[1] as f32 / 255.0,
+      texel[2] as f32 / 255.0,
+      texel[3] as f32 / 255.0,
+    ]
+  }
+}
+
+/**
+ * The attribute pointers & element buffer of a vertex array,
+ * where the first vertex array is the default one.
+ */
+#[derive(Clone, Debug)]
+struct VertexArrayStore {
+  element_buffer: Option<usize>,
+  attributes: Vec<AttributePointer>,
+}
+
+impl VertexArrayStore {
+  fn create(attribute_count: usize) -> Self {
+    VertexArrayStore {
+      element_buffer: None,
+      attributes: vec![AttributePointer::default(); attribute_count],
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct AttributePointer {
+  enabled: bool,
+  buffer: Option<usize>,
+  size: i32,
+  precision: Option<ViewPrecision>,
+  normalized: bool,
+  stride: i32,
+  offset: i32,
+  divisor: u32,
+}
+
+impl<S> SoftwareRenderAPI<S> where S: SoftwareShader {
+  pub fn create(shader: S, width: usize, height: usize) -> Self {
+    let state = SoftwareState {
+      width,
+      height,
+      pixels: vec![0; width * height * 4],
+      viewport: (0, 0, width as i32, height as i32),
+      clear_color: [0.0; 4],
+      blend_color: [0.0; 4],
+      blend_func: (BlendFuncFactor::One, BlendFuncFactor::Zero),
+      pipeline: PipelineState::default(),
+      depth: vec![1.0; width * height],
+      stencil: vec![0; width * height],
+      buffers: vec![],
+      array_buffer: None,
+      vertex_arrays: vec![VertexArrayStore::create(MAX_VERTEX_ATTRIBS)],
+      vertex_array: 0,
+      programs: vec![ProgramStore::create(&shader)],
+      program: 0,
+      uniform_buffers: vec![],
+      textures: vec![],
+      texture_units: vec![],
+      active_texture: 0,
+      framebuffers: vec![],
+      renderbuffers: vec![],
+      framebuffer: None,
+    };
+
+    SoftwareRenderAPI {
+      shaders: RefCell::new(vec![shader]),
+      state: RefCell::new(state),
+    }
+  }
+
+  /**
+   * Adds a program that runs `shader`, which isn't used until
+   * it's passed to `use_program`.
+   */
+  pub fn add_program(&self, shader: S) -> SoftwareProgram {
+    let mut shaders = self.shaders.borrow_mut();
+    let mut state = self.state.borrow_mut();
+    state.programs.push(ProgramStore::create(&shader));
+    shaders.push(shader);
+    SoftwareProgram { id: shaders.len() - 1 }
+  }
+
+  pub fn width(&self) -> usize {
+    self.state.borrow().width
+  }
+
+  pub fn height(&self) -> usize {
+    self.state.borrow().height
+  }
+
+  /**
+   * A copy of the canvas as RGBA8, top row first, regardless
+   * of the framebuffer that's bound.
+   */
+  pub fn canvas_pixels(&self) -> Vec<u8> {
+    self.state.borrow().pixels.clone()
+  }
+
+  /**
+   * The RGBA8 value of a pixel of the canvas, where `(0, 0)`
+   * is the top left.
+   */
+  pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+    let state = self.state.borrow();
+    let i = (y * state.width + x) * 4;
+    [state.pixels[i], state.pixels[i + 1], state.pixels[i + 2], state.pixels[i + 3]]
+  }
+}
+
+impl<S> RenderAPI for SoftwareRenderAPI<S> where S: SoftwareShader {
+  type Buffer = SoftwareBuffer;
+  type Program = SoftwareProgram;
+  type UniformIndex = usize;
+  type UniformBlockIndex = usize;
+  type Texture = SoftwareTexture;
+  type Framebuffer = SoftwareFramebuffer;
+  type Renderbuffer = SoftwareRenderbuffer;
+  type VertexArray = SoftwareVertexArray;
+
+  fn bind_buffer(&self, buffer: &Self::Buffer) {
+    let mut state = self.state.borrow_mut();
+    // like GL a buffer from another context can't be bound.
+    if buffer.id >= state.buffers.len() {
+      return;
+    }
+    match buffer.kind {
+      BufferKind::ArrayBuffer => state.array_buffer = Some(buffer.id),
+      BufferKind::ElementBuffer => state.vertex_array_mut().element_buffer = Some(buffer.id),
+      BufferKind::UniformBuffer => {},
+    }
+  }
+
+  fn bind_buffer_base(&self, buffer: &Self::Buffer, index: u32) {
+    if let BufferKind::UniformBuffer = buffer.kind {
+      let mut state = self.state.borrow_mut();
+      if buffer.id >= state.buffers.len() {
+        return;
+      }
+      let index = index as usize;
+      if state.uniform_buffers.len() <= index {
+        state.uniform_buffers.resize(index + 1, None);
+      }
+      state.uniform_buffers[index] = Some(buffer.id);
+    }
+  }
+
+  fn bind_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>) {
+    let mut state = self.state.borrow_mut();
+    match framebuffer {
+      Some(framebuffer) if framebuffer.id >= state.framebuffers.len() => {},
+      _ => state.framebuffer = framebuffer.map(|f| f.id),
+    }
+  }
+
+  /**
+   * The unit is made active even if the texture is unknown,
+   * as `activeTexture` is a call of its own in GL.
+   */
+  fn bind_texture(&self, unit: TextureUnit, texture: &Self::Texture) {
+    let mut state = self.state.borrow_mut();
+    state.active_texture = unit.0 as usize;
+    if texture.id < state.textures.len() {
+      state.bind_texture(texture.id);
+    }
+  }
+
+  fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>) {
+    let mut state = self.state.borrow_mut();
+    match vertex_array {
+      Some(vertex_array) if vertex_array.id >= state.vertex_arrays.len() => {},
+      _ => state.vertex_array = vertex_array.map_or(0, |v| v.id),
+    }
+  }
+
+  fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    self.state.borrow_mut().blend_color = [red, green, blue, alpha];
+  }
+
+  fn blend_func(&self, src: BlendFuncFactor, dst: BlendFuncFactor) {
+    self.state.borrow_mut().blend_func = (src, dst);
+  }
+
+  fn buffer_data<V>(
+      &self,
+      buffer: &Self::Buffer,
+      view: &V,
+      _draw_kind: DrawKind,
+  ) -> Result<(), RenderApiError> where V: View {
+    self.bind_buffer(buffer);
+    if let Some(store) = self.state.borrow_mut().buffers.get_mut(buffer.id) {
+      store.precision = Some(view.get_precision());
+      store.data = view.to_bytes();
+    }
+    Ok(())
+  }
+
+  fn buffer_data_with_size(&self, buffer: &Self::Buffer, size: usize, _draw_kind: DrawKind) {
+    self.bind_buffer(buffer);
+    if let Some(store) = self.state.borrow_mut().buffers.get_mut(buffer.id) {
+      store.precision = None;
+      store.data = vec![0; size];
+    }
+  }
+
+  fn buffer_sub_data<V>(&self, buffer: &Self::Buffer, offset: usize, view: &V) -> Result<(), RenderApiError> where V: View {
+    self.bind_buffer(buffer);
+    let mut state = self.state.borrow_mut();
+    let store = match state.buffers.get_mut(buffer.id) {
+      Some(store) => store,
+      None => return Ok(()),
+    };
+    let bytes = view.to_bytes();
+    // like GL a write past the end of the buffer is ignored.
+    if let Some(target) = store.data.get_mut(offset..offset + bytes.len()) {
+      target.copy_from_slice(&bytes);
+      store.precision = Some(view.get_precision());
+    }
+    Ok(())
+  }
+
+  fn check_framebuffer_status(&self, framebuffer: &Self::Framebuffer) -> Result<(), RenderApiError> {
+    self.bind_framebuffer(Some(framebuffer));
+    let state = self.state.borrow();
+    state.framebuffer_status().map_err(RenderApiError::IncompleteFramebuffer)
+  }
+
+  fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    self.state.borrow_mut().clear_color = [red, green, blue, alpha];
+  }
+
+  /**
+   * Like GL, clears are limited to the scissor rect & by the
+   * colour, depth & stencil write masks, but not the viewport.
+   */
+  fn clear<M>(&self, mask: M) where M: Into<ClearMasks> {
+    let mask = mask.into();
+    let mut state = self.state.borrow_mut();
+    if state.framebuffer_status().is_err() {
+      return;
+    }
+
+    let (min_x, min_y, max_x, max_y) = state.scissor_bounds();
+    let color = state.color_image().filter(|_| mask.color).map(|image| (image, to_rgba8(state.clear_color)));
+    let (depth, stencil) = state.depth_stencil_images();
+    let depth = depth.filter(|_| mask.depth && state.pipeline.depth.is_none_or(|d| d.write));
+    let stencil = stencil.filter(|_| mask.stencil);
+    let stencil_mask = state.pipeline.stencil.map_or(!0, |s| s.write_mask as u8);
+    let width = state.target_size().0;
+
+    for y in min_y..max_y {
+      for x in min_x..max_x {
+        if let Some((image, color)) = color {
+          let i = state.pixel_index(image, x, y);
+          state.store_color(image, i, color);
+        }
+        let i = y as usize * width + x as usize;
+        if let Some(value) = depth.and_then(|image| state.depth_values_mut(image).get_mut(i)) {
+          *value = 1.0;
+        }
+        if let Some(value) = stencil.and_then(|image| state.stencil_values_mut(image).get_mut(i)) {
+          *value &= !stencil_mask;
+        }
+      }
+    }
+  }
+
+  fn create_buffer(&self, kind: BufferKind) -> Result<Self::Buffer, RenderApiError> {
+    let mut state = self.state.borrow_mut();
+    let id = state.buffers.len();
+    state.buffers.push(BufferStore::default());
+    Ok(SoftwareBuffer { id, kind })
+  }
+
+  fn create_framebuffer(&self) -> Result<Self::Framebuffer, RenderApiError> {
+    let mut state = self.state.borrow_mut();
+    let id = state.framebuffers.len();
+    state.framebuffers.push(FramebufferStore::default());
+    Ok(SoftwareFramebuffer { id })
+  }
+
+  fn create_renderbuffer(&self) -> Result<Self::Renderbuffer, RenderApiError> {
+    let mut state = self.state.borrow_mut();
+    let id = state.renderbuffers.len();
+    state.renderbuffers.push(RenderbufferStore::default());
+    Ok(SoftwareRenderbuffer { id })
+  }
+
+  fn create_texture(&self) -> Result<Self::Texture, RenderApiError> {
+    let mut state = self.state.borrow_mut();
+    let id = state.textures.len();
+    state.textures.push(TextureStore::default());
+    Ok(SoftwareTexture { id })
+  }
+
+  fn create_vertex_array(&self) -> Result<Self::VertexArray, RenderApiError> {
+    let mut state = self.state.borrow_mut();
+    let id = state.vertex_arrays.len();
+    state.vertex_arrays.push(VertexArrayStore::create(MAX_VERTEX_ATTRIBS));
+    Ok(SoftwareVertexArray { id })
+  }
+
+  fn draw_arrays(&self, mode: DrawArrayKind, first: i32, count: i32) {
+    self.draw_arrays_instanced(mode, first, count, 1);
+  }
+
+  /**
+   * Each instance is drawn in turn, with attributes that have
+   * a divisor read from the element for that instance.
+   */
+  fn draw_arrays_instanced(&self, mode: DrawArrayKind, first: i32, count: i32, instance_count: i32) {
+    let mut state = self.state.borrow_mut();
+    let shader = &self.shaders.borrow()[state.program];
+    let first = first.max(0) as usize;
+    let indices: Vec<usize> = (first..first + count.max(0) as usize).collect();
+    for instance in 0..instance_count.max(0) as usize {
+      state.draw(shader, mode, &indices, instance);
+    }
+  }
+
+  fn draw_elements(&self, mode: DrawArrayKind, count: i32, kind: IndexKind, offset: i32) {
+    self.draw_elements_instanced(mode, count, kind, offset, 1);
+  }
+
+  /**
+   * Like `draw_arrays_instanced`, each instance is drawn in
+   * turn with its own values of the per instance attributes.
+   */
+  fn draw_elements_instanced(
+      &self,
+      mode: DrawArrayKind,
+      count: i32,
+      kind: IndexKind,
+      offset: i32,
+      instance_count: i32,
+  ) {
+    let mut state = self.state.borrow_mut();
+    let indices = match state.read_indices(count, kind, offset) {
+      Some(indices) => indices,
+      // like WebGL, reading outside of a buffer draws nothing.
+      None => return,
+    };
+
+    let shader = &self.shaders.borrow()[state.program];
+    for instance in 0..instance_count.max(0) as usize {
+      state.draw(shader, mode, &indices, instance);
+    }
+  }
+
+  fn enable_vertex_attrib_array<A>(&self, key: A) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|i| {
+      if let Some(pointer) = self.state.borrow_mut().attribute_mut(i) {
+        pointer.enabled = true;
+      }
+    })
+  }
+
+  fn framebuffer_renderbuffer(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      renderbuffer: &Self::Renderbuffer,
+  ) {
+    let mut state = self.state.borrow_mut();
+    state.attach(framebuffer.id, attachment, Image::Renderbuffer(renderbuffer.id));
+  }
+
+  fn framebuffer_texture_2d(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      texture: &Self::Texture,
+  ) {
+    let mut state = self.state.borrow_mut();
+    state.attach(framebuffer.id, attachment, Image::Texture(texture.id));
+  }
+
+  fn generate_mipmap(&self, texture: &Self::Texture) {
+    if let Some(store) = self.state.borrow_mut().textures.get_mut(texture.id) {
+      store.mipmapped = !store.pixels.is_empty();
+    }
+  }
+
+  fn get_attribute<AK>(&self, key: AK) -> Result<AttributeIndex, RenderApiError> where AK: AttributeKey {
+    let name = key.name();
+    let state = self.state.borrow();
+    state.programs[state.program].attribute_names.iter()
+      .position(|a| *a == name)
+      .map(|i| i as AttributeIndex)
+      .ok_or_else(|| RenderApiError::InvalidAttributeName(name.to_string()))
+  }
+
+  fn get_uniform<UK>(&self, key: UK) -> Result<Self::UniformIndex, RenderApiError> where UK: UniformKey {
+    let name = key.name();
+    let state = self.state.borrow();
+    state.programs[state.program].uniform_names.iter()
+      .position(|u| *u == name)
+      .ok_or_else(|| RenderApiError::InvalidUniformName(name.to_string()))
+  }
+
+  fn get_uniform_block<BK>(&self, key: BK) -> Result<Self::UniformBlockIndex, RenderApiError> where BK: UniformBlockKey {
+    let name = key.name();
+    let state = self.state.borrow();
+    state.programs[state.program].uniform_block_names.iter()
+      .position(|b| *b == name)
+      .ok_or_else(|| RenderApiError::InvalidUniformBlockName(name.to_string()))
+  }
+
+  fn read_pixels(&self, x: i32, y: i32, width: i32, height: i32) -> Result<Vec<u8>, RenderApiError> {
+    let state = self.state.borrow();
+    state.framebuffer_status().map_err(RenderApiError::IncompleteFramebuffer)?;
+    let image = state.color_image().ok_or(RenderApiError::FailedToReadPixels)?;
+    let (image_width, image_height) = state.image_size(image);
+    let pixels = state.image_pixels(image);
+
+    let mut output = vec![0; width.max(0) as usize * height.max(0) as usize * 4];
+    for row in 0..height.max(0) {
+      for column in 0..width.max(0) {
+        let (px, py) = (x + column, y + row);
+        if px < 0 || py < 0 || px as usize >= image_width || py as usize >= image_height {
+          continue;
+        }
+        let from = image_index(image, image_width, image_height, px as usize, py as usize);
+        let to = ((row * width + column) * 4) as usize;
+        output[to..to + 4].copy_from_slice(&pixels[from..from + 4]);
+      }
+    }
+    Ok(output)
+  }
+
+  /**
+   * Colour formats are stored as RGBA8 regardless of their
+   * format, & depth as `f32`.
+   */
+  fn renderbuffer_storage(
+      &self,
+      renderbuffer: &Self::Renderbuffer,
+      format: RenderbufferFormat,
+      width: i32,
+      height: i32,
+  ) {
+    let (width, height) = (width.max(0) as usize, height.max(0) as usize);
+    let is_color = format.can_attach_to(FramebufferAttachment::Color(0));
+    let is_depth_stencil = format.can_attach_to(FramebufferAttachment::DepthStencil);
+    let has_depth = is_depth_stencil || format.can_attach_to(FramebufferAttachment::Depth);
+    let has_stencil = is_depth_stencil || format.can_attach_to(FramebufferAttachment::Stencil);
+    let mut state = self.state.borrow_mut();
+    let store = match state.renderbuffers.get_mut(renderbuffer.id) {
+      Some(store) => store,
+      None => return,
+    };
+    store.format = Some(format);
+    store.width = width;
+    store.height = height;
+    store.pixels = if is_color { vec![0; width * height * 4] } else { vec![] };
+    store.depth = if has_depth { vec![1.0; width * height] } else { vec![] };
+    store.stencil = if has_stencil { vec![0; width * height] } else { vec![] };
+  }
+
+  fn set_pipeline_state(&self, state: &PipelineState) {
+    self.state.borrow_mut().pipeline = *state;
+  }
+
+  fn set_uniform<U>(&self, key: U, value: UniformValue) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    key.with_context(self).map(|index| {
+      self.state.borrow_mut().program_mut().uniforms[index] = uniform_components(value);
+    })
+  }
+
+  fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+    self.state.borrow_mut().viewport = (x, y, width, height);
+  }
+
+  /**
+   * Replacing the image leaves the old mipmaps out of date,
+   * so they have to be generated again, the same as in GL.
+   */
+  fn tex_image_2d(
+      &self,
+      texture: &Self::Texture,
+      width: i32,
+      height: i32,
+      pixels: &Uint8View,
+  ) -> Result<(), RenderApiError> {
+    check_texture_data(width, height, pixels)?;
+    let mut state = self.state.borrow_mut();
+    let store = match state.textures.get_mut(texture.id) {
+      Some(store) => store,
+      None => return Ok(()),
+    };
+    store.width = width as usize;
+    store.height = height as usize;
+    store.pixels = pixels.to_bytes();
+    store.mipmapped = false;
+    Ok(())
+  }
+
+  fn tex_parameter(&self, texture: &Self::Texture, parameter: TextureParameter) {
+    let mut state = self.state.borrow_mut();
+    let store = match state.textures.get_mut(texture.id) {
+      Some(store) => store,
+      None => return,
+    };
+    match parameter {
+      TextureParameter::MagFilter(filter) => store.mag_filter = filter,
+      TextureParameter::MinFilter(filter) => store.min_filter = filter,
+      TextureParameter::WrapS(wrap) => store.wrap_s = wrap,
+      TextureParameter::WrapT(wrap) => store.wrap_t = wrap,
+    }
+  }
+
+  fn uniform_block_binding<B>(&self, key: B, binding: u32) -> Result<(), RenderApiError> where B: IntoUniformBlockIndex {
+    key.with_context(self).map(|index| {
+      self.state.borrow_mut().program_mut().uniform_block_bindings[index] = binding;
+    })
+  }
+
+  fn use_program(&self, program: &Self::Program) {
+    let mut state = self.state.borrow_mut();
+    if program.id < state.programs.len() {
+      state.program = program.id;
+    }
+  }
+
+  fn vertex_attrib_divisor<A>(&self, key: A, divisor: u32) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| {
+      if let Some(pointer) = self.state.borrow_mut().attribute_mut(index) {
+        pointer.divisor = divisor;
+      }
+    })
+  }
+
+  /**
+   * Every attribute is a float in a software shader, so
+   * integers are read as they would be without normalizing.
+   */
+  fn vertex_attrib_i_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    self.vertex_attrib_pointer_with_i32(key, size, precision, false, stride, offset)
+  }
+
+  fn vertex_attrib_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      normalized: bool,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| {
+      let mut state = self.state.borrow_mut();
+      let buffer = state.array_buffer;
+      let pointer = match state.attribute_mut(index) {
+        Some(pointer) => pointer,
+        None => return,
+      };
+      pointer.buffer = buffer;
+      pointer.size = size;
+      pointer.precision = Some(precision);
+      pointer.normalized = normalized;
+      pointer.stride = stride;
+      pointer.offset = offset;
+    })
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftwareBuffer {
+  pub id: usize,
+  pub kind: BufferKind,
+}
+
+impl HasBufferKind for SoftwareBuffer {
+  fn buffer_kind_constant(&self) -> u32 {
+    self.kind.buffer_kind_constant()
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftwareProgram {
+  pub id: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftwareTexture {
+  pub id: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftwareFramebuffer {
+  pub id: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftwareRenderbuffer {
+  pub id: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftwareVertexArray {
+  pub id: usize,
+}
+
+/**
+ * A shaded vertex in framebuffer coordinates.
+ */
+#[derive(Clone, Debug)]
+struct WindowVertex {
+  x: f32,
+  y: f32,
+  z: f32,
+  inv_w: f32,
+  varyings: Vec<f32>,
+}
+
+impl SoftwareState {
+  fn bind_texture(&mut self, texture: usize) {
+    if self.texture_units.len() <= self.active_texture {
+      self.texture_units.resize(self.active_texture + 1, None);
+    }
+    self.texture_units[self.active_texture] = Some(texture);
+  }
+
+  fn attach(&mut self, framebuffer: usize, attachment: FramebufferAttachment, image: Image) {
+    let known = match image {
+      Image::Texture(id) => id < self.textures.len(),
+      Image::Renderbuffer(id) => id < self.renderbuffers.len(),
+      Image::Canvas => true,
+    };
+    if framebuffer >= self.framebuffers.len() || !known {
+      return;
+    }
+    self.framebuffer = Some(framebuffer);
+    let attachments = &mut self.framebuffers[framebuffer].attachments;
+    attachments.retain(|(a, _)| *a != attachment);
+    attachments.push((attachment, image));
+  }
+
+  /**
+   * Follows the completeness rules of WebGL 2, the canvas is
+   * always complete.
+   */
+  fn framebuffer_status(&self) -> Result<(), FramebufferStatus> {
+    let framebuffer = match self.framebuffer {
+      Some(framebuffer) => &self.framebuffers[framebuffer],
+      None => return Ok(()),
+    };
+    if framebuffer.attachments.is_empty() {
+      return Err(FramebufferStatus::IncompleteMissingAttachment);
+    }
+
+    let mut sizes = vec![];
+    for (attachment, image) in framebuffer.attachments.iter() {
+      let attachable = match image {
+        Image::Texture(_) => matches!(attachment, FramebufferAttachment::Color(_)),
+        Image::Renderbuffer(id) => self.renderbuffers[*id].format
+          .is_some_and(|format| format.can_attach_to(*attachment)),
+        Image::Canvas => false,
+      };
+      let size = self.image_size(*image);
+      if !attachable || size.0 == 0 || size.1 == 0 {
+        return Err(FramebufferStatus::IncompleteAttachment);
+      }
+      sizes.push(size);
+    }
+
+    if sizes.iter().any(|size| *size != sizes[0]) {
+      return Err(FramebufferStatus::IncompleteDimensions);
+    }
+    Ok(())
+  }
+
+  /**
+   * The image draws & clears write their colour to, which is
+   * `None` when the bound framebuffer is incomplete or has no
+   * colour attachment.
+   */
+  fn color_image(&self) -> Option<Image> {
+    let framebuffer = match self.framebuffer {
+      Some(framebuffer) => &self.framebuffers[framebuffer],
+      None => return Some(Image::Canvas),
+    };
+    self.framebuffer_status().ok()?;
+    framebuffer.attachments.iter()
+      .find(|(attachment, _)| *attachment == FramebufferAttachment::Color(0))
+      .map(|(_, image)| *image)
+  }
+
+  /**
+   * The images holding the depth & stencil values of the bound
+   * framebuffer, where either is `None` if there are no values
+   * to test against, so that test always passes.
+   */
+  fn depth_stencil_images(&self) -> (Option<Image>, Option<Image>) {
+    let framebuffer = match self.framebuffer {
+      Some(framebuffer) => &self.framebuffers[framebuffer],
+      None => return (Some(Image::Canvas), Some(Image::Canvas)),
+    };
+    let find = |attachment: FramebufferAttachment| framebuffer.attachments.iter()
+      .find(|(a, _)| *a == attachment || *a == FramebufferAttachment::DepthStencil)
+      .map(|(_, image)| *image);
+    (find(FramebufferAttachment::Depth), find(FramebufferAttachment::Stencil))
+  }
+
+  fn depth_values_mut(&mut self, image: Image) -> &mut Vec<f32> {
+    match image {
+      Image::Renderbuffer(id) => &mut self.renderbuffers[id].depth,
+      _ => &mut self.depth,
+    }
+  }
+
+  fn stencil_values_mut(&mut self, image: Image) -> &mut Vec<u8> {
+    match image {
+      Image::Renderbuffer(id) => &mut self.renderbuffers[id].stencil,
+      _ => &mut self.stencil,
+    }
+  }
+
+  fn image_size(&self, image: Image) -> (usize, usize) {
+    match image {
+      Image::Canvas => (self.width, self.height),
+      Image::Texture(id) => (self.textures[id].width, self.textures[id].height),
+      Image::Renderbuffer(id) => (self.renderbuffers[id].width, self.renderbuffers[id].height),
+    }
+  }
+
+  fn image_pixels(&self, image: Image) -> &[u8] {
+    match image {
+      Image::Canvas => &self.pixels,
+      Image::Texture(id) => &self.textures[id].pixels,
+      Image::Renderbuffer(id) => &self.renderbuffers[id].pixels,
+    }
+  }
+
+  fn image_pixels_mut(&mut self, image: Image) -> &mut [u8] {
+    match image {
+      Image::Canvas => &mut self.pixels,
+      Image::Texture(id) => &mut self.textures[id].pixels,
+      Image::Renderbuffer(id) => &mut self.renderbuffers[id].pixels,
+    }
+  }
+
+  /**
+   * The size of the images being drawn to, as `(width, height)`.
+   */
+  fn target_size(&self) -> (usize, usize) {
+    match self.framebuffer {
+      None => (self.width, self.height),
+      Some(framebuffer) => self.framebuffers[framebuffer].attachments.first()
+        .map_or((0, 0), |(_, image)| self.image_size(*image)),
+    }
+  }
+
+  fn program_mut(&mut self) -> &mut ProgramStore {
+    &mut self.programs[self.program]
+  }
+
+  fn vertex_array_mut(&mut self) -> &mut VertexArrayStore {
+    &mut self.vertex_arrays[self.vertex_array]
+  }
+
+  /**
+   * The attribute of the bound vertex array at `index`, where
+   * like GL an index past the last attribute changes nothing.
+   */
+  fn attribute_mut(&mut self, index: AttributeIndex) -> Option<&mut AttributePointer> {
+    self.vertex_array_mut().attributes.get_mut(index as usize)
+  }
+
+  fn samplers(&self) -> Samplers<'_> {
+    Samplers { textures: &self.textures, units: &self.texture_units }
+  }
+
+  fn read_indices(&self, count: i32, kind: IndexKind, offset: i32) -> Option<Vec<usize>> {
+    let store = &self.buffers[self.vertex_arrays[self.vertex_array].element_buffer?];
+    let size = kind.size();
+    let start = offset.max(0) as usize;
+    let end = start + size * count.max(0) as usize;
+    let bytes = store.data.get(start..end)?;
+
+    Some(bytes.chunks_exact(size).map(|b| match kind {
+      IndexKind::UnsignedByte => b[0] as usize,
+      IndexKind::UnsignedShort => u16::from_le_bytes([b[0], b[1]]) as usize,
+      IndexKind::UnsignedInt => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize,
+    }).collect())
+  }
+
+  /**
+   * Shades the vertices at `indices`, then assembles them
+   * into primitives of `mode` which are rasterized.
+   */
+  fn draw<S>(&mut self, shader: &S, mode: DrawArrayKind, indices: &[usize], instance: usize) where S: SoftwareShader {
+    let image = match self.color_image() {
+      Some(image) => image,
+      None => return,
+    };
+    let vertices = match indices.iter()
+      .map(|i| self.shade_vertex(shader, *i, instance))
+      .collect::<Option<Vec<_>>>() {
+      Some(vertices) => vertices,
+      // like WebGL, reading outside of a buffer draws nothing.
+      None => return,
+    };
+
+    let n = vertices.len();
+    match mode {
+      DrawArrayKind::Triangles => {
+        for t in vertices.chunks_exact(3) {
+          self.draw_triangle(shader, image, &t[0], &t[1], &t[2]);
+        }
+      },
+      DrawArrayKind::TriangleStrip => {
+        for i in 0..n.saturating_sub(2) {
+          let (a, b) = if i % 2 == 0 { (i, i + 1) } else { (i + 1, i) };
+          self.draw_triangle(shader, image, &vertices[a], &vertices[b], &vertices[i + 2]);
+        }
+      },
+      DrawArrayKind::TriangleFan => {
+        for i in 1..n.saturating_sub(1) {
+          self.draw_triangle(shader, image, &vertices[0], &vertices[i], &vertices[i + 1]);
+        }
+      },
+      DrawArrayKind::Lines => {
+        for l in vertices.chunks_exact(2) {
+          self.draw_line(shader, image, &l[0], &l[1]);
+        }
+      },
+      DrawArrayKind::LineStrip | DrawArrayKind::LineLoop => {
+        for i in 0..n.saturating_sub(1) {
+          self.draw_line(shader, image, &vertices[i], &vertices[i + 1]);
+        }
+        if let (DrawArrayKind::LineLoop, true) = (mode, n > 2) {
+          self.draw_line(shader, image, &vertices[n - 1], &vertices[0]);
+        }
+      },
+      DrawArrayKind::Points => {
+        for v in vertices.iter() {
+          self.draw_point(shader, image, v);
+        }
+      },
+    }
+  }
+
+  fn shade_vertex<S>(&self, shader: &S, vertex: usize, instance: usize) -> Option<ShadedVertex> where S: SoftwareShader {
+    let attribute_count = self.programs[self.program].attribute_names.len();
+    let inputs = self.vertex_arrays[self.vertex_array].attributes.iter()
+      .take(attribute_count)
+      .map(|pointer| match pointer.divisor {
+        0 => self.fetch_attribute(pointer, vertex),
+        divisor => self.fetch_attribute(pointer, instance / divisor as usize),
+      })
+      .collect::<Option<Vec<_>>>()?;
+    Some(shader.vertex(&inputs, &self.uniforms_or_zero()))
+  }
+
+  fn fetch_attribute(&self, pointer: &AttributePointer, element: usize) -> Option<[f32; 4]> {
+    let mut value = [0.0, 0.0, 0.0, 1.0];
+    if !pointer.enabled {
+      return Some(value);
+    }
+
+    let store = &self.buffers[pointer.buffer?];
+    let precision = pointer.precision?;
+    let component = precision.size();
+    let size = pointer.size.clamp(0, 4) as usize;
+    let stride = if pointer.stride == 0 { component * size } else { pointer.stride as usize };
+    let start = pointer.offset as usize + stride * element;
+
+    for (i, slot) in value.iter_mut().enumerate().take(size) {
+      let at = start + i * component;
+      let bytes = store.data.get(at..at + component)?;
+      *slot = read_component(bytes, precision, pointer.normalized);
+    }
+    Some(value)
+  }
+
+  /**
+   * The values of every uniform & then every uniform block,
+   * each padded with zeros to at least a 4x4 matrix, so ones
+   * that weren't set or were set with too few components
+   * don't need checking by the shader.
+   */
+  fn uniforms_or_zero(&self) -> Vec<Vec<f32>> {
+    let program = &self.programs[self.program];
+    let blocks = program.uniform_block_bindings.iter().map(|binding| {
+      let buffer = self.uniform_buffers.get(*binding as usize).copied().flatten();
+      buffer.map_or(vec![], |id| {
+        self.buffers[id].data.chunks_exact(4)
+          .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+          .collect()
+      })
+    });
+
+    program.uniforms.iter().cloned()
+      .chain(blocks)
+      .map(|mut u| {
+        if u.len() < 16 {
+          u.resize(16, 0.0);
+        }
+        u
+      })
+      .collect()
+  }
+
+  fn to_window(&self, vertex: &ShadedVertex) -> Option<WindowVertex> {
+    let [x, y, z, w] = vertex.position;
+    if w <= 0.0 {
+      return None;
+    }
+
+    let (vx, vy, vw, vh) = self.viewport;
+    let window_y = vy as f32 + ((y / w) + 1.0) * 0.5 * vh as f32;
+    Some(WindowVertex {
+      x: vx as f32 + ((x / w) + 1.0) * 0.5 * vw as f32,
+      y: self.target_size().1 as f32 - window_y,
+      z: (((z / w) + 1.0) * 0.5).clamp(0.0, 1.0),
+      inv_w: 1.0 / w,
+      varyings: vertex.varyings.iter().map(|v| v / w).collect(),
+    })
+  }
+
+  /**
+   * The pixels of a rect with a bottom left origin, as `(min_x,
+   * min_y, max_x, max_y)` exclusive of the max, top left origin.
+   */
+  fn window_rect(&self, (x, y, width, height): (i32, i32, i32, i32)) -> (i32, i32, i32, i32) {
+    let (target_width, target_height) = self.target_size();
+    let (target_width, target_height) = (target_width as i32, target_height as i32);
+    let min_x = x.max(0);
+    let min_y = (target_height - (y + height)).max(0);
+    let max_x = (x + width).min(target_width);
+    let max_y = (target_height - y).min(target_height);
+    (min_x, min_y, max_x, max_y)
+  }
+
+  /**
+   * The pixels that may be cleared, which is all of them
+   * unless the scissor test is enabled.
+   */
+  fn scissor_bounds(&self) -> (i32, i32, i32, i32) {
+    let (width, height) = self.target_size();
+    let rect = self.pipeline.scissor
+      .map_or((0, 0, width as i32, height as i32), |s| (s.x, s.y, s.width, s.height));
+    self.window_rect(rect)
+  }
+
+  /**
+   * The pixels that may be drawn to, which is those in both
+   * the viewport & the scissor bounds.
+   */
+  fn bounds(&self) -> (i32, i32, i32, i32) {
+    let (min_x, min_y, max_x, max_y) = self.window_rect(self.viewport);
+    let (scissor_min_x, scissor_min_y, scissor_max_x, scissor_max_y) = self.scissor_bounds();
+    (min_x.max(scissor_min_x), min_y.max(scissor_min_y), max_x.min(scissor_max_x), max_y.min(scissor_max_y))
+  }
+
+  fn draw_triangle<S>(
+      &mut self,
+      shader: &S,
+      image: Image,
+      a: &ShadedVertex,
+      b: &ShadedVertex,
+      c: &ShadedVertex,
+  ) where S: SoftwareShader {
+    let (v0, v1, v2) = match (self.to_window(a), self.to_window(b), self.to_window(c)) {
+      (Some(v0), Some(v1), Some(v2)) => (v0, v1, v2),
+      _ => return,
+    };
+
+    let area = edge(&v0, &v1, v2.x, v2.y);
+    if area == 0.0 {
+      return;
+    }
+
+    // window coordinates have the y axis flipped, so the area
+    // of a counter clockwise (front facing) triangle is negative.
+    let front_facing = area < 0.0;
+    let culled = match self.pipeline.cull {
+      Some(CullFace::Front) => front_facing,
+      Some(CullFace::Back) => !front_facing,
+      Some(CullFace::FrontAndBack) => true,
+      None => false,
+    };
+    if culled {
+      return;
+    }
+
+    // make the winding positive so the same inclusion test
+    // works for both front and back facing triangles.
+    let (v1, v2, area) = if area < 0.0 { (v2, v1, -area) } else { (v1, v2, area) };
+    let (min_x, min_y, max_x, max_y) = self.bounds();
+    let x0 = (v0.x.min(v1.x).min(v2.x).floor() as i32).max(min_x);
+    let y0 = (v0.y.min(v1.y).min(v2.y).floor() as i32).max(min_y);
+    let x1 = (v0.x.max(v1.x).max(v2.x).ceil() as i32).min(max_x);
+    let y1 = (v0.y.max(v1.y).max(v2.y).ceil() as i32).min(max_y);
+    let uniforms = self.uniforms_or_zero();
+
+    for y in y0..y1 {
+      for x in x0..x1 {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        let w0 = edge(&v1, &v2, px, py);
+        let w1 = edge(&v2, &v0, px, py);
+        let w2 = edge(&v0, &v1, px, py);
+        let covered = covers(w0, &v1, &v2) && covers(w1, &v2, &v0) && covers(w2, &v0, &v1);
+        if !covered {
+          continue;
+        }
+
+        let weights = [w0 / area, w1 / area, w2 / area];
+        let z = v0.z * weights[0] + v1.z * weights[1] + v2.z * weights[2];
+        let varyings = interpolate(&[&v0, &v1, &v2], &weights);
+        let color = shader.fragment(&varyings, &uniforms, &self.samplers());
+        self.write_fragment(image, x, y, z, color);
+      }
+    }
+  }
+
+  fn draw_line<S>(&mut self, shader: &S, image: Image, a: &ShadedVertex, b: &ShadedVertex) where S: SoftwareShader {
+    let (v0, v1) = match (self.to_window(a), self.to_window(b)) {
+      (Some(v0), Some(v1)) => (v0, v1),
+      _ => return,
+    };
+
+    let (min_x, min_y, max_x, max_y) = self.bounds();
+    let uniforms = self.uniforms_or_zero();
+    let (dx, dy) = (v1.x - v0.x, v1.y - v0.y);
+    // the last pixel is left out, so connected lines in a
+    // strip don't write their shared pixel twice.
+    let steps = dx.abs().max(dy.abs()).round() as i32;
+
+    for step in 0..steps {
+      let t = step as f32 / steps as f32;
+      let x = (v0.x + dx * t).floor() as i32;
+      let y = (v0.y + dy * t).floor() as i32;
+      if x < min_x || x >= max_x || y < min_y || y >= max_y {
+        continue;
+      }
+
+      let z = v0.z * (1.0 - t) + v1.z * t;
+      let varyings = interpolate(&[&v0, &v1], &[1.0 - t, t]);
+      let color = shader.fragment(&varyings, &uniforms, &self.samplers());
+      self.write_fragment(image, x, y, z, color);
+    }
+  }
+
+  fn draw_point<S>(&mut self, shader: &S, image: Image, vertex: &ShadedVertex) where S: SoftwareShader {
+    let [x, y, z, w] = vertex.position;
+    if x.abs() > w || y.abs() > w || z.abs() > w {
+      return;
+    }
+
+    let v = match self.to_window(vertex) {
+      Some(v) => v,
+      None => return,
+    };
+
+    let (min_x, min_y, max_x, max_y) = self.bounds();
+    let (x, y) = (v.x.floor() as i32, v.y.floor() as i32);
+    if x < min_x || x >= max_x || y < min_y || y >= max_y {
+      return;
+    }
+
+    let color = shader.fragment(&vertex.varyings, &self.uniforms_or_zero(), &self.samplers());
+    self.write_fragment(image, x, y, v.z, color);
+  }
+
+  /**
+   * Writes the fragment at `(x, y)` in window coordinates if
+   * it passes the stencil & depth tests.
+   */
+  fn write_fragment(&mut self, image: Image, x: i32, y: i32, z: f32, color: [f32; 4]) {
+    if self.test_fragment(x, y, z) {
+      self.write_pixel(image, x, y, color);
+    }
+  }
+
+  /**
+   * Runs the stencil test then the depth test of a fragment,
+   * updating the stored stencil & depth values for the outcome,
+   * returning if both tests passed.
+   */
+  fn test_fragment(&mut self, x: i32, y: i32, z: f32) -> bool {
+    let i = y as usize * self.target_size().0 + x as usize;
+    let (depth_image, stencil_image) = self.depth_stencil_images();
+
+    let stencil = match (self.pipeline.stencil, stencil_image) {
+      (Some(state), Some(image)) => self.stencil_values_mut(image).get(i).map(|value| (state, image, *value)),
+      _ => None,
+    };
+    if let Some((state, image, value)) = stencil {
+      let mask = state.mask as u8;
+      if !compare(state.func, reference(&state) & mask, value & mask) {
+        self.write_stencil(image, i, &state, state.fail);
+        return false;
+      }
+    }
+
+    let depth = match (self.pipeline.depth, depth_image) {
+      (Some(state), Some(image)) => self.depth_values_mut(image).get_mut(i).map(|value| (state, value)),
+      _ => None,
+    };
+    let passed = match depth {
+      Some((state, value)) => {
+        let passed = compare(state.func, z, *value);
+        if passed && state.write {
+          *value = z;
+        }
+        passed
+      },
+      None => true,
+    };
+
+    if let Some((state, image, _)) = stencil {
+      self.write_stencil(image, i, &state, if passed { state.pass } else { state.depth_fail });
+    }
+    passed
+  }
+
+  fn write_stencil(&mut self, image: Image, i: usize, state: &StencilState, op: StencilOp) {
+    let write_mask = state.write_mask as u8;
+    let value = &mut self.stencil_values_mut(image)[i];
+    let updated = stencil_op(op, *value, reference(state));
+    *value = (*value & !write_mask) | (updated & write_mask);
+  }
+
+  /**
+   * Writes to the pixel at `(x, y)` in window coordinates, so
+   * with a top left origin.
+   */
+  fn write_pixel(&mut self, image: Image, x: i32, y: i32, color: [f32; 4]) {
+    let i = self.pixel_index(image, x, y);
+    let ((src, dst), blend_color) = (self.blend_func, self.blend_color);
+    let color = clamp_color(color);
+    let output = match self.pipeline.blend {
+      false => color,
+      true => {
+        let pixel = &self.image_pixels(image)[i..i + 4];
+        let current = [
+          pixel[0] as f32 / 255.0,
+          pixel[1] as f32 / 255.0,
+          pixel[2] as f32 / 255.0,
+          pixel[3] as f32 / 255.0,
+        ];
+        let s = blend_factor(src, color, current, blend_color);
+        let d = blend_factor(dst, color, current, blend_color);
+        let mut output = [0.0; 4];
+        for c in 0..4 {
+          output[c] = color[c] * s[c] + current[c] * d[c];
+        }
+        output
+      },
+    };
+    self.store_color(image, i, to_rgba8(output));
+  }
+
+  /**
+   * The index of the pixel at `(x, y)` in window coordinates.
+   */
+  fn pixel_index(&self, image: Image, x: i32, y: i32) -> usize {
+    let (width, height) = self.image_size(image);
+    image_index(image, width, height, x as usize, height - 1 - y as usize)
+  }
+
+  /**
+   * Stores the channels of the colour in the colour mask.
+   */
+  fn store_color(&mut self, image: Image, i: usize, color: [u8; 4]) {
+    let ColorMask { red, green, blue, alpha } = self.pipeline.color_mask;
+    let pixel = &mut self.image_pixels_mut(image)[i..i + 4];
+    for (c, written) in [red, green, blue, alpha].iter().enumerate() {
+      if *written {
+        pixel[c] = color[c];
+      }
+    }
+  }
+}
+
+/**
+ * The index of the pixel at `(x, y)` in an image, where `y`
+ * is from the bottom like in GL.
+ */
+fn image_index(image: Image, width: usize, height: usize, x: usize, y: usize) -> usize {
+  let row = if let Image::Canvas = image { height - 1 - y } else { y };
+  (row * width + x) * 4
+}
+
+fn edge(a: &WindowVertex, b: &WindowVertex, x: f32, y: f32) -> f32 {
+  (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+/**
+ * Pixels centered exactly on an edge only belong to the
+ * triangle if it's a top or left edge, so triangles that
+ * share an edge never both cover the same pixel.
+ */
+fn covers(weight: f32, a: &WindowVertex, b: &WindowVertex) -> bool {
+  let top = a.y == b.y && b.x > a.x;
+  let left = b.y < a.y;
+  weight > 0.0 || (weight == 0.0 && (top || left))
+}
+
+/**
+ * Perspective correct interpolation of the varyings, which
+ * were divided by `w` when converted to window coordinates.
+ */
+fn interpolate(vertices: &[&WindowVertex], weights: &[f32]) -> Vec<f32> {
+  let inv_w: f32 = vertices.iter().zip(weights).map(|(v, l)| v.inv_w * l).sum();
+  let length = vertices[0].varyings.len();
+  (0..length)
+    .map(|i| {
+      let sum: f32 = vertices.iter().zip(weights).map(|(v, l)| v.varyings[i] * l).sum();
+      sum / inv_w
+    })
+    .collect()
+}
+
+fn wrap(i: i64, size: usize, wrap: TextureWrap) -> usize {
+  let size = size as i64;
+  let wrapped = match wrap {
+    TextureWrap::Repeat => i.rem_euclid(size),
+    TextureWrap::ClampToEdge => i.clamp(0, size - 1),
+    TextureWrap::MirroredRepeat => {
+      let i = i.rem_euclid(size * 2);
+      if i < size { i } else { size * 2 - 1 - i }
+    },
+  };
+  wrapped as usize
+}
+
+fn read_component(bytes: &[u8], precision: ViewPrecision, normalized: bool) -> f32 {
+  let (value, max) = match precision {
+    ViewPrecision::Byte => (bytes[0] as i8 as f32, i8::MAX as f32),
+    ViewPrecision::UnsignedByte => (bytes[0] as f32, u8::MAX as f32),
+    ViewPrecision::Short => (i16::from_le_bytes([bytes[0], bytes[1]]) as f32, i16::MAX as f32),
+    ViewPrecision::UnsignedShort => (u16::from_le_bytes([bytes[0], bytes[1]]) as f32, u16::MAX as f32),
+    ViewPrecision::Int => {
+      let value = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+      (value as f32, i32::MAX as f32)
+    },
+    ViewPrecision::UnsignedInt => {
+      let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+      (value as f32, u32::MAX as f32)
+    },
+    ViewPrecision::HalfFloat => return HalfFloat(u16::from_le_bytes([bytes[0], bytes[1]])).to_f32(),
+    ViewPrecision::Float => return f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+  };
+  if normalized { (value / max).max(-1.0) } else { value }
+}
+
+/**
+ * If `incoming` passes the test against the `stored` value.
+ */
+fn compare<T>(func: CompareFunc, incoming: T, stored: T) -> bool where T: PartialOrd {
+  match func {
+    CompareFunc::Never => false,
+    CompareFunc::Less => incoming < stored,
+    CompareFunc::Equal => incoming == stored,
+    CompareFunc::LessOrEqual => incoming <= stored,
+    CompareFunc::Greater => incoming > stored,
+    CompareFunc::NotEqual => incoming != stored,
+    CompareFunc::GreaterOrEqual => incoming >= stored,
+    CompareFunc::Always => true,
+  }
+}
+
+/**
+ * The reference value clamped to the range of the 8 bit
+ * stencil values.
+ */
+fn reference(state: &StencilState) -> u8 {
+  state.reference.clamp(0, u8::MAX as i32) as u8
+}
+
+fn stencil_op(op: StencilOp, value: u8, reference: u8) -> u8 {
+  match op {
+    StencilOp::Keep => value,
+    StencilOp::Zero => 0,
+    StencilOp::Replace => reference,
+    StencilOp::Increment => value.saturating_add(1),
+    StencilOp::IncrementWrap => value.wrapping_add(1),
+    StencilOp::Decrement => value.saturating_sub(1),
+    StencilOp::DecrementWrap => value.wrapping_sub(1),
+    StencilOp::Invert => !value,
+  }
+}
+
+fn blend_factor(factor: BlendFuncFactor, src: [f32; 4], dst: [f32; 4], constant: [f32; 4]) -> [f32; 4] {
+  let splat = |v: f32| [v, v, v, v];
+  let inverse = |c: [f32; 4]| [1.0 - c[0], 1.0 - c[1], 1.0 - c[2], 1.0 - c[3]];
+  match factor {
+    BlendFuncFactor::Zero => splat(0.0),
+    BlendFuncFactor::One => splat(1.0),
+    BlendFuncFactor::SrcColor => src,
+    BlendFuncFactor::OneMinusSrcColor => inverse(src),
+    BlendFuncFactor::DstColor => dst,
+    BlendFuncFactor::OneMinusDstColor => inverse(dst),
+    BlendFuncFactor::SrcAlpha => splat(src[3]),
+    BlendFuncFactor::OneMinusSrcAlpha => splat(1.0 - src[3]),
+    BlendFuncFactor::DstAlpha => splat(dst[3]),
+    BlendFuncFactor::OneMinusDstAlpha => splat(1.0 - dst[3]),
+    BlendFuncFactor::ConstantColor => constant,
+    BlendFuncFactor::OneMinusConstantColor => inverse(constant),
+    BlendFuncFactor::ConstantAlpha => splat(constant[3]),
+    BlendFuncFactor::OneMinusConstantAlpha => splat(1.0 - constant[3]),
+    BlendFuncFactor::SrcAlphaSaturate => {
+      let f = src[3].min(1.0 - dst[3]);
+      [f, f, f, 1.0]
+    },
+  }
+}
+
+/**
+ * The components of a uniform as floats, which is how every
+ * uniform is passed to a `SoftwareShader`.
+ */
+fn uniform_components(value: UniformValue) -> Vec<f32> {
+  match value {
+    UniformValue::Float(x) => vec![x],
+    UniformValue::Vec2(v) => v.to_vec(),
+    UniformValue::Vec3(v) => v.to_vec(),
+    UniformValue::Vec4(v) => v.to_vec(),
+    UniformValue::Int(x) => vec![x as f32],
+    UniformValue::IVec2(v) => v.iter().map(|x| *x as f32).collect(),
+    UniformValue::IVec3(v) => v.iter().map(|x| *x as f32).collect(),
+    UniformValue::IVec4(v) => v.iter().map(|x| *x as f32).collect(),
+    UniformValue::FloatArray(v) => v,
+    UniformValue::Vec2Array(v) => v.concat(),
+    UniformValue::Vec3Array(v) => v.concat(),
+    UniformValue::Vec4Array(v) => v.concat(),
+    UniformValue::IntArray(v) => v.iter().map(|x| *x as f32).collect(),
+    UniformValue::IVec2Array(v) => v.concat().iter().map(|x| *x as f32).collect(),
+    UniformValue::IVec3Array(v) => v.concat().iter().map(|x| *x as f32).collect(),
+    UniformValue::IVec4Array(v) => v.concat().iter().map(|x| *x as f32).collect(),
+    UniformValue::Mat2(m) => m.to_vec(),
+    UniformValue::Mat3(m) => m.to_vec(),
+    UniformValue::Mat4(m) => m.to_vec(),
+  }
+}
+
+fn clamp_color(color: [f32; 4]) -> [f32; 4] {
+  let mut clamped = [0.0; 4];
+  for (out, c) in clamped.iter_mut().zip(color.iter()) {
+    *out = c.clamp(0.0, 1.0);
+  }
+  clamped
+}
+
+fn to_rgba8(color: [f32; 4]) -> [u8; 4] {
+  let [r, g, b, a] = clamp_color(color);
+  [
+    (r * 255.0).round() as u8,
+    (g * 255.0).round() as u8,
+    (b * 255.0).round() as u8,
+    (a * 255.0).round() as u8,
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::constants::{ClearMask};
+  use super::super::data::{Float32View, Uint16View};
+  use super::super::pipeline::{DepthState, ScissorRect};
+  use super::super::std140::{Std140Builder};
+
+  /**
+   * Passes clip space positions straight through, and colours
+   * every fragment with the `color` uniform.
+   */
+  struct FlatShader;
+
+  impl SoftwareShader for FlatShader {
+    fn attributes(&self) -> Vec<&'static str> { vec!["position"] }
+    fn uniforms(&self) -> Vec<&'static str> { vec!["color"] }
+
+    fn vertex(&self, attributes: &[[f32; 4]], _: &[Vec<f32>]) -> ShadedVertex {
+      ShadedVertex { position: attributes[0], varyings: vec![] }
+    }
+
+    fn fragment(&self, _: &[f32], uniforms: &[Vec<f32>], _: &Samplers) -> [f32; 4] {
+      [uniforms[0][0], uniforms[0][1], 0.0, 1.0]
+    }
+  }
+
+  /**
+   * Maps clip space positions to texture coordinates, then
+   * samples the texture on the unit of the `sampler` uniform.
+   */
+  struct TexturedShader;
+
+  impl SoftwareShader for TexturedShader {
+    fn attributes(&self) -> Vec<&'static str> { vec!["position"] }
+    fn uniforms(&self) -> Vec<&'static str> { vec!["sampler"] }
+
+    fn vertex(&self, attributes: &[[f32; 4]], _: &[Vec<f32>]) -> ShadedVertex {
+      let [x, y, _, _] = attributes[0];
+      ShadedVertex { position: attributes[0], varyings: vec![x * 0.5 + 0.5, y * 0.5 + 0.5] }
+    }
+
+    fn fragment(&self, varyings: &[f32], uniforms: &[Vec<f32>], samplers: &Samplers) -> [f32; 4] {
+      samplers.texture(uniforms[0][0] as usize, [varyings[0], varyings[1]])
+    }
+  }
+
+  /**
+   * Colours every fragment with the first `vec4` of the
+   * `Material` uniform block.
+   */
+  struct BlockShader;
+
+  impl SoftwareShader for BlockShader {
+    fn attributes(&self) -> Vec<&'static str> { vec!["position"] }
+    fn uniforms(&self) -> Vec<&'static str> { vec![] }
+    fn uniform_blocks(&self) -> Vec<&'static str> { vec!["Material"] }
+
+    fn vertex(&self, attributes: &[[f32; 4]], _: &[Vec<f32>]) -> ShadedVertex {
+      ShadedVertex { position: attributes[0], varyings: vec![] }
+    }
+
+    fn fragment(&self, _: &[f32], uniforms: &[Vec<f32>], _: &Samplers) -> [f32; 4] {
+      [uniforms[0][0], uniforms[0][1], uniforms[0][2], uniforms[0][3]]
+    }
+  }
+
+  #[derive(Clone, Copy)]
+  enum Keys {
+    Position,
+    Color,
+    Sampler,
+    Material,
+  }
+
+  impl AttributeKey for Keys {
+    fn name(&self) -> &str {
+      match self {
+        Keys::Position => "position",
+        Keys::Color => "color",
+        Keys::Sampler => "sampler",
+        Keys::Material => "Material",
+      }
+    }
+  }
+
+  impl UniformBlockKey for Keys {
+    fn name(&self) -> &str {
+      AttributeKey::name(self)
+    }
+  }
+
+  impl UniformKey for Keys {
+    fn name(&self) -> &str {
+      match self {
+        Keys::Position => "position",
+        Keys::Color => "color",
+        Keys::Sampler => "sampler",
+        Keys::Material => "Material",
+      }
+    }
+  }
+
+  fn api_with_points(points: &[f32]) -> SoftwareRenderAPI<FlatShader> {
+    let api = SoftwareRenderAPI::create(FlatShader, 4, 4);
+    let buffer = api.create_buffer(BufferKind::ArrayBuffer).unwrap();
+    let view = Float32View::create(points).unwrap();
+    api.buffer_data(&buffer, &view, DrawKind::StaticDraw).unwrap();
+    api.vertex_attrib_pointer_with_i32(Keys::Position, 2, ViewPrecision::Float, false, 0, 0).unwrap();
+    api.enable_vertex_attrib_array(Keys::Position).unwrap();
+    api.uniform2f(Keys::Color, 1.0, 0.0).unwrap();
+    api
+  }
+
+  #[test]
+  fn clear_fills_framebuffer() {
+    let api = SoftwareRenderAPI::create(FlatShader, 2, 2);
+    api.clear_color(0.0, 0.0, 1.0, 1.0);
+    api.clear(ClearMask::ColorBufferBit);
+    assert_eq!(api.canvas_pixels(), [0, 0, 255, 255].repeat(4));
+  }
+
+  #[test]
+  fn full_screen_quad_covers_each_pixel_once() {
+    let quad = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+    let api = api_with_points(&quad);
+    api.blend_func(BlendFuncFactor::One, BlendFuncFactor::One);
+    api.set_pipeline_state(&PipelineState::default().with_blend(true));
+    api.uniform2f(Keys::Color, 0.25, 0.0).unwrap();
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 6);
+
+    // pixels on the shared diagonal would be 128 if both
+    // triangles wrote to them.
+    assert_eq!(api.canvas_pixels(), [64, 0, 0, 255].repeat(16));
+  }
+
+  #[test]
+  fn unknown_attributes_and_buffers_are_ignored() {
+    let quad = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+    let api = api_with_points(&quad);
+    api.vertex_attrib_pointer_with_i32(7, 2, ViewPrecision::Float, false, 0, 0).unwrap();
+    api.enable_vertex_attrib_array(7).unwrap();
+    api.vertex_attrib_divisor(7, 1).unwrap();
+
+    // a buffer from another context, which is never bound.
+    let foreign = SoftwareBuffer { id: 7, kind: BufferKind::ArrayBuffer };
+    let view = Float32View::create(&[0.0; 6]).unwrap();
+    api.buffer_data(&foreign, &view, DrawKind::StaticDraw).unwrap();
+    api.buffer_data_with_size(&foreign, 24, DrawKind::StaticDraw);
+    api.buffer_sub_data(&foreign, 0, &view).unwrap();
+    let foreign = SoftwareBuffer { id: 7, kind: BufferKind::UniformBuffer };
+    api.bind_buffer_base(&foreign, 0);
+
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.canvas_pixels(), [255, 0, 0, 255].repeat(16));
+  }
+
+  #[test]
+  fn unknown_handles_are_ignored() {
+    let quad = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+    let api = api_with_points(&quad);
+    let texture = SoftwareTexture { id: 7 };
+    let renderbuffer = SoftwareRenderbuffer { id: 7 };
+    let framebuffer = SoftwareFramebuffer { id: 7 };
+    let pixels = Uint8View::create(&[0; 4]).unwrap();
+    api.tex_image_2d(&texture, 1, 1, &pixels).unwrap();
+    api.tex_parameter(&texture, TextureParameter::MagFilter(TextureMagFilter::Linear));
+    api.generate_mipmap(&texture);
+    api.bind_texture(TextureUnit(0), &texture);
+    api.renderbuffer_storage(&renderbuffer, RenderbufferFormat::Rgba8, 1, 1);
+    api.framebuffer_renderbuffer(&framebuffer, FramebufferAttachment::Color(0), &renderbuffer);
+    api.framebuffer_texture_2d(&framebuffer, FramebufferAttachment::Color(0), &texture);
+    api.bind_framebuffer(Some(&framebuffer));
+    assert!(api.check_framebuffer_status(&framebuffer).is_ok());
+    api.bind_vertex_array(Some(&SoftwareVertexArray { id: 7 }));
+    api.use_program(&SoftwareProgram { id: 7 });
+
+    // still draws to the canvas with the first program.
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.canvas_pixels(), [255, 0, 0, 255].repeat(16));
+  }
+
+  #[test]
+  fn uniforms_with_missing_components_read_as_zero() {
+    let quad = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+    let api = api_with_points(&quad);
+    // the shader reads `color` as a vec2.
+    api.uniform1f(Keys::Color, 1.0).unwrap();
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.canvas_pixels(), [255, 0, 0, 255].repeat(16));
+  }
+
+  #[test]
+  fn uniforms_belong_to_the_program_they_were_set_on() {
+    let quad = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+    let api = api_with_points(&quad);
+    let other = api.add_program(FlatShader);
+    api.use_program(&other);
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.pixel(1, 1), [0, 0, 0, 255]);
+
+    api.uniform2f(Keys::Color, 0.0, 1.0).unwrap();
+    api.use_program(&SoftwareProgram { id: 0 });
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.pixel(1, 1), [255, 0, 0, 255]);
+  }
+
+  #[test]
+  fn viewport_is_bottom_left_origin() {
+    let quad = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+    let api = api_with_points(&quad);
+    api.set_viewport(0, 0, 2, 2);
+    api.draw_arrays(DrawArrayKind::TriangleStrip, 0, 4);
+
+    assert_eq!(api.pixel(0, 3), [255, 0, 0, 255]);
+    assert_eq!(api.pixel(1, 2), [255, 0, 0, 255]);
+    assert_eq!(api.pixel(0, 0), [0, 0, 0, 0]);
+    assert_eq!(api.pixel(2, 3), [0, 0, 0, 0]);
+  }
+
+  #[test]
+  fn blend_func_mixes_with_framebuffer() {
+    let quad = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+    let api = api_with_points(&quad);
+    api.clear_color(0.0, 1.0, 0.0, 1.0);
+    api.clear(ClearMask::ColorBufferBit);
+    api.blend_color(0.0, 0.0, 0.0, 0.5);
+    api.blend_func(BlendFuncFactor::ConstantAlpha, BlendFuncFactor::OneMinusConstantAlpha);
+    api.set_pipeline_state(&PipelineState::default().with_blend(true));
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+
+    assert_eq!(api.pixel(1, 1), [128, 128, 0, 255]);
+  }
+
+  #[test]
+  fn blending_is_disabled_until_enabled() {
+    let api = api_with_points(&[-1.0, -1.0, 3.0, -1.0, -1.0, 3.0]);
+    api.clear_color(0.0, 1.0, 0.0, 1.0);
+    api.clear(ClearMask::ColorBufferBit);
+    api.blend_func(BlendFuncFactor::One, BlendFuncFactor::One);
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+
+    assert_eq!(api.pixel(1, 1), [255, 0, 0, 255]);
+  }
+
+  #[test]
+  fn clear_is_limited_by_scissor_and_color_mask() {
+    let api = SoftwareRenderAPI::create(FlatShader, 2, 2);
+    api.clear_color(1.0, 1.0, 1.0, 1.0);
+    api.set_pipeline_state(&PipelineState::default()
+      .with_scissor(ScissorRect { x: 0, y: 0, width: 1, height: 1 })
+      .with_color_mask(ColorMask { green: false, ..ColorMask::default() }));
+    api.clear(ClearMask::ColorBufferBit | ClearMask::DepthBufferBit);
+
+    // the scissor rect is from the bottom left.
+    assert_eq!(api.pixel(0, 1), [255, 0, 255, 255]);
+    assert_eq!(api.pixel(0, 0), [0, 0, 0, 0]);
+    assert_eq!(api.pixel(1, 1), [0, 0, 0, 0]);
+  }
+
+  #[test]
+  fn depth_test_keeps_nearest_fragments() {
+    let api = SoftwareRenderAPI::create(FlatShader, 4, 4);
+    let buffer = api.create_buffer(BufferKind::ArrayBuffer).unwrap();
+    let triangles = Float32View::create(&[
+      -1.0, -1.0, -0.5, 3.0, -1.0, -0.5, -1.0, 3.0, -0.5,
+      -1.0, -1.0, 0.5, 3.0, -1.0, 0.5, -1.0, 3.0, 0.5,
+    ]).unwrap();
+    api.buffer_data(&buffer, &triangles, DrawKind::StaticDraw).unwrap();
+    api.vertex_attrib_pointer_with_i32(Keys::Position, 3, ViewPrecision::Float, false, 0, 0).unwrap();
+    api.enable_vertex_attrib_array(Keys::Position).unwrap();
+    api.set_pipeline_state(&PipelineState::default().with_depth(DepthState::default()));
+
+    api.uniform2f(Keys::Color, 1.0, 0.0).unwrap();
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    api.uniform2f(Keys::Color, 0.0, 1.0).unwrap();
+    api.draw_arrays(DrawArrayKind::Triangles, 3, 3);
+    assert_eq!(api.pixel(1, 1), [255, 0, 0, 255]);
+
+    // clearing the depth lets the far triangle through.
+    api.clear(ClearMask::DepthBufferBit);
+    api.draw_arrays(DrawArrayKind::Triangles, 3, 3);
+    assert_eq!(api.pixel(1, 1), [0, 255, 0, 255]);
+  }
+
+  #[test]
+  fn stencil_test_limits_draws_to_marked_pixels() {
+    let api = api_with_points(&[-1.0, -1.0, 3.0, -1.0, -1.0, 3.0]);
+    let mark = StencilState { reference: 1, pass: StencilOp::Replace, ..StencilState::default() };
+    let nothing = ColorMask { red: false, green: false, blue: false, alpha: false };
+    api.set_pipeline_state(&PipelineState::default()
+      .with_stencil(mark)
+      .with_scissor(ScissorRect { x: 0, y: 0, width: 2, height: 4 })
+      .with_color_mask(nothing));
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.canvas_pixels(), [0, 0, 0, 0].repeat(16));
+
+    let test = StencilState { func: CompareFunc::Equal, reference: 1, ..StencilState::default() };
+    api.set_pipeline_state(&PipelineState::default().with_stencil(test));
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.pixel(1, 0), [255, 0, 0, 255]);
+    assert_eq!(api.pixel(2, 0), [0, 0, 0, 0]);
+  }
+
+  #[test]
+  fn cull_face_skips_triangles_by_winding() {
+    // counter clockwise, so front facing.
+    let api = api_with_points(&[-1.0, -1.0, 3.0, -1.0, -1.0, 3.0]);
+    api.set_pipeline_state(&PipelineState::default().with_cull(CullFace::Front));
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.pixel(1, 1), [0, 0, 0, 0]);
+
+    api.set_pipeline_state(&PipelineState::default().with_cull(CullFace::Back));
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.pixel(1, 1), [255, 0, 0, 255]);
+  }
+
+  #[test]
+  fn out_of_range_draw_is_ignored() {
+    let api = api_with_points(&[0.0, 0.0]);
+    api.draw_arrays(DrawArrayKind::Points, 0, 2);
+    assert_eq!(api.canvas_pixels(), [0, 0, 0, 0].repeat(16));
+  }
+
+  #[test]
+  fn indexed_quad_shares_vertices() {
+    let quad = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+    let api = api_with_points(&quad);
+    api.blend_func(BlendFuncFactor::One, BlendFuncFactor::One);
+    api.set_pipeline_state(&PipelineState::default().with_blend(true));
+    api.uniform2f(Keys::Color, 0.25, 0.0).unwrap();
+
+    let buffer = api.create_buffer(BufferKind::ElementBuffer).unwrap();
+    let indices = Uint16View::create(&[0, 1, 2, 3, 2, 1]).unwrap();
+    api.buffer_data(&buffer, &indices, DrawKind::StaticDraw).unwrap();
+    api.draw_elements(DrawArrayKind::Triangles, 6, IndexKind::UnsignedShort, 0);
+    assert_eq!(api.canvas_pixels(), [64, 0, 0, 255].repeat(16));
+
+    // the second triangle alone, offset in bytes.
+    api.clear(ClearMask::ColorBufferBit);
+    api.draw_elements(DrawArrayKind::Triangles, 3, IndexKind::UnsignedShort, 6);
+    assert_eq!(api.pixel(3, 0), [64, 0, 0, 255]);
+    assert_eq!(api.pixel(0, 3), [0, 0, 0, 0]);
+
+    // reading past the end of the indices draws nothing.
+    api.clear(ClearMask::ColorBufferBit);
+    api.draw_elements(DrawArrayKind::Triangles, 6, IndexKind::UnsignedShort, 2);
+    assert_eq!(api.canvas_pixels(), [0, 0, 0, 0].repeat(16));
+  }
+
+  /**
+   * A full screen quad sampling a 2x2 texture of red & green
+   * on the bottom row, then blue & white on the top row.
+   */
+  fn textured_quad() -> (SoftwareRenderAPI<TexturedShader>, SoftwareTexture) {
+    let api = SoftwareRenderAPI::create(TexturedShader, 4, 4);
+    let buffer = api.create_buffer(BufferKind::ArrayBuffer).unwrap();
+    let quad = Float32View::create(&[-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0]).unwrap();
+    api.buffer_data(&buffer, &quad, DrawKind::StaticDraw).unwrap();
+    api.vertex_attrib_pointer_with_i32(Keys::Position, 2, ViewPrecision::Float, false, 0, 0).unwrap();
+    api.enable_vertex_attrib_array(Keys::Position).unwrap();
+
+    let texture = api.create_texture().unwrap();
+    let pixels = Uint8View::create(&[
+      255, 0, 0, 255,  0, 255, 0, 255,
+      0, 0, 255, 255,  255, 255, 255, 255,
+    ]).unwrap();
+    api.bind_texture(TextureUnit(1), &texture);
+    api.tex_image_2d(&texture, 2, 2, &pixels).unwrap();
+    api.tex_parameter(&texture, TextureParameter::MagFilter(TextureMagFilter::Nearest));
+    api.uniform1i(Keys::Sampler, 1).unwrap();
+    (api, texture)
+  }
+
+  #[test]
+  fn samples_texture_on_bound_unit() {
+    let (api, texture) = textured_quad();
+    api.tex_parameter(&texture, TextureParameter::MinFilter(TextureMinFilter::Nearest));
+    api.draw_arrays(DrawArrayKind::TriangleStrip, 0, 4);
+
+    assert_eq!(api.pixel(0, 3), [255, 0, 0, 255]);
+    assert_eq!(api.pixel(3, 3), [0, 255, 0, 255]);
+    assert_eq!(api.pixel(0, 0), [0, 0, 255, 255]);
+    assert_eq!(api.pixel(3, 0), [255, 255, 255, 255]);
+  }
+
+  #[test]
+  fn texture_without_mipmaps_is_incomplete() {
+    let (api, texture) = textured_quad();
+    api.draw_arrays(DrawArrayKind::TriangleStrip, 0, 4);
+    assert_eq!(api.canvas_pixels(), [0, 0, 0, 255].repeat(16));
+
+    api.generate_mipmap(&texture);
+    api.draw_arrays(DrawArrayKind::TriangleStrip, 0, 4);
+    assert_eq!(api.pixel(0, 3), [255, 0, 0, 255]);
+  }
+
+  #[test]
+  fn linear_filter_blends_texels() {
+    let (api, texture) = textured_quad();
+    api.generate_mipmap(&texture);
+    api.tex_parameter(&texture, TextureParameter::MagFilter(TextureMagFilter::Linear));
+    api.tex_parameter(&texture, TextureParameter::WrapS(TextureWrap::ClampToEdge));
+    api.tex_parameter(&texture, TextureParameter::WrapT(TextureWrap::ClampToEdge));
+    api.draw_arrays(DrawArrayKind::TriangleStrip, 0, 4);
+
+    // the corners are clamped to a single texel, the pixels
+    // between them are a quarter of the way to the next one.
+    assert_eq!(api.pixel(0, 3), [255, 0, 0, 255]);
+    assert_eq!(api.pixel(1, 3), [191, 64, 0, 255]);
+  }
+
+  #[test]
+  fn draws_into_framebuffer_texture() {
+    let (api, texture) = textured_quad();
+    api.tex_parameter(&texture, TextureParameter::MinFilter(TextureMinFilter::Nearest));
+
+    // a 4x4 target with a quarter of the texture drawn to the
+    // bottom left, then the target is drawn to the canvas.
+    let target = api.create_texture().unwrap();
+    api.bind_texture(TextureUnit(0), &target);
+    api.tex_image_2d(&target, 4, 4, &Uint8View::create(&[0; 64]).unwrap()).unwrap();
+    api.tex_parameter(&target, TextureParameter::MinFilter(TextureMinFilter::Nearest));
+    api.tex_parameter(&target, TextureParameter::MagFilter(TextureMagFilter::Nearest));
+    let framebuffer = api.create_framebuffer().unwrap();
+    api.framebuffer_texture_2d(&framebuffer, FramebufferAttachment::Color(0), &target);
+    api.check_framebuffer_status(&framebuffer).unwrap();
+
+    api.set_viewport(0, 0, 2, 2);
+    api.draw_arrays(DrawArrayKind::TriangleStrip, 0, 4);
+    assert_eq!(api.canvas_pixels(), [0; 64].to_vec());
+    assert_eq!(&api.read_pixels(0, 0, 1, 1).unwrap(), &[255, 0, 0, 255]);
+    assert_eq!(&api.read_pixels(1, 1, 1, 1).unwrap(), &[255, 255, 255, 255]);
+
+    api.bind_framebuffer(None);
+    api.set_viewport(0, 0, 4, 4);
+    api.uniform1i(Keys::Sampler, 0).unwrap();
+    api.draw_arrays(DrawArrayKind::TriangleStrip, 0, 4);
+    assert_eq!(api.pixel(0, 3), [255, 0, 0, 255]);
+    assert_eq!(api.pixel(1, 3), [0, 255, 0, 255]);
+    assert_eq!(api.pixel(1, 2), [255, 255, 255, 255]);
+    assert_eq!(api.pixel(3, 0), [0, 0, 0, 0]);
+  }
+
+  #[test]
+  fn checks_framebuffer_completeness() {
+    let api = SoftwareRenderAPI::create(FlatShader, 2, 2);
+    let framebuffer = api.create_framebuffer().unwrap();
+    let status = |api: &SoftwareRenderAPI<FlatShader>| match api.check_framebuffer_status(&framebuffer) {
+      Err(RenderApiError::IncompleteFramebuffer(status)) => Some(status),
+      Err(other) => panic!("expected incomplete framebuffer, got {:?}", other),
+      Ok(()) => None,
+    };
+    assert_eq!(status(&api), Some(FramebufferStatus::IncompleteMissingAttachment));
+
+    let color = api.create_renderbuffer().unwrap();
+    api.framebuffer_renderbuffer(&framebuffer, FramebufferAttachment::Color(0), &color);
+    assert_eq!(status(&api), Some(FramebufferStatus::IncompleteAttachment));
+
+    api.renderbuffer_storage(&color, RenderbufferFormat::DepthComponent16, 2, 2);
+    assert_eq!(status(&api), Some(FramebufferStatus::IncompleteAttachment));
+
+    api.renderbuffer_storage(&color, RenderbufferFormat::Rgba8, 2, 2);
+    let depth = api.create_renderbuffer().unwrap();
+    api.renderbuffer_storage(&depth, RenderbufferFormat::DepthComponent16, 4, 4);
+    api.framebuffer_renderbuffer(&framebuffer, FramebufferAttachment::Depth, &depth);
+    assert_eq!(status(&api), Some(FramebufferStatus::IncompleteDimensions));
+    assert!(api.read_pixels(0, 0, 1, 1).is_err());
+
+    api.renderbuffer_storage(&depth, RenderbufferFormat::DepthComponent16, 2, 2);
+    assert_eq!(status(&api), None);
+    api.clear_color(0.0, 0.0, 1.0, 1.0);
+    api.clear(ClearMask::ColorBufferBit);
+    assert_eq!(api.read_pixels(0, 0, 2, 2).unwrap(), [0, 0, 255, 255].repeat(4));
+    assert_eq!(api.canvas_pixels(), [0; 16].to_vec());
+  }
+
+  #[test]
+  fn reads_uniform_blocks_from_bound_buffer() {
+    let api = SoftwareRenderAPI::create(BlockShader, 1, 1);
+    let buffer = api.create_buffer(BufferKind::ArrayBuffer).unwrap();
+    let view = Float32View::create(&[-1.0, -1.0, 3.0, -1.0, -1.0, 3.0]).unwrap();
+    api.buffer_data(&buffer, &view, DrawKind::StaticDraw).unwrap();
+    api.vertex_attrib_pointer_with_i32(Keys::Position, 2, ViewPrecision::Float, false, 0, 0).unwrap();
+    api.enable_vertex_attrib_array(Keys::Position).unwrap();
+
+    let mut material = Std140Builder::create();
+    material.push(&UniformValue::Vec4([0.0, 1.0, 0.0, 1.0]));
+    let uniforms = api.create_buffer(BufferKind::UniformBuffer).unwrap();
+    api.buffer_data(&uniforms, &material.to_view().unwrap(), DrawKind::StaticDraw).unwrap();
+    api.bind_buffer_base(&uniforms, 3);
+    api.uniform_block_binding(Keys::Material, 3).unwrap();
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+    assert_eq!(api.pixel(0, 0), [0, 255, 0, 255]);
+
+    assert!(api.get_uniform_block(Keys::Color).is_err());
+  }
+
+  #[test]
+  fn reads_canvas_bottom_row_first() {
+    let api = api_with_points(&[-1.0, -1.0, 3.0, -1.0, -1.0, 3.0]);
+    api.set_viewport(0, 0, 4, 1);
+    api.draw_arrays(DrawArrayKind::Triangles, 0, 3);
+
+    assert_eq!(api.read_pixels(0, 0, 1, 2).unwrap(), vec![255, 0, 0, 255, 0, 0, 0, 0]);
+    // outside of the framebuffer is left as zero.
+    assert_eq!(api.read_pixels(3, 0, 2, 1).unwrap(), vec![255, 0, 0, 255, 0, 0, 0, 0]);
+  }
+
+  #[test]
+  fn grid_shader_matches_glsl() {
+    let shader = GridShader;
+    let uniforms = vec![vec![200.0, 100.0], vec![30.0, 15.0], vec![10.0], vec![10.0]];
+    let vertex = shader.vertex(&[[1.0, 0.0, 0.0, 1.0], [1.0, 1.0, 0.0, 1.0]], &uniforms);
+    assert_eq!(vertex.position, [-0.5, 0.5, 0.0, 1.0]);
+    assert_eq!(vertex.varyings, vec![0.25, 0.75, 0.5, 1.0]);
+    let samplers = Samplers { textures: &[], units: &[] };
+    assert_eq!(shader.fragment(&vertex.varyings, &uniforms, &samplers), [0.5, 1.0, 0.75, 1.25]);
+  }
+}
@@ -0,0 +1,149 @@
+use super::api::{UniformValue};
+use super::data::{DataViewError, Uint8View};
+
+/**
+ * Packs values into the std140 layout of a uniform block, so
+ * the bytes can be uploaded to a uniform buffer & read by a
+ * block declaring the same members in the same order.
+ *
+ * Each value is written at the next offset aligned for its
+ * type, where a `vec3` is aligned like a `vec4`, and every
+ * array element & matrix column is padded out to 16 bytes.
+ *
+ * https://www.khronos.org/registry/OpenGL/specs/es/3.0/es_spec_3.0.pdf#page=82
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Std140Builder {
+  data: Vec<u8>,
+}
+
+impl Std140Builder {
+  pub fn create() -> Self {
+    Std140Builder { data: vec![] }
+  }
+
+  /**
+   * Writes the value, returning the offset it was written at.
+   */
+  pub fn push(&mut self, value: &UniformValue) -> usize {
+    match value {
+      UniformValue::Float(x) => self.write(4, &floats(&[*x])),
+      UniformValue::Vec2(v) => self.write(8, &floats(v)),
+      UniformValue::Vec3(v) => self.write(16, &floats(v)),
+      UniformValue::Vec4(v) => self.write(16, &floats(v)),
+      UniformValue::Int(x) => self.write(4, &ints(&[*x])),
+      UniformValue::IVec2(v) => self.write(8, &ints(v)),
+      UniformValue::IVec3(v) => self.write(16, &ints(v)),
+      UniformValue::IVec4(v) => self.write(16, &ints(v)),
+      UniformValue::FloatArray(v) => self.write_array(v.iter().map(|x| floats(&[*x]))),
+      UniformValue::Vec2Array(v) => self.write_array(v.iter().map(|x| floats(x))),
+      UniformValue::Vec3Array(v) => self.write_array(v.iter().map(|x| floats(x))),
+      UniformValue::Vec4Array(v) => self.write_array(v.iter().map(|x| floats(x))),
+      UniformValue::IntArray(v) => self.write_array(v.iter().map(|x| ints(&[*x]))),
+      UniformValue::IVec2Array(v) => self.write_array(v.iter().map(|x| ints(x))),
+      UniformValue::IVec3Array(v) => self.write_array(v.iter().map(|x| ints(x))),
+      UniformValue::IVec4Array(v) => self.write_array(v.iter().map(|x| ints(x))),
+      UniformValue::Mat2(m) => self.write_array(m.chunks(2).map(floats)),
+      UniformValue::Mat3(m) => self.write_array(m.chunks(3).map(floats)),
+      UniformValue::Mat4(m) => self.write_array(m.chunks(4).map(floats)),
+    }
+  }
+
+  /**
+   * Writes a nested struct, which like an array is aligned
+   * to & padded out to 16 bytes, returning its offset.
+   */
+  pub fn push_struct(&mut self, member: &Std140Builder) -> usize {
+    self.write(16, &member.to_bytes())
+  }
+
+  /**
+   * The size of the block, which is padded out to 16 bytes.
+   */
+  pub fn size(&self) -> usize {
+    align_to(self.data.len(), 16)
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = self.data.clone();
+    bytes.resize(self.size(), 0);
+    bytes
+  }
+
+  pub fn to_view(&self) -> Result<Uint8View, DataViewError> {
+    Uint8View::create(&self.to_bytes())
+  }
+
+  fn write(&mut self, align: usize, bytes: &[u8]) -> usize {
+    let offset = align_to(self.data.len(), align);
+    self.data.resize(offset, 0);
+    self.data.extend_from_slice(bytes);
+    offset
+  }
+
+  fn write_array<I>(&mut self, elements: I) -> usize where I: Iterator<Item=Vec<u8>> {
+    let offset = align_to(self.data.len(), 16);
+    self.data.resize(offset, 0);
+    for element in elements {
+      let end = self.data.len() + 16;
+      self.data.extend_from_slice(&element);
+      self.data.resize(end, 0);
+    }
+    offset
+  }
+}
+
+fn align_to(offset: usize, align: usize) -> usize {
+  offset.div_ceil(align) * align
+}
+
+fn floats(values: &[f32]) -> Vec<u8> {
+  values.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn ints(values: &[i32]) -> Vec<u8> {
+  values.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn aligns_members_by_type() {
+    let mut block = Std140Builder::create();
+    assert_eq!(block.push(&UniformValue::Float(1.0)), 0);
+    assert_eq!(block.push(&UniformValue::Vec3([1.0, 2.0, 3.0])), 16);
+    assert_eq!(block.push(&UniformValue::Float(4.0)), 28);
+    assert_eq!(block.push(&UniformValue::IVec2([5, 6])), 32);
+    assert_eq!(block.push(&UniformValue::Mat4([0.0; 16])), 48);
+    assert_eq!(block.push(&UniformValue::Vec2Array(vec![[0.0; 2]; 2])), 112);
+    assert_eq!(block.push(&UniformValue::Int(7)), 144);
+    assert_eq!(block.size(), 160);
+  }
+
+  #[test]
+  fn pads_array_elements_and_matrix_columns() {
+    let mut block = Std140Builder::create();
+    block.push(&UniformValue::FloatArray(vec![1.0, 2.0]));
+    block.push(&UniformValue::Mat2([3.0, 4.0, 5.0, 6.0]));
+
+    let expected: Vec<u8> = [1.0f32, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0]
+      .iter()
+      .chain([3.0f32, 4.0, 0.0, 0.0, 5.0, 6.0, 0.0, 0.0].iter())
+      .flat_map(|x| x.to_le_bytes())
+      .collect();
+    assert_eq!(block.to_bytes(), expected);
+  }
+
+  #[test]
+  fn nested_structs_are_aligned_to_16_bytes() {
+    let mut light = Std140Builder::create();
+    light.push(&UniformValue::Float(1.0));
+
+    let mut block = Std140Builder::create();
+    block.push(&UniformValue::Float(0.0));
+    assert_eq!(block.push_struct(&light), 16);
+    assert_eq!(block.push(&UniformValue::Float(2.0)), 32);
+  }
+}
@@ -0,0 +1,272 @@
+use super::api::{RenderAPI, RenderApiError};
+use super::buffer::{DynamicBuffer, GrowthPolicy};
+use super::constants::{BufferKind, DrawKind};
+use super::data::{View};
+use super::layout::{VertexLayout};
+
+/**
+ * WebGL requires attribute offsets to be a multiple of the
+ * size of their type, which is at most 4 bytes.
+ */
+const ALIGNMENT: usize = 4;
+
+/**
+ * How a `StreamBuffer` avoids writing over vertices the GPU
+ * could still be reading from an earlier frame.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamStrategy {
+  /**
+   * Reallocates the one buffer at the start of each frame,
+   * so the driver can hand out new storage while the old
+   * storage is still in use.
+   */
+  Orphan,
+  /**
+   * Cycles through this many buffers, one per frame.
+   */
+  Cycle(usize),
+}
+
+/**
+ * Where a `StreamBuffer` put some vertices, where `offset` is
+ * in bytes from the start of the buffer.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamAllocation {
+  pub buffer: usize,
+  pub offset: i32,
+  pub size: usize,
+}
+
+impl StreamAllocation {
+  /**
+   * The layout with the offset of the allocation added to
+   * every attribute, to point them at these vertices.
+   */
+  pub fn offset_layout<A>(&self, layout: &VertexLayout<A>) -> VertexLayout<A> where A: Copy {
+    let mut layout = layout.clone();
+    for attribute in layout.attributes.iter_mut() {
+      attribute.offset += self.offset;
+    }
+    layout
+  }
+}
+
+/**
+ * Hands out space for vertices that only last a frame, such
+ * as debug geometry, so they don't need a buffer each.
+ *
+ * Vertices are pushed one after the other into the buffer of
+ * the current frame, which is left bound as the array buffer
+ * so the attributes can be pointed at the vertices straight
+ * away. Vertices that don't fit reallocate the buffer, so
+ * anything pushed before them must already have been drawn.
+ *
+ * ```ignore
+ * stream.begin_frame(&context);
+ * let allocation = stream.push(&context, &vertices)?;
+ * context.set_vertex_layout(&allocation.offset_layout(&layout), 0)?;
+ * context.draw_arrays(DrawArrayKind::Lines, 0, count);
+ * ```
+ */
+#[derive(Debug)]
+pub struct StreamBuffer<R: RenderAPI> {
+  buffers: Vec<DynamicBuffer<R>>,
+  strategy: StreamStrategy,
+  capacity: usize,
+  current: usize,
+  cursor: usize,
+}
+
+impl<R> StreamBuffer<R> where R: RenderAPI {
+  /**
+   * Creates the buffers with `capacity` bytes each, which
+   * grows when more is pushed at once than fits.
+   */
+  pub fn create(context: &R, capacity: usize, strategy: StreamStrategy) -> Result<Self, RenderApiError> {
+    let count = match strategy {
+      StreamStrategy::Orphan => 1,
+      StreamStrategy::Cycle(count) => count.max(1),
+    };
+
+    let mut buffers = Vec::with_capacity(count);
+    for _ in 0..count {
+      let mut buffer = DynamicBuffer::create(context, BufferKind::ArrayBuffer, DrawKind::StreamDraw)?;
+      buffer.allocate(context, capacity);
+      buffers.push(buffer);
+    }
+    Ok(StreamBuffer { buffers, strategy, capacity, current: 0, cursor: 0 })
+  }
+
+  /**
+   * Starts pushing from the start of the next buffer, or the
+   * start of the orphaned buffer.
+   */
+  pub fn begin_frame(&mut self, context: &R) {
+    match self.strategy {
+      StreamStrategy::Orphan => self.buffers[0].allocate(context, self.capacity),
+      StreamStrategy::Cycle(_) => self.current = (self.current + 1) % self.buffers.len(),
+    }
+    self.cursor = 0;
+  }
+
+  /**
+   * Writes the vertices after the ones already pushed this
+   * frame, aligned so the offset can be used as is with
+   * `vertex_attrib_pointer_with_i32`.
+   */
+  pub fn push<V>(&mut self, context: &R, view: &V) -> Result<StreamAllocation, RenderApiError> where V: View {
+    let size = view.length() * view.get_precision().size();
+    let mut offset = self.cursor.div_ceil(ALIGNMENT) * ALIGNMENT;
+    if size > self.capacity {
+      self.capacity = GrowthPolicy::Double.grow(self.capacity, size);
+    }
+
+    let buffer = &mut self.buffers[self.current];
+    if buffer.capacity() < self.capacity || offset + size > buffer.capacity() {
+      buffer.allocate(context, self.capacity);
+      offset = 0;
+    }
+    buffer.write(context, offset, view)?;
+    self.cursor = offset + size;
+    Ok(StreamAllocation { buffer: self.current, offset: offset as i32, size })
+  }
+
+  /**
+   * Binds the buffer of the allocation as the array buffer.
+   */
+  pub fn bind(&self, context: &R, allocation: &StreamAllocation) {
+    self.buffers[allocation.buffer].bind(context);
+  }
+
+  pub fn buffer(&self, allocation: &StreamAllocation) -> &R::Buffer {
+    self.buffers[allocation.buffer].buffer()
+  }
+
+  /**
+   * The number of bytes each buffer can hold.
+   */
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::api::{AttributeKey, UniformKey};
+  use super::super::constants::{DrawArrayKind, ViewPrecision};
+  use super::super::data::{Float32View};
+  use super::super::recording::{Command, RecordingBuffer, RecordingRenderAPI};
+  use super::super::software::{GridShader, SoftwareRenderAPI};
+
+  #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+  struct Key(&'static str);
+
+  impl AttributeKey for Key {
+    fn name(&self) -> &str { self.0 }
+  }
+
+  impl UniformKey for Key {
+    fn name(&self) -> &str { self.0 }
+  }
+
+  fn floats(values: &[f32]) -> Float32View {
+    Float32View::create(values).unwrap()
+  }
+
+  fn array_buffer(id: usize) -> RecordingBuffer {
+    RecordingBuffer { id, kind: BufferKind::ArrayBuffer }
+  }
+
+  #[test]
+  fn cycles_through_buffers_each_frame() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    let mut stream = StreamBuffer::create(&api, 16, StreamStrategy::Cycle(2)).unwrap();
+    api.take_commands();
+
+    let first = stream.push(&api, &floats(&[1.0, 2.0])).unwrap();
+    let second = stream.push(&api, &floats(&[3.0])).unwrap();
+    stream.begin_frame(&api);
+    let third = stream.push(&api, &floats(&[4.0])).unwrap();
+    stream.begin_frame(&api);
+    let fourth = stream.push(&api, &floats(&[5.0])).unwrap();
+
+    assert_eq!(first, StreamAllocation { buffer: 0, offset: 0, size: 8 });
+    assert_eq!(second, StreamAllocation { buffer: 0, offset: 8, size: 4 });
+    assert_eq!(third, StreamAllocation { buffer: 1, offset: 0, size: 4 });
+    assert_eq!(fourth, StreamAllocation { buffer: 0, offset: 0, size: 4 });
+    assert!(api.take_commands().iter().all(|c| matches!(c, Command::BufferSubData { .. })));
+  }
+
+  #[test]
+  fn orphans_buffer_each_frame() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    let mut stream = StreamBuffer::create(&api, 16, StreamStrategy::Orphan).unwrap();
+    stream.push(&api, &floats(&[1.0])).unwrap();
+    api.take_commands();
+    stream.begin_frame(&api);
+
+    let allocation = stream.push(&api, &floats(&[2.0])).unwrap();
+    assert_eq!(allocation.offset, 0);
+    assert_eq!(api.take_commands(), vec![
+      Command::BufferDataWithSize { buffer: array_buffer(0), size: 16, draw_kind: DrawKind::StreamDraw },
+      Command::BufferSubData {
+        buffer: array_buffer(0),
+        offset: 0,
+        precision: ViewPrecision::Float,
+        data: floats(&[2.0]).to_bytes(),
+      },
+    ]);
+  }
+
+  #[test]
+  fn reallocates_when_full() {
+    let api = RecordingRenderAPI::create(&[], &[]);
+    let mut stream = StreamBuffer::create(&api, 8, StreamStrategy::Cycle(1)).unwrap();
+    stream.push(&api, &floats(&[1.0])).unwrap();
+
+    let wrapped = stream.push(&api, &floats(&[2.0, 3.0])).unwrap();
+    assert_eq!(wrapped.offset, 0);
+    assert_eq!(stream.capacity(), 8);
+
+    let grown = stream.push(&api, &floats(&[0.0; 3])).unwrap();
+    assert_eq!(grown.offset, 0);
+    assert_eq!(stream.capacity(), 16);
+  }
+
+  #[test]
+  fn offsets_layout_to_allocation() {
+    let allocation = StreamAllocation { buffer: 0, offset: 12, size: 24 };
+    let layout = VertexLayout::create()
+      .with_interleaved(Key("position"), 2, ViewPrecision::Float, false)
+      .with_interleaved(Key("cell"), 2, ViewPrecision::Float, false);
+    let offsets: Vec<i32> = allocation.offset_layout(&layout).attributes.iter().map(|a| a.offset).collect();
+    assert_eq!(offsets, vec![12, 20]);
+  }
+
+  #[test]
+  fn draws_geometry_pushed_after_other_geometry() {
+    let api = SoftwareRenderAPI::create(GridShader, 2, 2);
+    api.uniform2f(Key("resolution"), 2.0, 2.0).unwrap();
+    api.uniform2f(Key("origin"), 0.0, 0.0).unwrap();
+    api.uniform1f(Key("spacing"), 0.0).unwrap();
+    api.uniform1f(Key("size"), 1.0).unwrap();
+
+    let mut stream = StreamBuffer::create(&api, 64, StreamStrategy::Cycle(2)).unwrap();
+    stream.push(&api, &floats(&[9.0; 5])).unwrap();
+    let quad = floats(&[0.0, 0.0, 2.0, 0.0, 0.0, 2.0, 2.0, 2.0]);
+    let allocation = stream.push(&api, &quad).unwrap();
+    let layout = VertexLayout::create().with_interleaved(Key("position"), 2, ViewPrecision::Float, false);
+    assert_eq!(allocation.offset, 20);
+
+    api.set_vertex_layout(&allocation.offset_layout(&layout), 0).unwrap();
+    api.draw_arrays(DrawArrayKind::TriangleStrip, 0, 4);
+    for x in 0..2 {
+      for y in 0..2 {
+        assert_ne!(api.pixel(x, y), [0, 0, 0, 0], "pixel ({}, {}) wasn't drawn", x, y);
+      }
+    }
+  }
+}
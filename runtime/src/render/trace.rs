@@ -0,0 +1,1004 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use super::api::{
+  AttributeIndex,
+  AttributeKey,
+  IntoAttributeIndex,
+  IntoUniformBlockIndex,
+  IntoUniformIndex,
+  RenderAPI,
+  RenderApiError,
+  UniformBlockKey,
+  UniformKey,
+  UniformValue,
+};
+use super::constants::{
+  BufferKind,
+  BlendFuncFactor,
+  ClearMasks,
+  DrawArrayKind,
+  DrawKind,
+  FramebufferAttachment,
+  IndexKind,
+  RenderbufferFormat,
+  TextureParameter,
+  TextureUnit,
+  ViewPrecision,
+  HasBufferKind,
+};
+use super::data::{ByteView, DataViewError, Uint8View, View};
+use super::pipeline::{PipelineState};
+
+/**
+ * Bumped whenever a change to `TraceCall` would stop older
+ * traces from loading or replaying the same way.
+ */
+pub const TRACE_VERSION: u32 = 11;
+
+/**
+ * A recording of calls made against a `RenderAPI`, including
+ * the contents of any buffers & textures uploaded, which can be saved
+ * as JSON or bincode then replayed against any backend.
+ *
+ * Attributes & uniforms are stored by name, as locations
+ * aren't guaranteed to be the same between backends.
+ * Programs are created outside of the `RenderAPI`, so they're
+ * numbered in the order they were first used.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trace {
+  pub version: u32,
+  pub calls: Vec<TraceCall>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TraceCall {
+  BindBuffer { buffer: usize },
+  BindBufferBase { buffer: usize, index: u32 },
+  BindFramebuffer { framebuffer: Option<usize> },
+  BindTexture { unit: TextureUnit, texture: usize },
+  BindVertexArray { vertex_array: Option<usize> },
+  BlendColor { red: f32, green: f32, blue: f32, alpha: f32 },
+  BlendFunc { src: BlendFuncFactor, dst: BlendFuncFactor },
+  BufferData {
+    buffer: usize,
+    precision: ViewPrecision,
+    data: Vec<u8>,
+    draw_kind: DrawKind,
+  },
+  BufferDataWithSize { buffer: usize, size: usize, draw_kind: DrawKind },
+  BufferSubData {
+    buffer: usize,
+    offset: usize,
+    precision: ViewPrecision,
+    data: Vec<u8>,
+  },
+  CheckFramebufferStatus { framebuffer: usize },
+  ClearColor { red: f32, green: f32, blue: f32, alpha: f32 },
+  Clear(ClearMasks),
+  CreateBuffer { buffer: usize, kind: BufferKind },
+  CreateFramebuffer { framebuffer: usize },
+  CreateRenderbuffer { renderbuffer: usize },
+  CreateTexture { texture: usize },
+  CreateVertexArray { vertex_array: usize },
+  DrawArrays { mode: DrawArrayKind, first: i32, count: i32 },
+  DrawArraysInstanced { mode: DrawArrayKind, first: i32, count: i32, instance_count: i32 },
+  DrawElements { mode: DrawArrayKind, count: i32, kind: IndexKind, offset: i32 },
+  DrawElementsInstanced {
+    mode: DrawArrayKind,
+    count: i32,
+    kind: IndexKind,
+    offset: i32,
+    instance_count: i32,
+  },
+  EnableVertexAttribArray(TraceAttribute),
+  FramebufferRenderbuffer {
+    framebuffer: usize,
+    attachment: FramebufferAttachment,
+    renderbuffer: usize,
+  },
+  FramebufferTexture2D { framebuffer: usize, attachment: FramebufferAttachment, texture: usize },
+  GenerateMipmap { texture: usize },
+  ReadPixels { x: i32, y: i32, width: i32, height: i32 },
+  RenderbufferStorage { renderbuffer: usize, format: RenderbufferFormat, width: i32, height: i32 },
+  SetPipelineState(PipelineState),
+  SetUniform { uniform: String, value: UniformValue },
+  SetViewport { x: i32, y: i32, width: i32, height: i32 },
+  TexImage2D { texture: usize, width: i32, height: i32, data: Vec<u8> },
+  TexParameter { texture: usize, parameter: TextureParameter },
+  UniformBlockBinding { block: String, binding: u32 },
+  UseProgram { program: usize },
+  VertexAttribDivisor { attribute: TraceAttribute, divisor: u32 },
+  VertexAttribIPointer {
+    attribute: TraceAttribute,
+    size: i32,
+    precision: ViewPrecision,
+    stride: i32,
+    offset: i32,
+  },
+  VertexAttribPointer {
+    attribute: TraceAttribute,
+    size: i32,
+    precision: ViewPrecision,
+    normalized: bool,
+    stride: i32,
+    offset: i32,
+  },
+}
+
+/**
+ * An attribute is stored by name if it was ever looked up
+ * by key, otherwise all that's known is its location.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TraceAttribute {
+  Name(String),
+  Index(AttributeIndex),
+}
+
+/**
+ * An attribute, uniform or uniform block key from a trace,
+ * which is only known by the name it was recorded with.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceKey(pub String);
+
+impl AttributeKey for TraceKey {
+  fn name(&self) -> &str {
+    &self.0
+  }
+}
+
+impl UniformKey for TraceKey {
+  fn name(&self) -> &str {
+    &self.0
+  }
+}
+
+impl UniformBlockKey for TraceKey {
+  fn name(&self) -> &str {
+    &self.0
+  }
+}
+
+impl Trace {
+  pub fn to_json(&self) -> Result<String, TraceError> {
+    serde_json::to_string(self).map_err(|e| TraceError::Json(e.to_string()))
+  }
+
+  pub fn from_json(json: &str) -> Result<Self, TraceError> {
+    let trace: Trace = serde_json::from_str(json).map_err(|e| TraceError::Json(e.to_string()))?;
+    trace.check_version()
+  }
+
+  pub fn to_binary(&self) -> Result<Vec<u8>, TraceError> {
+    bincode::serialize(self).map_err(|e| TraceError::Binary(e.to_string()))
+  }
+
+  pub fn from_binary(bytes: &[u8]) -> Result<Self, TraceError> {
+    let trace: Trace = bincode::deserialize(bytes).map_err(|e| TraceError::Binary(e.to_string()))?;
+    trace.check_version()
+  }
+
+  fn check_version(self) -> Result<Self, TraceError> {
+    if self.version == TRACE_VERSION {
+      Ok(self)
+    } else {
+      Err(TraceError::UnsupportedVersion(self.version))
+    }
+  }
+
+  /**
+   * Makes every call in the trace against `api`, in order.
+   */
+  pub fn replay<R>(&self, api: &R) -> Result<(), ReplayError> where R: RenderAPI {
+    self.replay_with_programs(api, &[])
+  }
+
+  /**
+   * Like `replay`, where `programs` are the programs of `api`
+   * in the order they were first used while tracing.
+   */
+  pub fn replay_with_programs<R>(&self, api: &R, programs: &[R::Program]) -> Result<(), ReplayError> where R: RenderAPI {
+    let mut buffers: HashMap<usize, R::Buffer> = HashMap::new();
+    let mut textures: HashMap<usize, R::Texture> = HashMap::new();
+    let mut framebuffers: HashMap<usize, R::Framebuffer> = HashMap::new();
+    let mut renderbuffers: HashMap<usize, R::Renderbuffer> = HashMap::new();
+    let mut vertex_arrays: HashMap<usize, R::VertexArray> = HashMap::new();
+
+    for call in self.calls.iter() {
+      match call {
+        TraceCall::BindBuffer { buffer } => {
+          let target = buffers.get(buffer).ok_or(ReplayError::UnknownBuffer(*buffer))?;
+          api.bind_buffer(target);
+        },
+        TraceCall::BindBufferBase { buffer, index } => {
+          let target = buffers.get(buffer).ok_or(ReplayError::UnknownBuffer(*buffer))?;
+          api.bind_buffer_base(target, *index);
+        },
+        TraceCall::BindFramebuffer { framebuffer: None } => api.bind_framebuffer(None),
+        TraceCall::BindFramebuffer { framebuffer: Some(framebuffer) } => {
+          let target = framebuffers.get(framebuffer).ok_or(ReplayError::UnknownFramebuffer(*framebuffer))?;
+          api.bind_framebuffer(Some(target));
+        },
+        TraceCall::BindTexture { unit, texture } => {
+          let target = textures.get(texture).ok_or(ReplayError::UnknownTexture(*texture))?;
+          api.bind_texture(*unit, target);
+        },
+        TraceCall::BindVertexArray { vertex_array: None } => api.bind_vertex_array(None),
+        TraceCall::BindVertexArray { vertex_array: Some(vertex_array) } => {
+          let target = vertex_arrays.get(vertex_array)
+            .ok_or(ReplayError::UnknownVertexArray(*vertex_array))?;
+          api.bind_vertex_array(Some(target));
+        },
+        TraceCall::BlendColor { red, green, blue, alpha } => {
+          api.blend_color(*red, *green, *blue, *alpha);
+        },
+        TraceCall::BlendFunc { src, dst } => api.blend_func(*src, *dst),
+        TraceCall::BufferData { buffer, precision, data, draw_kind } => {
+          let target = buffers.get(buffer).ok_or(ReplayError::UnknownBuffer(*buffer))?;
+          api.buffer_data(target, &ByteView::create(*precision, data), *draw_kind)?;
+        },
+        TraceCall::BufferDataWithSize { buffer, size, draw_kind } => {
+          let target = buffers.get(buffer).ok_or(ReplayError::UnknownBuffer(*buffer))?;
+          api.buffer_data_with_size(target, *size, *draw_kind);
+        },
+        TraceCall::BufferSubData { buffer, offset, precision, data } => {
+          let target = buffers.get(buffer).ok_or(ReplayError::UnknownBuffer(*buffer))?;
+          api.buffer_sub_data(target, *offset, &ByteView::create(*precision, data))?;
+        },
+        TraceCall::CheckFramebufferStatus { framebuffer } => {
+          let target = framebuffers.get(framebuffer).ok_or(ReplayError::UnknownFramebuffer(*framebuffer))?;
+          // only replayed for binding the framebuffer, as the
+          // status wasn't what was being traced.
+          let _ = api.check_framebuffer_status(target);
+        },
+        TraceCall::ClearColor { red, green, blue, alpha } => {
+          api.clear_color(*red, *green, *blue, *alpha);
+        },
+        TraceCall::Clear(mask) => api.clear(*mask),
+        TraceCall::CreateBuffer { buffer, kind } => {
+          buffers.insert(*buffer, api.create_buffer(*kind)?);
+        },
+        TraceCall::CreateFramebuffer { framebuffer } => {
+          framebuffers.insert(*framebuffer, api.create_framebuffer()?);
+        },
+        TraceCall::CreateRenderbuffer { renderbuffer } => {
+          renderbuffers.insert(*renderbuffer, api.create_renderbuffer()?);
+        },
+        TraceCall::CreateTexture { texture } => {
+          textures.insert(*texture, api.create_texture()?);
+        },
+        TraceCall::CreateVertexArray { vertex_array } => {
+          vertex_arrays.insert(*vertex_array, api.create_vertex_array()?);
+        },
+        TraceCall::DrawArrays { mode, first, count } => api.draw_arrays(*mode, *first, *count),
+        TraceCall::DrawArraysInstanced { mode, first, count, instance_count } => {
+          api.draw_arrays_instanced(*mode, *first, *count, *instance_count);
+        },
+        TraceCall::DrawElements { mode, count, kind, offset } => {
+          api.draw_elements(*mode, *count, *kind, *offset);
+        },
+        TraceCall::DrawElementsInstanced { mode, count, kind, offset, instance_count } => {
+          api.draw_elements_instanced(*mode, *count, *kind, *offset, *instance_count);
+        },
+        TraceCall::EnableVertexAttribArray(attribute) => {
+          let index = attribute_index(api, attribute)?;
+          api.enable_vertex_attrib_array(index)?;
+        },
+        TraceCall::FramebufferRenderbuffer { framebuffer, attachment, renderbuffer } => {
+          let target = framebuffers.get(framebuffer).ok_or(ReplayError::UnknownFramebuffer(*framebuffer))?;
+          let storage = renderbuffers.get(renderbuffer)
+            .ok_or(ReplayError::UnknownRenderbuffer(*renderbuffer))?;
+          api.framebuffer_renderbuffer(target, *attachment, storage);
+        },
+        TraceCall::FramebufferTexture2D { framebuffer, attachment, texture } => {
+          let target = framebuffers.get(framebuffer).ok_or(ReplayError::UnknownFramebuffer(*framebuffer))?;
+          let image = textures.get(texture).ok_or(ReplayError::UnknownTexture(*texture))?;
+          api.framebuffer_texture_2d(target, *attachment, image);
+        },
+        TraceCall::GenerateMipmap { texture } => {
+          let target = textures.get(texture).ok_or(ReplayError::UnknownTexture(*texture))?;
+          api.generate_mipmap(target);
+        },
+        TraceCall::ReadPixels { x, y, width, height } => {
+          api.read_pixels(*x, *y, *width, *height)?;
+        },
+        TraceCall::RenderbufferStorage { renderbuffer, format, width, height } => {
+          let target = renderbuffers.get(renderbuffer)
+            .ok_or(ReplayError::UnknownRenderbuffer(*renderbuffer))?;
+          api.renderbuffer_storage(target, *format, *width, *height);
+        },
+        TraceCall::SetPipelineState(state) => api.set_pipeline_state(state),
+        TraceCall::SetUniform { uniform, value } => {
+          api.set_uniform(TraceKey(uniform.clone()), value.clone())?;
+        },
+        TraceCall::SetViewport { x, y, width, height } => {
+          api.set_viewport(*x, *y, *width, *height);
+        },
+        TraceCall::TexImage2D { texture, width, height, data } => {
+          let target = textures.get(texture).ok_or(ReplayError::UnknownTexture(*texture))?;
+          let pixels = Uint8View::create(data).map_err(ReplayError::DataViewError)?;
+          api.tex_image_2d(target, *width, *height, &pixels)?;
+        },
+        TraceCall::TexParameter { texture, parameter } => {
+          let target = textures.get(texture).ok_or(ReplayError::UnknownTexture(*texture))?;
+          api.tex_parameter(target, *parameter);
+        },
+        TraceCall::UniformBlockBinding { block, binding } => {
+          api.uniform_block_binding(TraceKey(block.clone()), *binding)?;
+        },
+        TraceCall::UseProgram { program } => {
+          let target = programs.get(*program).ok_or(ReplayError::UnknownProgram(*program))?;
+          api.use_program(target);
+        },
+        TraceCall::VertexAttribDivisor { attribute, divisor } => {
+          let index = attribute_index(api, attribute)?;
+          api.vertex_attrib_divisor(index, *divisor)?;
+        },
+        TraceCall::VertexAttribIPointer { attribute, size, precision, stride, offset } => {
+          let index = attribute_index(api, attribute)?;
+          api.vertex_attrib_i_pointer_with_i32(index, *size, *precision, *stride, *offset)?;
+        },
+        TraceCall::VertexAttribPointer { attribute, size, precision, normalized, stride, offset } => {
+          let index = attribute_index(api, attribute)?;
+          api.vertex_attrib_pointer_with_i32(index, *size, *precision, *normalized, *stride, *offset)?;
+        },
+      }
+    }
+
+    Ok(())
+  }
+}
+
+fn attribute_index<R>(api: &R, attribute: &TraceAttribute) -> Result<AttributeIndex, RenderApiError> where R: RenderAPI {
+  match attribute {
+    TraceAttribute::Name(name) => api.get_attribute(TraceKey(name.clone())),
+    TraceAttribute::Index(index) => Ok(*index),
+  }
+}
+
+/**
+ * Wraps another `RenderAPI`, forwarding every call to it
+ * while capturing a `Trace` of the calls that succeeded.
+ */
+#[derive(Debug)]
+pub struct TracingRenderAPI<R> where R: RenderAPI {
+  inner: R,
+  calls: RefCell<Vec<TraceCall>>,
+  attribute_names: RefCell<HashMap<(Option<usize>, AttributeIndex), String>>,
+  programs: RefCell<Vec<R::Program>>,
+  program: Cell<Option<usize>>,
+  next_buffer_id: Cell<usize>,
+  next_texture_id: Cell<usize>,
+  next_framebuffer_id: Cell<usize>,
+  next_renderbuffer_id: Cell<usize>,
+  next_vertex_array_id: Cell<usize>,
+}
+
+impl<R> TracingRenderAPI<R> where R: RenderAPI {
+  pub fn create(inner: R) -> Self {
+    TracingRenderAPI {
+      inner,
+      calls: RefCell::new(vec![]),
+      attribute_names: RefCell::new(HashMap::new()),
+      programs: RefCell::new(vec![]),
+      program: Cell::new(None),
+      next_buffer_id: Cell::new(0),
+      next_texture_id: Cell::new(0),
+      next_framebuffer_id: Cell::new(0),
+      next_renderbuffer_id: Cell::new(0),
+      next_vertex_array_id: Cell::new(0),
+    }
+  }
+
+  pub fn inner(&self) -> &R {
+    &self.inner
+  }
+
+  /**
+   * A copy of the trace captured so far.
+   */
+  pub fn trace(&self) -> Trace {
+    Trace { version: TRACE_VERSION, calls: self.calls.borrow().clone() }
+  }
+
+  /**
+   * Removes & returns the trace captured so far.
+   */
+  pub fn take_trace(&self) -> Trace {
+    Trace { version: TRACE_VERSION, calls: self.calls.replace(vec![]) }
+  }
+
+  fn record(&self, call: TraceCall) {
+    self.calls.borrow_mut().push(call);
+  }
+
+  fn trace_attribute(&self, index: AttributeIndex) -> TraceAttribute {
+    match self.attribute_names.borrow().get(&(self.program.get(), index)) {
+      Some(name) => TraceAttribute::Name(name.clone()),
+      None => TraceAttribute::Index(index),
+    }
+  }
+}
+
+impl<R> RenderAPI for TracingRenderAPI<R> where R: RenderAPI {
+  type Buffer = TracedBuffer<R::Buffer>;
+  type Program = R::Program;
+  type UniformIndex = TraceKey;
+  type UniformBlockIndex = TraceKey;
+  type Texture = TracedTexture<R::Texture>;
+  type Framebuffer = TracedFramebuffer<R::Framebuffer>;
+  type Renderbuffer = TracedRenderbuffer<R::Renderbuffer>;
+  type VertexArray = TracedVertexArray<R::VertexArray>;
+
+  fn bind_buffer(&self, buffer: &Self::Buffer) {
+    self.inner.bind_buffer(&buffer.inner);
+    self.record(TraceCall::BindBuffer { buffer: buffer.id });
+  }
+
+  fn bind_buffer_base(&self, buffer: &Self::Buffer, index: u32) {
+    self.inner.bind_buffer_base(&buffer.inner, index);
+    self.record(TraceCall::BindBufferBase { buffer: buffer.id, index });
+  }
+
+  fn bind_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>) {
+    self.inner.bind_framebuffer(framebuffer.map(|f| &f.inner));
+    self.record(TraceCall::BindFramebuffer { framebuffer: framebuffer.map(|f| f.id) });
+  }
+
+  fn bind_texture(&self, unit: TextureUnit, texture: &Self::Texture) {
+    self.inner.bind_texture(unit, &texture.inner);
+    self.record(TraceCall::BindTexture { unit, texture: texture.id });
+  }
+
+  fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>) {
+    self.inner.bind_vertex_array(vertex_array.map(|v| &v.inner));
+    self.record(TraceCall::BindVertexArray { vertex_array: vertex_array.map(|v| v.id) });
+  }
+
+  fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    self.inner.blend_color(red, green, blue, alpha);
+    self.record(TraceCall::BlendColor { red, green, blue, alpha });
+  }
+
+  fn blend_func(&self, src: BlendFuncFactor, dst: BlendFuncFactor) {
+    self.inner.blend_func(src, dst);
+    self.record(TraceCall::BlendFunc { src, dst });
+  }
+
+  fn buffer_data<V>(
+      &self,
+      buffer: &Self::Buffer,
+      view: &V,
+      draw_kind: DrawKind,
+  ) -> Result<(), RenderApiError> where V: View {
+    self.inner.buffer_data(&buffer.inner, view, draw_kind)?;
+    self.record(TraceCall::BufferData {
+      buffer: buffer.id,
+      precision: view.get_precision(),
+      data: view.to_bytes(),
+      draw_kind,
+    });
+    Ok(())
+  }
+
+  fn buffer_data_with_size(&self, buffer: &Self::Buffer, size: usize, draw_kind: DrawKind) {
+    self.inner.buffer_data_with_size(&buffer.inner, size, draw_kind);
+    self.record(TraceCall::BufferDataWithSize { buffer: buffer.id, size, draw_kind });
+  }
+
+  fn buffer_sub_data<V>(&self, buffer: &Self::Buffer, offset: usize, view: &V) -> Result<(), RenderApiError> where V: View {
+    self.inner.buffer_sub_data(&buffer.inner, offset, view)?;
+    self.record(TraceCall::BufferSubData {
+      buffer: buffer.id,
+      offset,
+      precision: view.get_precision(),
+      data: view.to_bytes(),
+    });
+    Ok(())
+  }
+
+  /**
+   * Recorded even when incomplete, as the framebuffer is still
+   * bound either way.
+   */
+  fn check_framebuffer_status(&self, framebuffer: &Self::Framebuffer) -> Result<(), RenderApiError> {
+    let status = self.inner.check_framebuffer_status(&framebuffer.inner);
+    self.record(TraceCall::CheckFramebufferStatus { framebuffer: framebuffer.id });
+    status
+  }
+
+  fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    self.inner.clear_color(red, green, blue, alpha);
+    self.record(TraceCall::ClearColor { red, green, blue, alpha });
+  }
+
+  fn clear<M>(&self, mask: M) where M: Into<ClearMasks> {
+    let mask = mask.into();
+    self.inner.clear(mask);
+    self.record(TraceCall::Clear(mask));
+  }
+
+  fn create_buffer(&self, kind: BufferKind) -> Result<Self::Buffer, RenderApiError> {
+    let inner = self.inner.create_buffer(kind)?;
+    let id = self.next_buffer_id.get();
+    self.next_buffer_id.set(id + 1);
+    self.record(TraceCall::CreateBuffer { buffer: id, kind });
+    Ok(TracedBuffer { id, inner })
+  }
+
+  fn create_framebuffer(&self) -> Result<Self::Framebuffer, RenderApiError> {
+    let inner = self.inner.create_framebuffer()?;
+    let id = self.next_framebuffer_id.get();
+    self.next_framebuffer_id.set(id + 1);
+    self.record(TraceCall::CreateFramebuffer { framebuffer: id });
+    Ok(TracedFramebuffer { id, inner })
+  }
+
+  fn create_renderbuffer(&self) -> Result<Self::Renderbuffer, RenderApiError> {
+    let inner = self.inner.create_renderbuffer()?;
+    let id = self.next_renderbuffer_id.get();
+    self.next_renderbuffer_id.set(id + 1);
+    self.record(TraceCall::CreateRenderbuffer { renderbuffer: id });
+    Ok(TracedRenderbuffer { id, inner })
+  }
+
+  fn create_texture(&self) -> Result<Self::Texture, RenderApiError> {
+    let inner = self.inner.create_texture()?;
+    let id = self.next_texture_id.get();
+    self.next_texture_id.set(id + 1);
+    self.record(TraceCall::CreateTexture { texture: id });
+    Ok(TracedTexture { id, inner })
+  }
+
+  fn create_vertex_array(&self) -> Result<Self::VertexArray, RenderApiError> {
+    let inner = self.inner.create_vertex_array()?;
+    let id = self.next_vertex_array_id.get();
+    self.next_vertex_array_id.set(id + 1);
+    self.record(TraceCall::CreateVertexArray { vertex_array: id });
+    Ok(TracedVertexArray { id, inner })
+  }
+
+  fn draw_arrays(&self, mode: DrawArrayKind, first: i32, count: i32) {
+    self.inner.draw_arrays(mode, first, count);
+    self.record(TraceCall::DrawArrays { mode, first, count });
+  }
+
+  fn draw_arrays_instanced(&self, mode: DrawArrayKind, first: i32, count: i32, instance_count: i32) {
+    self.inner.draw_arrays_instanced(mode, first, count, instance_count);
+    self.record(TraceCall::DrawArraysInstanced { mode, first, count, instance_count });
+  }
+
+  fn draw_elements(&self, mode: DrawArrayKind, count: i32, kind: IndexKind, offset: i32) {
+    self.inner.draw_elements(mode, count, kind, offset);
+    self.record(TraceCall::DrawElements { mode, count, kind, offset });
+  }
+
+  fn draw_elements_instanced(
+      &self,
+      mode: DrawArrayKind,
+      count: i32,
+      kind: IndexKind,
+      offset: i32,
+      instance_count: i32,
+  ) {
+    self.inner.draw_elements_instanced(mode, count, kind, offset, instance_count);
+    self.record(TraceCall::DrawElementsInstanced { mode, count, kind, offset, instance_count });
+  }
+
+  fn enable_vertex_attrib_array<A>(&self, key: A) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    let index = key.with_context(self)?;
+    self.inner.enable_vertex_attrib_array(index)?;
+    self.record(TraceCall::EnableVertexAttribArray(self.trace_attribute(index)));
+    Ok(())
+  }
+
+  fn framebuffer_renderbuffer(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      renderbuffer: &Self::Renderbuffer,
+  ) {
+    self.inner.framebuffer_renderbuffer(&framebuffer.inner, attachment, &renderbuffer.inner);
+    self.record(TraceCall::FramebufferRenderbuffer {
+      framebuffer: framebuffer.id,
+      attachment,
+      renderbuffer: renderbuffer.id,
+    });
+  }
+
+  fn framebuffer_texture_2d(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      texture: &Self::Texture,
+  ) {
+    self.inner.framebuffer_texture_2d(&framebuffer.inner, attachment, &texture.inner);
+    self.record(TraceCall::FramebufferTexture2D {
+      framebuffer: framebuffer.id,
+      attachment,
+      texture: texture.id,
+    });
+  }
+
+  fn generate_mipmap(&self, texture: &Self::Texture) {
+    self.inner.generate_mipmap(&texture.inner);
+    self.record(TraceCall::GenerateMipmap { texture: texture.id });
+  }
+
+  fn get_attribute<AK>(&self, key: AK) -> Result<AttributeIndex, RenderApiError> where AK: AttributeKey {
+    let name = key.name().to_string();
+    let index = self.inner.get_attribute(key)?;
+    self.attribute_names.borrow_mut().insert((self.program.get(), index), name);
+    Ok(index)
+  }
+
+  fn get_uniform<UK>(&self, key: UK) -> Result<Self::UniformIndex, RenderApiError> where UK: UniformKey {
+    let name = key.name().to_string();
+    self.inner.get_uniform(key).map(|_| TraceKey(name))
+  }
+
+  fn get_uniform_block<BK>(&self, key: BK) -> Result<Self::UniformBlockIndex, RenderApiError> where BK: UniformBlockKey {
+    let name = key.name().to_string();
+    self.inner.get_uniform_block(key).map(|_| TraceKey(name))
+  }
+
+  fn read_pixels(&self, x: i32, y: i32, width: i32, height: i32) -> Result<Vec<u8>, RenderApiError> {
+    let pixels = self.inner.read_pixels(x, y, width, height)?;
+    self.record(TraceCall::ReadPixels { x, y, width, height });
+    Ok(pixels)
+  }
+
+  fn renderbuffer_storage(
+      &self,
+      renderbuffer: &Self::Renderbuffer,
+      format: RenderbufferFormat,
+      width: i32,
+      height: i32,
+  ) {
+    self.inner.renderbuffer_storage(&renderbuffer.inner, format, width, height);
+    self.record(TraceCall::RenderbufferStorage { renderbuffer: renderbuffer.id, format, width, height });
+  }
+
+  fn set_pipeline_state(&self, state: &PipelineState) {
+    self.inner.set_pipeline_state(state);
+    self.record(TraceCall::SetPipelineState(*state));
+  }
+
+  fn set_uniform<U>(&self, key: U, value: UniformValue) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    let uniform = key.with_context(self)?;
+    self.inner.set_uniform(uniform.clone(), value.clone())?;
+    self.record(TraceCall::SetUniform { uniform: uniform.0, value });
+    Ok(())
+  }
+
+  fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+    self.inner.set_viewport(x, y, width, height);
+    self.record(TraceCall::SetViewport { x, y, width, height });
+  }
+
+  fn tex_image_2d(
+      &self,
+      texture: &Self::Texture,
+      width: i32,
+      height: i32,
+      pixels: &Uint8View,
+  ) -> Result<(), RenderApiError> {
+    self.inner.tex_image_2d(&texture.inner, width, height, pixels)?;
+    let data = pixels.to_bytes();
+    self.record(TraceCall::TexImage2D { texture: texture.id, width, height, data });
+    Ok(())
+  }
+
+  fn tex_parameter(&self, texture: &Self::Texture, parameter: TextureParameter) {
+    self.inner.tex_parameter(&texture.inner, parameter);
+    self.record(TraceCall::TexParameter { texture: texture.id, parameter });
+  }
+
+  fn uniform_block_binding<B>(&self, key: B, binding: u32) -> Result<(), RenderApiError> where B: IntoUniformBlockIndex {
+    let block = key.with_context(self)?;
+    self.inner.uniform_block_binding(block.clone(), binding)?;
+    self.record(TraceCall::UniformBlockBinding { block: block.0, binding });
+    Ok(())
+  }
+
+  /**
+   * The program in use before the first call to this isn't
+   * known, so attributes looked up before then are only
+   * named while that program is in use.
+   */
+  fn use_program(&self, program: &Self::Program) {
+    self.inner.use_program(program);
+    let id = {
+      let mut programs = self.programs.borrow_mut();
+      match programs.iter().position(|p| p == program) {
+        Some(id) => id,
+        None => {
+          programs.push(program.clone());
+          programs.len() - 1
+        },
+      }
+    };
+    self.program.set(Some(id));
+    self.record(TraceCall::UseProgram { program: id });
+  }
+
+  fn vertex_attrib_divisor<A>(&self, key: A, divisor: u32) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    let index = key.with_context(self)?;
+    self.inner.vertex_attrib_divisor(index, divisor)?;
+    self.record(TraceCall::VertexAttribDivisor { attribute: self.trace_attribute(index), divisor });
+    Ok(())
+  }
+
+  fn vertex_attrib_i_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    let index = key.with_context(self)?;
+    self.inner.vertex_attrib_i_pointer_with_i32(index, size, precision, stride, offset)?;
+    self.record(TraceCall::VertexAttribIPointer {
+      attribute: self.trace_attribute(index),
+      size,
+      precision,
+      stride,
+      offset,
+    });
+    Ok(())
+  }
+
+  fn vertex_attrib_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      normalized: bool,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    let index = key.with_context(self)?;
+    self.inner.vertex_attrib_pointer_with_i32(index, size, precision, normalized, stride, offset)?;
+    self.record(TraceCall::VertexAttribPointer {
+      attribute: self.trace_attribute(index),
+      size,
+      precision,
+      normalized,
+      stride,
+      offset,
+    });
+    Ok(())
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TracedBuffer<B> {
+  pub id: usize,
+  pub inner: B,
+}
+
+impl<B> HasBufferKind for TracedBuffer<B> where B: HasBufferKind {
+  fn buffer_kind_constant(&self) -> u32 {
+    self.inner.buffer_kind_constant()
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TracedTexture<T> {
+  pub id: usize,
+  pub inner: T,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TracedFramebuffer<F> {
+  pub id: usize,
+  pub inner: F,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TracedRenderbuffer<R> {
+  pub id: usize,
+  pub inner: R,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TracedVertexArray<V> {
+  pub id: usize,
+  pub inner: V,
+}
+
+#[derive(Debug)]
+pub enum TraceError {
+  Json(String),
+  Binary(String),
+  UnsupportedVersion(u32),
+}
+
+impl fmt::Display for TraceError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TraceError::Json(e) => write!(f, "Invalid JSON trace, {}", e),
+      TraceError::Binary(e) => write!(f, "Invalid binary trace, {}", e),
+      TraceError::UnsupportedVersion(v) => write!(f, "Unsupported trace version, {}", v),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+  RenderApiError(RenderApiError),
+  DataViewError(DataViewError),
+  UnknownBuffer(usize),
+  UnknownFramebuffer(usize),
+  UnknownRenderbuffer(usize),
+  UnknownTexture(usize),
+  UnknownVertexArray(usize),
+  UnknownProgram(usize),
+}
+
+impl From<RenderApiError> for ReplayError {
+  fn from(error: RenderApiError) -> Self {
+    ReplayError::RenderApiError(error)
+  }
+}
+
+impl fmt::Display for ReplayError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ReplayError::RenderApiError(e) => write!(f, "replay RenderApiError: {}", e.to_string()),
+      ReplayError::DataViewError(e) => write!(f, "replay DataViewError: {}", e.to_string()),
+      ReplayError::UnknownBuffer(id) => write!(f, "replay used buffer {} before creating it", id),
+      ReplayError::UnknownFramebuffer(id) => write!(f, "replay used framebuffer {} before creating it", id),
+      ReplayError::UnknownRenderbuffer(id) => write!(f, "replay used renderbuffer {} before creating it", id),
+      ReplayError::UnknownTexture(id) => write!(f, "replay used texture {} before creating it", id),
+      ReplayError::UnknownVertexArray(id) => write!(f, "replay used vertex array {} before creating it", id),
+      ReplayError::UnknownProgram(id) => write!(f, "replay used program {} without being given it", id),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::constants::{TextureMinFilter};
+  use super::super::recording::{Command, RecordingProgram, RecordingRenderAPI};
+  use super::super::super::render_loop::{RenderLoop};
+
+  fn recording_api() -> RecordingRenderAPI {
+    RecordingRenderAPI::create(&["position", "cell"], &["resolution", "origin", "spacing", "size"])
+  }
+
+  fn traced_frames() -> (Trace, RecordingRenderAPI) {
+    let api = TracingRenderAPI::create(recording_api());
+    let mut render_loop = RenderLoop::create(api, 64, 32).unwrap();
+    render_loop.draw();
+    render_loop.update_viewport(32, 64).unwrap();
+    render_loop.draw();
+
+    let api = render_loop.into_context();
+    (api.take_trace(), api.inner)
+  }
+
+  #[test]
+  fn replay_reproduces_calls() {
+    let (trace, original) = traced_frames();
+    let replayed = recording_api();
+    trace.replay(&replayed).unwrap();
+    assert_eq!(replayed.commands(), original.commands());
+  }
+
+  #[test]
+  fn json_round_trip() {
+    let (trace, _) = traced_frames();
+    let json = trace.to_json().unwrap();
+    assert_eq!(Trace::from_json(&json).unwrap(), trace);
+  }
+
+  #[test]
+  fn binary_round_trip() {
+    let (trace, _) = traced_frames();
+    let binary = trace.to_binary().unwrap();
+    assert!(binary.len() < trace.to_json().unwrap().len());
+    assert_eq!(Trace::from_binary(&binary).unwrap(), trace);
+  }
+
+  #[test]
+  fn rejects_other_versions() {
+    let trace = Trace { version: TRACE_VERSION + 1, calls: vec![] };
+    match Trace::from_json(&trace.to_json().unwrap()) {
+      Err(TraceError::UnsupportedVersion(v)) => assert_eq!(v, TRACE_VERSION + 1),
+      other => panic!("expected unsupported version, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn replay_reproduces_texture_and_framebuffer_calls() {
+    let api = TracingRenderAPI::create(RecordingRenderAPI::create(&[], &["sampler"]));
+    let texture = api.create_texture().unwrap();
+    let pixels = Uint8View::create(&[255, 0, 0, 255]).unwrap();
+    api.tex_image_2d(&texture, 1, 1, &pixels).unwrap();
+    api.tex_parameter(&texture, TextureParameter::MinFilter(TextureMinFilter::Linear));
+    api.generate_mipmap(&texture);
+    api.bind_texture(TextureUnit(2), &texture);
+    api.uniform1i(TraceKey("sampler".to_string()), 2).unwrap();
+
+    let framebuffer = api.create_framebuffer().unwrap();
+    let depth = api.create_renderbuffer().unwrap();
+    api.renderbuffer_storage(&depth, RenderbufferFormat::DepthComponent16, 1, 1);
+    api.framebuffer_texture_2d(&framebuffer, FramebufferAttachment::Color(0), &texture);
+    api.framebuffer_renderbuffer(&framebuffer, FramebufferAttachment::Depth, &depth);
+    api.check_framebuffer_status(&framebuffer).unwrap();
+    api.read_pixels(0, 0, 1, 1).unwrap();
+    api.bind_framebuffer(None);
+
+    let replayed = RecordingRenderAPI::create(&[], &["sampler"]);
+    api.take_trace().replay(&replayed).unwrap();
+    assert_eq!(replayed.commands(), api.inner.commands());
+  }
+
+  #[test]
+  fn replay_fails_on_unknown_buffer() {
+    let trace = Trace {
+      version: TRACE_VERSION,
+      calls: vec![TraceCall::BufferData {
+        buffer: 3,
+        precision: ViewPrecision::Float,
+        data: vec![],
+        draw_kind: DrawKind::StaticDraw,
+      }],
+    };
+    match trace.replay(&recording_api()) {
+      Err(ReplayError::UnknownBuffer(id)) => assert_eq!(id, 3),
+      other => panic!("expected unknown buffer, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn replay_reproduces_uniform_block_calls() {
+    let uniform_blocks = ["Camera"];
+    let api = TracingRenderAPI::create(RecordingRenderAPI::create(&[], &[]).with_uniform_blocks(&uniform_blocks));
+    let buffer = api.create_buffer(BufferKind::UniformBuffer).unwrap();
+    let camera = Uint8View::create(&[0; 16]).unwrap();
+    api.buffer_data(&buffer, &camera, DrawKind::DynamicDraw).unwrap();
+    api.bind_buffer_base(&buffer, 1);
+    api.uniform_block_binding(TraceKey("Camera".to_string()), 1).unwrap();
+
+    let replayed = RecordingRenderAPI::create(&[], &[]).with_uniform_blocks(&uniform_blocks);
+    api.take_trace().replay(&replayed).unwrap();
+    assert_eq!(replayed.commands(), api.inner.commands());
+  }
+
+  #[test]
+  fn replay_uses_programs_in_the_order_they_were_first_used() {
+    let api = TracingRenderAPI::create(RecordingRenderAPI::create(&["position"], &[]));
+    let other = api.inner().add_program(&["normal", "position"], &[]);
+    api.use_program(&other);
+    api.enable_vertex_attrib_array(TraceKey("position".to_string())).unwrap();
+    api.use_program(&RecordingProgram(0));
+    api.use_program(&other);
+
+    let trace = api.take_trace();
+    assert_eq!(trace.calls[0], TraceCall::UseProgram { program: 0 });
+    assert_eq!(trace.calls[2], TraceCall::UseProgram { program: 1 });
+
+    let replayed = RecordingRenderAPI::create(&[], &[]);
+    let programs = [replayed.add_program(&["position"], &[]), RecordingProgram(0)];
+    trace.replay_with_programs(&replayed, &programs).unwrap();
+    assert_eq!(replayed.commands(), vec![
+      Command::UseProgram(RecordingProgram(1)),
+      Command::EnableVertexAttribArray(0),
+      Command::UseProgram(RecordingProgram(0)),
+      Command::UseProgram(RecordingProgram(1)),
+    ]);
+
+    match trace.replay(&RecordingRenderAPI::create(&[], &[])) {
+      Err(ReplayError::UnknownProgram(0)) => (),
+      other => panic!("expected unknown program, got {:?}", other),
+    }
+  }
+}
@@ -0,0 +1,294 @@
+use naga::front::glsl::{Frontend, Options};
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::{AddressSpace, ArraySize, Binding, Module, ScalarKind, TypeInner, VectorSize};
+use super::constants::{ShaderStage, ShaderType};
+use super::reflection::{ActiveVariable, ProgramReflection};
+use std::fmt;
+
+/**
+ * A shader that naga has parsed & validated, along with the
+ * variables it shares with the other stage & the program.
+ */
+#[derive(Debug)]
+pub struct ValidatedShader {
+  pub stage: ShaderStage,
+  pub module: Module,
+  pub inputs: Vec<InterfaceVariable>,
+  pub outputs: Vec<InterfaceVariable>,
+  pub uniforms: Vec<InterfaceVariable>,
+}
+
+/**
+ * A declared variable, where `size` is the length of an
+ * array & 1 for anything else.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterfaceVariable {
+  pub name: String,
+  pub kind: ShaderType,
+  pub size: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+  FailedToParse { stage: ShaderStage, message: String },
+  Invalid { stage: ShaderStage, message: String },
+  MissingEntryPoint(ShaderStage),
+}
+
+impl fmt::Display for ValidationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ValidationError::FailedToParse { stage, message } =>
+        write!(f, "failed to parse {} shader: {}", stage.name(), message),
+      ValidationError::Invalid { stage, message } =>
+        write!(f, "{} shader is invalid: {}", stage.name(), message),
+      ValidationError::MissingEntryPoint(stage) =>
+        write!(f, "{} shader has no main function", stage.name()),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VaryingMismatch {
+  /**
+   * A fragment input the vertex shader doesn't output.
+   */
+  MissingOutput(String),
+  Type { name: String, output: ShaderType, input: ShaderType },
+}
+
+impl fmt::Display for VaryingMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      VaryingMismatch::MissingOutput(name) =>
+        write!(f, "fragment input {} isn't output by the vertex shader", name),
+      VaryingMismatch::Type { name, output, input } => write!(
+        f, "{} is output as {} but input as {}",
+        name, output.glsl_name(), input.glsl_name(),
+      ),
+    }
+  }
+}
+
+/**
+ * Parses & validates a GLSL ES 3.00 shader, after it's been
+ * through the `ShaderPreprocessor` if it uses one.
+ *
+ * naga only parses desktop GLSL 4.40 & up, where every `in`,
+ * `out` & `uniform` needs an explicit location or binding, so
+ * these are added first. They're assigned in order, so the
+ * stages are matched by name like in WebGL rather than by
+ * location. Lines are kept as they are otherwise, so errors
+ * point at the same line.
+ *
+ * naga doesn't support combined samplers like `sampler2D`,
+ * so shaders that use textures can't be validated yet.
+ */
+pub fn validate_shader(stage: ShaderStage, source: &str) -> Result<ValidatedShader, ValidationError> {
+  let source = translate(source);
+  let naga_stage = match stage {
+    ShaderStage::Vertex => naga::ShaderStage::Vertex,
+    ShaderStage::Fragment => naga::ShaderStage::Fragment,
+  };
+
+  let module = Frontend::default().parse(&Options::from(naga_stage), &source).map_err(|errors| {
+    ValidationError::FailedToParse { stage, message: errors.emit_to_string(&source) }
+  })?;
+  Validator::new(ValidationFlags::all(), Capabilities::all()).validate(&module).map_err(|error| {
+    ValidationError::Invalid { stage, message: error.emit_to_string(&source) }
+  })?;
+
+  let entry_point = module.entry_points.iter()
+    .find(|entry_point| entry_point.stage == naga_stage)
+    .ok_or(ValidationError::MissingEntryPoint(stage))?;
+
+  let inputs = entry_point.function.arguments.iter()
+    .filter(|argument| matches!(argument.binding, Some(Binding::Location { .. })))
+    .filter_map(|argument| variable(&module, argument.name.as_ref()?, argument.ty))
+    .collect();
+
+  let outputs = entry_point.function.result.iter()
+    .flat_map(|result| match &module.types[result.ty].inner {
+      TypeInner::Struct { members, .. } => members.clone(),
+      _ => vec![],
+    })
+    .filter(|member| matches!(member.binding, Some(Binding::Location { .. })))
+    .filter_map(|member| variable(&module, member.name.as_ref()?, member.ty))
+    .collect();
+
+  let uniforms = module.global_variables.iter()
+    .filter(|(_, global)| global.space == AddressSpace::Uniform)
+    .filter_map(|(_, global)| variable(&module, global.name.as_ref()?, global.ty))
+    .collect();
+
+  Ok(ValidatedShader { stage, module, inputs, outputs, uniforms })
+}
+
+/**
+ * Checks every fragment input is output by the vertex shader
+ * with the same type.
+ */
+pub fn check_varyings(vert: &ValidatedShader, frag: &ValidatedShader) -> Result<(), Vec<VaryingMismatch>> {
+  let mismatches: Vec<VaryingMismatch> = frag.inputs.iter().filter_map(|input| {
+    match vert.outputs.iter().find(|output| output.name == input.name) {
+      None => Some(VaryingMismatch::MissingOutput(input.name.clone())),
+      Some(output) if output.kind != input.kind => Some(VaryingMismatch::Type {
+        name: input.name.clone(),
+        output: output.kind,
+        input: input.kind,
+      }),
+      Some(_) => None,
+    }
+  }).collect();
+
+  if mismatches.is_empty() { Ok(()) } else { Err(mismatches) }
+}
+
+/**
+ * The variables a program linked from both shaders would
+ * have, so keys can be checked with `check_keys` without a
+ * context. Unlike a linked program, variables the compiler
+ * would remove are included, & nothing has a location.
+ */
+pub fn reflect_program(vert: &ValidatedShader, frag: &ValidatedShader) -> ProgramReflection<()> {
+  let attributes = vert.inputs.iter().enumerate().map(|(index, input)| {
+    ActiveVariable { name: input.name.clone(), kind: input.kind, size: input.size, location: index as u32 }
+  }).collect();
+
+  let mut uniforms: Vec<ActiveVariable<Option<()>>> = vec![];
+  for uniform in vert.uniforms.iter().chain(frag.uniforms.iter()) {
+    if uniforms.iter().all(|u| u.name != uniform.name) {
+      uniforms.push(ActiveVariable { name: uniform.name.clone(), kind: uniform.kind, size: uniform.size, location: None });
+    }
+  }
+
+  ProgramReflection { attributes, uniforms }
+}
+
+fn variable(module: &Module, name: &str, ty: naga::Handle<naga::Type>) -> Option<InterfaceVariable> {
+  let (ty, size) = match module.types[ty].inner {
+    TypeInner::Array { base, size: ArraySize::Constant(size), .. } => (base, size.get() as i32),
+    _ => (ty, 1),
+  };
+  Some(InterfaceVariable { name: name.to_string(), kind: shader_type(&module.types[ty].inner), size })
+}
+
+fn shader_type(inner: &TypeInner) -> ShaderType {
+  let vector = |size, vec2, vec3, vec4| match size {
+    VectorSize::Bi => vec2,
+    VectorSize::Tri => vec3,
+    VectorSize::Quad => vec4,
+  };
+
+  match *inner {
+    TypeInner::Scalar(scalar) => match scalar.kind {
+      ScalarKind::Float => ShaderType::Float,
+      ScalarKind::Sint => ShaderType::Int,
+      ScalarKind::Uint => ShaderType::UnsignedInt,
+      ScalarKind::Bool => ShaderType::Bool,
+      _ => ShaderType::Unknown(0),
+    },
+    TypeInner::Vector { size, scalar } => match scalar.kind {
+      ScalarKind::Float => vector(size, ShaderType::FloatVec2, ShaderType::FloatVec3, ShaderType::FloatVec4),
+      ScalarKind::Sint => vector(size, ShaderType::IntVec2, ShaderType::IntVec3, ShaderType::IntVec4),
+      ScalarKind::Uint => vector(size, ShaderType::UnsignedIntVec2, ShaderType::UnsignedIntVec3, ShaderType::UnsignedIntVec4),
+      ScalarKind::Bool => vector(size, ShaderType::BoolVec2, ShaderType::BoolVec3, ShaderType::BoolVec4),
+      _ => ShaderType::Unknown(0),
+    },
+    TypeInner::Matrix { columns, rows, .. } if columns == rows =>
+      vector(columns, ShaderType::FloatMat2, ShaderType::FloatMat3, ShaderType::FloatMat4),
+    _ => ShaderType::Unknown(0),
+  }
+}
+
+fn translate(source: &str) -> String {
+  let (mut inputs, mut outputs, mut bindings) = (0, 0, 0);
+  let next = |count: &mut u32| { *count += 1; *count - 1 };
+
+  source.lines().map(|line| {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("#version") {
+      return "#version 450 core".to_string();
+    }
+
+    let storage = trimmed.split_whitespace()
+      .find(|word| !matches!(*word, "flat" | "smooth" | "centroid" | "highp" | "mediump" | "lowp"));
+    match storage {
+      _ if trimmed.starts_with("layout") => line.to_string(),
+      Some("in") => format!("layout(location = {}) {}", next(&mut inputs), line),
+      Some("out") => format!("layout(location = {}) {}", next(&mut outputs), line),
+      Some("uniform") => format!("layout(binding = {}) {}", next(&mut bindings), line),
+      _ => line.to_string(),
+    }
+  }).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::super::render_loop::{program_keys};
+  use super::super::preprocessor::{ShaderPreprocessor};
+
+  const VERT_SHADER: &str = include_str!("../../../shaders/vert_shader.glsl");
+  const FRAG_SHADER: &str = include_str!("../../../shaders/frag_shader.glsl");
+  const CLIP_SPACE_CHUNK: &str = include_str!("../../../shaders/chunks/clip_space.glsl");
+
+  fn project_shaders() -> (ValidatedShader, ValidatedShader) {
+    let preprocessor = ShaderPreprocessor::create().with_chunk("clip_space", CLIP_SPACE_CHUNK);
+    let vert = preprocessor.process("vertex", VERT_SHADER).unwrap();
+    let frag = preprocessor.process("fragment", FRAG_SHADER).unwrap();
+
+    let vert = validate_shader(ShaderStage::Vertex, &vert.source).map_err(|e| e.to_string()).unwrap();
+    let frag = validate_shader(ShaderStage::Fragment, &frag.source).map_err(|e| e.to_string()).unwrap();
+    (vert, frag)
+  }
+
+  #[test]
+  fn project_shaders_are_valid() {
+    let (vert, frag) = project_shaders();
+    assert_eq!(check_varyings(&vert, &frag), Ok(()));
+  }
+
+  #[test]
+  fn project_shaders_match_the_program_keys() {
+    let (vert, frag) = project_shaders();
+    let reflection = reflect_program(&vert, &frag);
+    let mismatches = reflection.check_keys(&program_keys())
+      .map_err(|mismatches| mismatches.iter().map(|m| m.to_string()).collect::<Vec<_>>());
+    assert_eq!(mismatches, Ok(()));
+  }
+
+  #[test]
+  fn reports_mismatched_varyings() {
+    let vert = "#version 300 es\nout vec3 color;\nvoid main() { color = vec3(1); gl_Position = vec4(0); }";
+    let frag = "#version 300 es\nprecision mediump float;\nin vec4 color;\nin float depth;\nout vec4 outColor;\nvoid main() { outColor = color; }";
+    let vert = validate_shader(ShaderStage::Vertex, vert).unwrap();
+    let frag = validate_shader(ShaderStage::Fragment, frag).unwrap();
+
+    assert_eq!(check_varyings(&vert, &frag), Err(vec![
+      VaryingMismatch::Type { name: "color".to_string(), output: ShaderType::FloatVec3, input: ShaderType::FloatVec4 },
+      VaryingMismatch::MissingOutput("depth".to_string()),
+    ]));
+  }
+
+  #[test]
+  fn reads_uniform_types() {
+    let frag = "#version 300 es\nprecision mediump float;\nuniform mat4 transform;\nuniform vec3 lights[4];\nout vec4 outColor;\nvoid main() { outColor = transform * vec4(lights[0], 1); }";
+    let frag = validate_shader(ShaderStage::Fragment, frag).unwrap();
+
+    assert_eq!(frag.uniforms, vec![
+      InterfaceVariable { name: "transform".to_string(), kind: ShaderType::FloatMat4, size: 1 },
+      InterfaceVariable { name: "lights".to_string(), kind: ShaderType::FloatVec3, size: 4 },
+    ]);
+  }
+
+  #[test]
+  fn reports_errors_in_the_source() {
+    let vert = "#version 300 es\nvoid main() { gl_Position = vec4(missing); }";
+    match validate_shader(ShaderStage::Vertex, vert) {
+      Err(ValidationError::FailedToParse { stage: ShaderStage::Vertex, message }) => assert!(message.contains("missing")),
+      other => panic!("expected a parse error, got {:?}", other),
+    }
+  }
+}
@@ -0,0 +1,579 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use wasm_bindgen::{JsCast};
+use web_sys::{
+  WebGlBuffer,
+  WebGlFramebuffer,
+  WebGlProgram,
+  WebGlRenderbuffer,
+  WebGl2RenderingContext,
+  WebGlTexture,
+  WebGlUniformLocation,
+  WebGlVertexArrayObject,
+};
+use super::api::{
+  check_texture_data,
+  AttributeIndex,
+  AttributeKey,
+  IntoAttributeIndex,
+  IntoUniformBlockIndex,
+  IntoUniformIndex,
+  RenderAPI,
+  RenderApiError,
+  UniformBlockKey,
+  UniformKey,
+  UniformValue,
+};
+use super::constants::{
+  BufferKind,
+  ShaderStage,
+  ShaderType,
+  BlendFuncFactor,
+  Capability,
+  ClearMasks,
+  DrawArrayKind,
+  DrawKind,
+  FramebufferAttachment,
+  FramebufferStatus,
+  IndexKind,
+  RenderbufferFormat,
+  TextureParameter,
+  TextureUnit,
+  ViewPrecision,
+  HasBufferKind,
+  HasBlendFuncFactor,
+  HasViewPrecision,
+  HasCapability,
+  HasClearMaskKind,
+  HasCompareFunc,
+  HasCullFace,
+  HasDrawArrayKind,
+  HasDrawKind,
+  HasFramebufferAttachment,
+  HasIndexKind,
+  HasRenderbufferFormat,
+  HasStencilOp,
+  HasTextureParameter,
+  HasTextureUnit,
+};
+use super::builder::{compile_shader, link_program, BuildError};
+use super::data::{Uint8View, View};
+use super::gl;
+use super::locations::{LocationCache, ProgramKeys};
+use super::pipeline::{ColorMask, DepthState, PipelineState, ScissorRect, StencilState};
+use super::reflection::{ActiveVariable, ProgramReflection};
+
+#[derive(Debug)]
+pub struct WebRenderAPI {
+  gl: WebGl2RenderingContext,
+  program: RefCell<WebProgram>,
+}
+
+/**
+ * A linked program, its active variables & the locations
+ * looked up in it, which are shared by every copy of the
+ * handle.
+ */
+#[derive(Clone, Debug)]
+pub struct WebProgram {
+  program: WebGlProgram,
+  locations: Rc<LocationCache<WebGlUniformLocation>>,
+  reflection: Rc<ProgramReflection<WebGlUniformLocation>>,
+}
+
+impl WebProgram {
+  /**
+   * Expects `program` to be linked, as its active variables
+   * are queried here.
+   */
+  pub fn create(gl: &WebGl2RenderingContext, program: WebGlProgram) -> Self {
+    let reflection = Rc::new(reflect_program(gl, &program));
+    WebProgram { program, locations: Rc::new(LocationCache::create()), reflection }
+  }
+
+  pub fn reflection(&self) -> &ProgramReflection<WebGlUniformLocation> {
+    &self.reflection
+  }
+}
+
+fn reflect_program(gl: &WebGl2RenderingContext, program: &WebGlProgram) -> ProgramReflection<WebGlUniformLocation> {
+  let count = |parameter| gl.get_program_parameter(program, parameter).as_f64().unwrap_or(0.0) as u32;
+
+  // built in attributes like `gl_VertexID` are active but
+  // have no location, so they're left out.
+  let attributes = (0..count(gl::ACTIVE_ATTRIBUTES))
+    .filter_map(|index| gl.get_active_attrib(program, index))
+    .filter_map(|info| {
+      let location = u32::try_from(gl.get_attrib_location(program, &info.name())).ok()?;
+      Some(ActiveVariable {
+        name: info.name(),
+        kind: ShaderType::from_constant(info.type_()),
+        size: info.size(),
+        location,
+      })
+    })
+    .collect();
+
+  let uniforms = (0..count(gl::ACTIVE_UNIFORMS))
+    .filter_map(|index| gl.get_active_uniform(program, index))
+    .map(|info| ActiveVariable {
+      location: gl.get_uniform_location(program, &info.name()),
+      name: info.name(),
+      kind: ShaderType::from_constant(info.type_()),
+      size: info.size(),
+    })
+    .collect();
+
+  ProgramReflection { attributes, uniforms }
+}
+
+impl PartialEq for WebProgram {
+  fn eq(&self, other: &Self) -> bool {
+    self.program == other.program
+  }
+}
+
+impl WebRenderAPI {
+  /**
+   * Expects `program` to already be in use.
+   */
+  pub fn create(gl: WebGl2RenderingContext, program: WebProgram) -> Self {
+    WebRenderAPI { gl, program: RefCell::new(program) }
+  }
+
+  /**
+   * The program that's in use.
+   */
+  pub fn program(&self) -> WebProgram {
+    self.program.borrow().clone()
+  }
+
+  /**
+   * Compiles & links another program from the same context,
+   * which fails if it's missing any of `keys`. The program
+   * in use doesn't change.
+   */
+  pub fn build_program(
+      &self,
+      vert_source: &str,
+      frag_source: &str,
+      keys: &ProgramKeys,
+  ) -> Result<WebProgram, BuildError> {
+    let vert_shader = compile_shader(&self.gl, vert_source, ShaderStage::Vertex)?;
+    let frag_shader = compile_shader(&self.gl, frag_source, ShaderStage::Fragment)?;
+    let program = WebProgram::create(&self.gl, link_program(&self.gl, &vert_shader, &frag_shader)?);
+    program.reflection().check_keys(keys).map_err(BuildError::MismatchedProgramKeys)?;
+
+    // lookups don't need the program to be in use, so it's
+    // only swapped in here while the keys are resolved.
+    let previous = self.program.replace(program.clone());
+    let resolved = keys.resolve(self);
+    self.program.replace(previous);
+    resolved.map_err(BuildError::MissingProgramKey)?;
+    Ok(program)
+  }
+
+  fn set_capability(&self, capability: Capability, enabled: bool) {
+    if enabled {
+      self.gl.enable(capability.capability_constant());
+    } else {
+      self.gl.disable(capability.capability_constant());
+    }
+  }
+
+  /**
+   * Binds the texture to the active unit for the edit, then
+   * binds back what was there so the unit draws with the same
+   * texture as before.
+   */
+  fn edit_texture<T>(&self, texture: &WebGlTexture, edit: impl FnOnce() -> T) -> T {
+    let previous = self.gl.get_parameter(gl::TEXTURE_BINDING_2D).ok()
+      .and_then(|value| value.dyn_into::<WebGlTexture>().ok());
+    self.gl.bind_texture(gl::TEXTURE_2D, Some(texture));
+    let result = edit();
+    self.gl.bind_texture(gl::TEXTURE_2D, previous.as_ref());
+    result
+  }
+}
+
+/**
+ * The Web Gl binding for the interface.
+ */
+impl RenderAPI for WebRenderAPI {
+  type Buffer = WebRenderBuffer;
+  type Program = WebProgram;
+  type UniformIndex = WebGlUniformLocation;
+  type UniformBlockIndex = u32;
+  type Texture = WebGlTexture;
+  type Framebuffer = WebGlFramebuffer;
+  type Renderbuffer = WebGlRenderbuffer;
+  type VertexArray = WebGlVertexArrayObject;
+
+  fn bind_buffer(&self, buffer: &Self::Buffer) {
+    self.gl.bind_buffer(buffer.buffer_kind_constant(), Some(&buffer.internal));
+  }
+
+  fn bind_buffer_base(&self, buffer: &Self::Buffer, index: u32) {
+    self.gl.bind_buffer_base(buffer.buffer_kind_constant(), index, Some(&buffer.internal));
+  }
+
+  fn bind_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>) {
+    self.gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer);
+  }
+
+  fn bind_texture(&self, unit: TextureUnit, texture: &Self::Texture) {
+    self.gl.active_texture(unit.texture_unit_constant());
+    self.gl.bind_texture(gl::TEXTURE_2D, Some(texture));
+  }
+
+  fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>) {
+    self.gl.bind_vertex_array(vertex_array);
+  }
+
+  fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    self.gl.blend_color(red, green, blue, alpha);
+  }
+
+  fn blend_func(&self, src: BlendFuncFactor, dst: BlendFuncFactor) {
+    self.gl.blend_func(src.blend_func_factor_constant(), dst.blend_func_factor_constant());
+  }
+
+  fn buffer_data<V>(
+      &self,
+      buffer: &Self::Buffer,
+      view: &V,
+      draw_kind: DrawKind,
+  ) -> Result<(), RenderApiError> where V: View {
+    let kind = buffer.buffer_kind_constant();
+    let draw = draw_kind.draw_kind_constant();
+    let object = view.object()?;
+    self.gl.bind_buffer(kind, Some(&buffer.internal));
+    self.gl.buffer_data_with_array_buffer_view(kind, &object, draw);
+    Ok(())
+  }
+
+  fn buffer_data_with_size(&self, buffer: &Self::Buffer, size: usize, draw_kind: DrawKind) {
+    let kind = buffer.buffer_kind_constant();
+    self.gl.bind_buffer(kind, Some(&buffer.internal));
+    self.gl.buffer_data_with_i32(kind, size as i32, draw_kind.draw_kind_constant())
+  }
+
+  fn buffer_sub_data<V>(&self, buffer: &Self::Buffer, offset: usize, view: &V) -> Result<(), RenderApiError> where V: View {
+    let kind = buffer.buffer_kind_constant();
+    let object = view.object()?;
+    self.gl.bind_buffer(kind, Some(&buffer.internal));
+    self.gl.buffer_sub_data_with_i32_and_array_buffer_view(kind, offset as i32, &object);
+    Ok(())
+  }
+
+  fn check_framebuffer_status(&self, framebuffer: &Self::Framebuffer) -> Result<(), RenderApiError> {
+    self.gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer));
+    let status = self.gl.check_framebuffer_status(gl::FRAMEBUFFER);
+    FramebufferStatus::check(status).map_err(RenderApiError::IncompleteFramebuffer)
+  }
+
+  fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+    self.gl.clear_color(red, green, blue, alpha);
+  }
+
+  fn clear<M>(&self, mask: M) where M: Into<ClearMasks> {
+    self.gl.clear(mask.into().clear_mask_constant());
+  }
+
+  fn create_buffer(&self, kind: BufferKind) -> Result<Self::Buffer, RenderApiError> {
+    self.gl.create_buffer().ok_or(RenderApiError::FailedToCreateBuffer).map(|internal| {
+      WebRenderBuffer { kind, internal }
+    })
+  }
+
+  fn create_framebuffer(&self) -> Result<Self::Framebuffer, RenderApiError> {
+    self.gl.create_framebuffer().ok_or(RenderApiError::FailedToCreateFramebuffer)
+  }
+
+  fn create_renderbuffer(&self) -> Result<Self::Renderbuffer, RenderApiError> {
+    self.gl.create_renderbuffer().ok_or(RenderApiError::FailedToCreateRenderbuffer)
+  }
+
+  fn create_texture(&self) -> Result<Self::Texture, RenderApiError> {
+    self.gl.create_texture().ok_or(RenderApiError::FailedToCreateTexture)
+  }
+
+  fn create_vertex_array(&self) -> Result<Self::VertexArray, RenderApiError> {
+    self.gl.create_vertex_array().ok_or(RenderApiError::FailedToCreateVertexArray)
+  }
+
+  fn draw_arrays(&self, mode: DrawArrayKind, first: i32, count: i32) {
+    self.gl.draw_arrays(mode.draw_array_kind_constant(), first, count);
+  }
+
+  fn draw_arrays_instanced(&self, mode: DrawArrayKind, first: i32, count: i32, instance_count: i32) {
+    self.gl.draw_arrays_instanced(mode.draw_array_kind_constant(), first, count, instance_count);
+  }
+
+  fn draw_elements(&self, mode: DrawArrayKind, count: i32, kind: IndexKind, offset: i32) {
+    self.gl.draw_elements_with_i32(
+        mode.draw_array_kind_constant(),
+        count,
+        kind.index_kind_constant(),
+        offset,
+    );
+  }
+
+  fn draw_elements_instanced(
+      &self,
+      mode: DrawArrayKind,
+      count: i32,
+      kind: IndexKind,
+      offset: i32,
+      instance_count: i32,
+  ) {
+    self.gl.draw_elements_instanced_with_i32(
+        mode.draw_array_kind_constant(),
+        count,
+        kind.index_kind_constant(),
+        offset,
+        instance_count,
+    );
+  }
+
+  fn enable_vertex_attrib_array<A>(&self, key: A) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|i| self.gl.enable_vertex_attrib_array(i))
+  }
+
+  fn framebuffer_renderbuffer(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      renderbuffer: &Self::Renderbuffer,
+  ) {
+    self.gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer));
+    self.gl.framebuffer_renderbuffer(
+        gl::FRAMEBUFFER,
+        attachment.framebuffer_attachment_constant(),
+        gl::RENDERBUFFER,
+        Some(renderbuffer),
+    );
+  }
+
+  fn framebuffer_texture_2d(
+      &self,
+      framebuffer: &Self::Framebuffer,
+      attachment: FramebufferAttachment,
+      texture: &Self::Texture,
+  ) {
+    self.gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer));
+    self.gl.framebuffer_texture_2d(
+        gl::FRAMEBUFFER,
+        attachment.framebuffer_attachment_constant(),
+        gl::TEXTURE_2D,
+        Some(texture),
+        0,
+    );
+  }
+
+  fn generate_mipmap(&self, texture: &Self::Texture) {
+    self.edit_texture(texture, || self.gl.generate_mipmap(gl::TEXTURE_2D));
+  }
+
+  fn get_attribute<AK>(&self, key: AK) -> Result<AttributeIndex, RenderApiError> where AK: AttributeKey {
+    let program = self.program.borrow();
+    program.locations.attribute(key.name(), || {
+      u32::try_from(self.gl.get_attrib_location(&program.program, key.name())).ok()
+    })
+  }
+
+  fn get_uniform<UK>(&self, key: UK) -> Result<Self::UniformIndex, RenderApiError> where UK: UniformKey {
+    let program = self.program.borrow();
+    program.locations.uniform(key.name(), || self.gl.get_uniform_location(&program.program, key.name()))
+  }
+
+  fn get_uniform_block<BK>(&self, key: BK) -> Result<Self::UniformBlockIndex, RenderApiError> where BK: UniformBlockKey {
+    let program = self.program.borrow();
+    program.locations.uniform_block(key.name(), || {
+      match self.gl.get_uniform_block_index(&program.program, key.name()) {
+        gl::INVALID_INDEX => None,
+        index => Some(index),
+      }
+    })
+  }
+
+  fn read_pixels(&self, x: i32, y: i32, width: i32, height: i32) -> Result<Vec<u8>, RenderApiError> {
+    let mut pixels = vec![0; width.max(0) as usize * height.max(0) as usize * 4];
+    self.gl.read_pixels_with_opt_u8_array(
+        x,
+        y,
+        width,
+        height,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        Some(&mut pixels),
+    ).map_err(|_| RenderApiError::FailedToReadPixels)?;
+    Ok(pixels)
+  }
+
+  fn renderbuffer_storage(
+      &self,
+      renderbuffer: &Self::Renderbuffer,
+      format: RenderbufferFormat,
+      width: i32,
+      height: i32,
+  ) {
+    self.gl.bind_renderbuffer(gl::RENDERBUFFER, Some(renderbuffer));
+    self.gl.renderbuffer_storage(gl::RENDERBUFFER, format.renderbuffer_format_constant(), width, height);
+  }
+
+  fn set_pipeline_state(&self, state: &PipelineState) {
+    let PipelineState { depth, stencil, cull, scissor, blend, color_mask } = *state;
+    self.set_capability(Capability::DepthTest, depth.is_some());
+    self.set_capability(Capability::StencilTest, stencil.is_some());
+    self.set_capability(Capability::CullFace, cull.is_some());
+    self.set_capability(Capability::ScissorTest, scissor.is_some());
+    self.set_capability(Capability::Blend, blend);
+
+    match depth {
+      Some(DepthState { func, write }) => {
+        self.gl.depth_func(func.compare_func_constant());
+        self.gl.depth_mask(write);
+      },
+      None => self.gl.depth_mask(true),
+    }
+    match stencil {
+      Some(StencilState { func, reference, mask, fail, depth_fail, pass, write_mask }) => {
+        self.gl.stencil_func(func.compare_func_constant(), reference, mask);
+        self.gl.stencil_op(fail.stencil_op_constant(), depth_fail.stencil_op_constant(), pass.stencil_op_constant());
+        self.gl.stencil_mask(write_mask);
+      },
+      None => self.gl.stencil_mask(!0),
+    }
+    if let Some(face) = cull {
+      self.gl.cull_face(face.cull_face_constant());
+    }
+    if let Some(ScissorRect { x, y, width, height }) = scissor {
+      self.gl.scissor(x, y, width, height);
+    }
+    let ColorMask { red, green, blue, alpha } = color_mask;
+    self.gl.color_mask(red, green, blue, alpha);
+  }
+
+  fn set_uniform<U>(&self, key: U, value: UniformValue) -> Result<(), RenderApiError> where U: IntoUniformIndex {
+    let index = key.with_context(self)?;
+    let location = Some(&index);
+    match value {
+      UniformValue::Float(x) => self.gl.uniform1f(location, x),
+      UniformValue::Vec2([x, y]) => self.gl.uniform2f(location, x, y),
+      UniformValue::Vec3([x, y, z]) => self.gl.uniform3f(location, x, y, z),
+      UniformValue::Vec4([x, y, z, w]) => self.gl.uniform4f(location, x, y, z, w),
+      UniformValue::Int(x) => self.gl.uniform1i(location, x),
+      UniformValue::IVec2([x, y]) => self.gl.uniform2i(location, x, y),
+      UniformValue::IVec3([x, y, z]) => self.gl.uniform3i(location, x, y, z),
+      UniformValue::IVec4([x, y, z, w]) => self.gl.uniform4i(location, x, y, z, w),
+      UniformValue::FloatArray(v) => self.gl.uniform1fv_with_f32_array(location, &v),
+      UniformValue::Vec2Array(v) => self.gl.uniform2fv_with_f32_array(location, &v.concat()),
+      UniformValue::Vec3Array(v) => self.gl.uniform3fv_with_f32_array(location, &v.concat()),
+      UniformValue::Vec4Array(v) => self.gl.uniform4fv_with_f32_array(location, &v.concat()),
+      UniformValue::IntArray(v) => self.gl.uniform1iv_with_i32_array(location, &v),
+      UniformValue::IVec2Array(v) => self.gl.uniform2iv_with_i32_array(location, &v.concat()),
+      UniformValue::IVec3Array(v) => self.gl.uniform3iv_with_i32_array(location, &v.concat()),
+      UniformValue::IVec4Array(v) => self.gl.uniform4iv_with_i32_array(location, &v.concat()),
+      UniformValue::Mat2(m) => self.gl.uniform_matrix2fv_with_f32_array(location, false, &m),
+      UniformValue::Mat3(m) => self.gl.uniform_matrix3fv_with_f32_array(location, false, &m),
+      UniformValue::Mat4(m) => self.gl.uniform_matrix4fv_with_f32_array(location, false, &m),
+    }
+    Ok(())
+  }
+
+  fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+    self.gl.viewport(x, y, width, height);
+  }
+
+  fn tex_image_2d(
+      &self,
+      texture: &Self::Texture,
+      width: i32,
+      height: i32,
+      pixels: &Uint8View,
+  ) -> Result<(), RenderApiError> {
+    check_texture_data(width, height, pixels)?;
+    let object = pixels.object().map_err(|_| RenderApiError::FailedToUploadTexture)?;
+    self.edit_texture(texture, || {
+      self.gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+          gl::TEXTURE_2D,
+          0,
+          gl::RGBA as i32,
+          width,
+          height,
+          0,
+          gl::RGBA,
+          pixels.view_precision_constant(),
+          Some(&object),
+      )
+    }).map_err(|_| RenderApiError::FailedToUploadTexture)
+  }
+
+  fn tex_parameter(&self, texture: &Self::Texture, parameter: TextureParameter) {
+    let (name, value) = parameter.texture_parameter_constants();
+    self.edit_texture(texture, || self.gl.tex_parameteri(gl::TEXTURE_2D, name, value as i32));
+  }
+
+  fn uniform_block_binding<B>(&self, key: B, binding: u32) -> Result<(), RenderApiError> where B: IntoUniformBlockIndex {
+    key.with_context(self).map(|index| {
+      self.gl.uniform_block_binding(&self.program.borrow().program, index, binding)
+    })
+  }
+
+  fn use_program(&self, program: &Self::Program) {
+    self.gl.use_program(Some(&program.program));
+    self.program.replace(program.clone());
+  }
+
+  fn vertex_attrib_divisor<A>(&self, key: A, divisor: u32) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| self.gl.vertex_attrib_divisor(index, divisor))
+  }
+
+  fn vertex_attrib_i_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| {
+      self.gl.vertex_attrib_i_pointer_with_i32(index, size, precision.view_precision_constant(), stride, offset)
+    })
+  }
+
+  fn vertex_attrib_pointer_with_i32<A>(
+      &self,
+      key: A,
+      size: i32,
+      precision: ViewPrecision,
+      normalized: bool,
+      stride: i32,
+      offset: i32
+  ) -> Result<(), RenderApiError> where A: IntoAttributeIndex {
+    key.with_context(self).map(|index| {
+      self.gl.vertex_attrib_pointer_with_i32(
+          index,
+          size,
+          precision.view_precision_constant(),
+          normalized,
+          stride,
+          offset,
+      )
+    })
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct WebRenderBuffer {
+  pub kind: BufferKind,
+  pub internal: WebGlBuffer,
+}
+
+impl HasBufferKind for WebRenderBuffer {
+  fn buffer_kind_constant(&self) -> u32 {
+    self.kind.buffer_kind_constant()
+  }
+}
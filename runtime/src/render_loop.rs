@@ -4,49 +4,105 @@ use super::render::api::{
   RenderApiError,
   UniformKey,
 };
-use super::render::data::{Float32View, DataViewError, View};
+use super::render::data::{Float32View, Uint16View, DataViewError};
 use super::render::constants::{
-  BufferKind,
   DrawArrayKind,
-  DrawKind,
   ClearMask,
-  HasBufferKind,
+  ShaderType,
 };
+use super::render::locations::{ProgramKeys};
+use super::render::mesh::{Mesh, MeshAttribute};
 
 #[derive(Clone, Copy, Debug)]
 enum VertexAttributes {
   Position,
+  Cell,
 }
 
 #[derive(Clone, Copy, Debug)]
 enum VertexUniforms {
   Resoultion,
+  Origin,
+  Spacing,
+  Size,
 }
 
 impl AttributeKey for VertexAttributes {
   fn name(&self) -> &str {
     match self {
-      VertexAttributes::Position => "position"
+      VertexAttributes::Position => "position",
+      VertexAttributes::Cell => "cell",
     }
   }
+
+  fn shader_type(&self) -> Option<ShaderType> {
+    Some(ShaderType::FloatVec2)
+  }
 }
 
 impl UniformKey for VertexUniforms {
   fn name(&self) -> &str {
     match self {
-      VertexUniforms::Resoultion => "resolution"
+      VertexUniforms::Resoultion => "resolution",
+      VertexUniforms::Origin => "origin",
+      VertexUniforms::Spacing => "spacing",
+      VertexUniforms::Size => "size",
+    }
+  }
+
+  fn shader_type(&self) -> Option<ShaderType> {
+    match self {
+      VertexUniforms::Resoultion => Some(ShaderType::FloatVec2),
+      VertexUniforms::Origin => Some(ShaderType::FloatVec2),
+      VertexUniforms::Spacing => Some(ShaderType::Float),
+      VertexUniforms::Size => Some(ShaderType::Float),
     }
   }
 }
 
+/**
+ * Every attribute & uniform the render loop uses, which the
+ * program it's given needs to have.
+ */
+pub fn program_keys() -> ProgramKeys {
+  ProgramKeys::create()
+    .with_attributes(&[VertexAttributes::Position, VertexAttributes::Cell])
+    .with_uniforms(&[
+      VertexUniforms::Resoultion,
+      VertexUniforms::Origin,
+      VertexUniforms::Spacing,
+      VertexUniforms::Size,
+    ])
+}
+
+/**
+ * Draws the grid followed by any meshes that have been added,
+ * each mesh keeping its own vertex layout in a vertex array.
+ *
+ * The grid is a single square instanced once per cell, so
+ * resizing it only updates the uniforms describing where the
+ * cells are drawn.
+ */
 #[derive(Debug)]
-pub struct RenderLoop<R, B> {
-  view: Float32View,
-  buffer: B,
+pub struct RenderLoop<R: RenderAPI> {
+  grid: Mesh<R>,
+  meshes: Vec<Mesh<R>>,
   context: R,
 }
 
-fn grid_points(width: i32, height: i32, row_len: u32, col_len: u32) -> Vec<f32> {
+/**
+ * Where the squares of the grid are drawn in pixels, where
+ * `origin` is the top left of the first square, `spacing`
+ * is the distance between squares & `size` is their length.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct GridLayout {
+  origin: [f32; 2],
+  spacing: f32,
+  size: f32,
+}
+
+fn grid_layout(width: i32, height: i32, row_len: u32, col_len: u32) -> GridLayout {
   let width_f = width as f32;
   let height_f = height as f32;
 
@@ -57,83 +113,96 @@ fn grid_points(width: i32, height: i32, row_len: u32, col_len: u32) -> Vec<f32>
   // the distance from the top to center the grid in the screen
   let height_offset = (height_f / 2.0) - ((col_len as f32 * grid_outer) / 2.0);
 
-  let write_point = |write_to: &mut [f32], x_offset: f32, y_offset: f32| {
-    write_to[0] = x_offset;
-    write_to[1] = y_offset;
-    write_to[2] = 0.0;
-  };
-
-  let write_triangle = |triangle: &mut [f32], x: f32, y: f32, length: f32| {
-    write_point(&mut triangle[0..3], x, y);
-    write_point(&mut triangle[3..6], x + length, y);
-    write_point(&mut triangle[6..9], x, y + length);
-  };
-
-  let make_square_points = |row_start: f32, col_start: f32| -> [f32; 18] {
-    let mut square: [f32; 18] = [0.0; 18];
-    let length = grid_inner;
-    let row_offset = (row_start * grid_outer) + border;
-    let col_offset = (col_start * grid_outer) + border + height_offset;
-    write_triangle(&mut square[0..9], row_offset, col_offset, length);
-    write_triangle(&mut square[9..18], row_offset + length, col_offset + length, -length);
-    square
-  };
+  GridLayout {
+    origin: [border, border + height_offset],
+    spacing: grid_outer,
+    size: grid_inner,
+  }
+}
 
-  let mut grid = vec![];
+/**
+ * The row & column of each square, which is all that varies
+ * between the instances of the square.
+ */
+fn grid_cells(row_len: u32, col_len: u32) -> Vec<f32> {
+  let mut cells = vec![];
   for r_index in 0..row_len {
     for c_index in 0..col_len {
-      let square_points = make_square_points(r_index as f32, c_index as f32);
-      grid.extend_from_slice(&square_points);
+      cells.extend_from_slice(&[r_index as f32, c_index as f32]);
     }
   }
-
-  grid
+  cells
 }
 
-fn get_view_data(width: i32, height: i32) -> Vec<f32> {
-  grid_points(width, height, 12, 3)
+/**
+ * A unit square, as two triangles sharing the vertices along
+ * the diagonal.
+ */
+const SQUARE_POINTS: [f32; 8] = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+const SQUARE_INDICES: [u16; 6] = [0, 1, 2, 3, 2, 1];
+
+const ROW_LEN: u32 = 12;
+const COL_LEN: u32 = 3;
+
+fn set_grid_uniforms<R>(context: &R, width: i32, height: i32) -> Result<(), RenderApiError> where R: RenderAPI {
+  let GridLayout { origin, spacing, size } = grid_layout(width, height, ROW_LEN, COL_LEN);
+  context.uniform2f(VertexUniforms::Resoultion, width as f32, height as f32)?;
+  context.uniform2f(VertexUniforms::Origin, origin[0], origin[1])?;
+  context.uniform1f(VertexUniforms::Spacing, spacing)?;
+  context.uniform1f(VertexUniforms::Size, size)
 }
 
-impl<R, B> RenderLoop<R, B> where R: RenderAPI<Buffer=B>, B: HasBufferKind {
+impl<R> RenderLoop<R> where R: RenderAPI {
   pub fn create(context: R, width: i32, height: i32) -> Result<Self, RenderLoopError> {
-    let buffer = context.create_buffer(BufferKind::ArrayBuffer)?;
-    let data = get_view_data(width, height);
-    let view = Float32View::create(&data)?;
-    context.bind_buffer(&buffer, &view, DrawKind::StaticDraw);
+    let points = Float32View::create(&SQUARE_POINTS)?;
+    let indices = Uint16View::create(&SQUARE_INDICES)?;
+    let position = MeshAttribute::packed(VertexAttributes::Position, 2);
+    let mut grid = Mesh::create_indexed(&context, DrawArrayKind::Triangles, &points, &indices, &[position])?;
 
-    let position = VertexAttributes::Position;
-    let precision = view.get_precision();
-    context.vertex_attrib_pointer_with_i32(position, 3, precision, false, 0, 0)?;
-    context.enable_vertex_attrib_array(position)?;
+    let cells = Float32View::create(&grid_cells(ROW_LEN, COL_LEN))?;
+    let cell = MeshAttribute::packed(VertexAttributes::Cell, 2);
+    grid.set_instances(&context, &cells, &[cell])?;
 
-    let resolution = VertexUniforms::Resoultion;
-    context.uniform2f(resolution, width as f32, height as f32)?;
+    set_grid_uniforms(&context, width, height)?;
 
-    Ok(RenderLoop { buffer, view, context })
+    Ok(RenderLoop { grid, meshes: vec![], context })
+  }
+
+  /**
+   * Adds a mesh to be drawn after the grid every frame, it
+   * should be created with the same context as the loop.
+   */
+  pub fn add_mesh(&mut self, mesh: Mesh<R>) {
+    self.meshes.push(mesh);
   }
 
   pub fn draw(&self) {
     self.context.clear_color(0.0, 0.0, 0.0, 1.0);
     self.context.clear(ClearMask::ColorBufferBit);
 
-    let count = (self.view.length() / 3) as i32;
-    self.context.draw_arrays(DrawArrayKind::Triangles, 0, count);
+    self.grid.draw(&self.context);
+    for mesh in self.meshes.iter() {
+      mesh.draw(&self.context);
+    }
   }
 
   pub fn update_viewport(&mut self, width: i32, height: i32) -> Result<(), RenderLoopError> {
-    let data = get_view_data(width, height);
-
     self.context.set_viewport(0, 0, width, height);
-    self.view.update_data(&data)?;
-    self.context.bind_buffer(&self.buffer, &self.view, DrawKind::StaticDraw);
-
-    let resolution = VertexUniforms::Resoultion;
-    self.context.uniform2f(resolution, width as f32, height as f32)?;
+    set_grid_uniforms(&self.context, width, height)?;
 
     return Ok(());
   }
+
+  pub fn context(&self) -> &R {
+    &self.context
+  }
+
+  pub fn into_context(self) -> R {
+    self.context
+  }
 }
 
+#[derive(Debug)]
 pub enum RenderLoopError {
   RenderApiError(RenderApiError),
   DataViewError(DataViewError),
@@ -159,3 +228,305 @@ impl ToString for RenderLoopError {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::render::api::{UniformValue};
+  use super::super::render::cache::{CachedRenderAPI};
+  use super::super::render::constants::{BufferKind, DrawKind, IndexKind, ViewPrecision};
+  use super::super::render::data::{View};
+  use super::super::render::golden::{assert_matches_golden};
+  use super::super::render::reflection::{ActiveVariable, ProgramReflection};
+  use super::super::render::recording::{
+    Command,
+    RecordingBuffer,
+    RecordingRenderAPI,
+    RecordingUniformIndex,
+    RecordingVertexArray,
+  };
+  use super::super::render::software::{GridShader, SoftwareRenderAPI};
+
+  fn create_loop(width: i32, height: i32) -> RenderLoop<RecordingRenderAPI> {
+    let api = RecordingRenderAPI::create(&["position", "cell"], &["resolution", "origin", "spacing", "size"]);
+    RenderLoop::create(api, width, height).unwrap()
+  }
+
+  fn grid_uniforms(width: i32, height: i32) -> Vec<Command> {
+    let layout = grid_layout(width, height, ROW_LEN, COL_LEN);
+    vec![
+      Command::SetUniform { index: RecordingUniformIndex(0), value: UniformValue::Vec2([width as f32, height as f32]) },
+      Command::SetUniform { index: RecordingUniformIndex(1), value: UniformValue::Vec2(layout.origin) },
+      Command::SetUniform { index: RecordingUniformIndex(2), value: UniformValue::Float(layout.spacing) },
+      Command::SetUniform { index: RecordingUniformIndex(3), value: UniformValue::Float(layout.size) },
+    ]
+  }
+
+  #[test]
+  fn create_uploads_square_and_instances() {
+    let render_loop = create_loop(640, 480);
+    let buffer = RecordingBuffer { id: 0, kind: BufferKind::ArrayBuffer };
+    let index_buffer = RecordingBuffer { id: 1, kind: BufferKind::ElementBuffer };
+    let instance_buffer = RecordingBuffer { id: 2, kind: BufferKind::ArrayBuffer };
+    let points = Float32View::create(&SQUARE_POINTS).unwrap();
+    let indices = Uint16View::create(&SQUARE_INDICES).unwrap();
+    let cells = Float32View::create(&grid_cells(ROW_LEN, COL_LEN)).unwrap();
+
+    let vertex_array = RecordingVertexArray(0);
+
+    let mut expected = vec![
+      Command::CreateVertexArray(vertex_array),
+      Command::CreateBuffer(buffer),
+      Command::CreateBuffer(index_buffer),
+      Command::BindVertexArray(Some(vertex_array)),
+      Command::BufferData {
+        buffer,
+        precision: ViewPrecision::Float,
+        data: points.to_bytes(),
+        draw_kind: DrawKind::StaticDraw,
+      },
+      Command::BufferData {
+        buffer: index_buffer,
+        precision: ViewPrecision::UnsignedShort,
+        data: indices.to_bytes(),
+        draw_kind: DrawKind::StaticDraw,
+      },
+      Command::VertexAttribPointer {
+        index: 0,
+        size: 2,
+        precision: ViewPrecision::Float,
+        normalized: false,
+        stride: 0,
+        offset: 0,
+      },
+      Command::EnableVertexAttribArray(0),
+      Command::VertexAttribDivisor { index: 0, divisor: 0 },
+      Command::BindVertexArray(None),
+      Command::CreateBuffer(instance_buffer),
+      Command::BindVertexArray(Some(vertex_array)),
+      Command::BufferData {
+        buffer: instance_buffer,
+        precision: ViewPrecision::Float,
+        data: cells.to_bytes(),
+        draw_kind: DrawKind::StaticDraw,
+      },
+      Command::VertexAttribPointer {
+        index: 1,
+        size: 2,
+        precision: ViewPrecision::Float,
+        normalized: false,
+        stride: 0,
+        offset: 0,
+      },
+      Command::EnableVertexAttribArray(1),
+      Command::VertexAttribDivisor { index: 1, divisor: 1 },
+      Command::BindVertexArray(None),
+    ];
+    expected.extend(grid_uniforms(640, 480));
+    assert_eq!(render_loop.context.take_commands(), expected);
+  }
+
+  #[test]
+  fn create_fails_without_resolution_uniform() {
+    let api = RecordingRenderAPI::create(&["position", "cell"], &[]);
+    match RenderLoop::create(api, 640, 480) {
+      Err(RenderLoopError::RenderApiError(RenderApiError::InvalidUniformName(name))) => {
+        assert_eq!(name, "resolution");
+      },
+      other => panic!("expected invalid uniform name, got {:?}", other.map(|_| ())),
+    }
+  }
+
+  #[test]
+  fn program_keys_match_the_shaders() {
+    let api = RecordingRenderAPI::create(&["position", "cell"], &["resolution", "origin", "spacing", "size"]);
+    assert!(program_keys().resolve(&api).is_ok());
+
+    let api = RecordingRenderAPI::create(&["position"], &["resolution", "origin", "spacing", "size"]);
+    assert!(program_keys().resolve(&api).is_err());
+  }
+
+  #[test]
+  fn program_keys_match_the_shader_types() {
+    let attribute = |name: &str, location| {
+      ActiveVariable { name: name.to_string(), kind: ShaderType::FloatVec2, size: 1, location }
+    };
+    let uniform = |name: &str, kind| ActiveVariable { name: name.to_string(), kind, size: 1, location: None };
+    let mut reflection: ProgramReflection<usize> = ProgramReflection {
+      attributes: vec![attribute("position", 0), attribute("cell", 1)],
+      uniforms: vec![
+        uniform("resolution", ShaderType::FloatVec2),
+        uniform("origin", ShaderType::FloatVec2),
+        uniform("spacing", ShaderType::Float),
+        uniform("size", ShaderType::Float),
+      ],
+    };
+    assert_eq!(reflection.check_keys(&program_keys()), Ok(()));
+
+    reflection.uniforms[2].kind = ShaderType::Int;
+    assert!(reflection.check_keys(&program_keys()).is_err());
+  }
+
+  #[test]
+  fn draw_clears_and_draws_every_square() {
+    let render_loop = create_loop(640, 480);
+    render_loop.context.take_commands();
+    render_loop.draw();
+
+    assert_eq!(render_loop.context.take_commands(), vec![
+      Command::ClearColor { red: 0.0, green: 0.0, blue: 0.0, alpha: 1.0 },
+      Command::Clear(ClearMask::ColorBufferBit.into()),
+      Command::BindVertexArray(Some(RecordingVertexArray(0))),
+      Command::DrawElementsInstanced {
+        mode: DrawArrayKind::Triangles,
+        count: 6,
+        kind: IndexKind::UnsignedShort,
+        offset: 0,
+        instance_count: 12 * 3,
+      },
+      Command::BindVertexArray(None),
+    ]);
+  }
+
+  #[test]
+  fn cached_draws_only_set_the_clear_color_once() {
+    let api = RecordingRenderAPI::create(&["position", "cell"], &["resolution", "origin", "spacing", "size"]);
+    let render_loop = RenderLoop::create(CachedRenderAPI::create(api), 640, 480).unwrap();
+    render_loop.draw();
+    render_loop.context.inner().take_commands();
+    render_loop.context.reset_stats();
+    render_loop.draw();
+
+    assert_eq!(render_loop.context.inner().take_commands(), vec![
+      Command::Clear(ClearMask::ColorBufferBit.into()),
+      Command::BindVertexArray(Some(RecordingVertexArray(0))),
+      Command::DrawElementsInstanced {
+        mode: DrawArrayKind::Triangles,
+        count: 6,
+        kind: IndexKind::UnsignedShort,
+        offset: 0,
+        instance_count: 12 * 3,
+      },
+      Command::BindVertexArray(None),
+    ]);
+    assert_eq!(render_loop.context.stats().skipped, 1);
+  }
+
+  #[test]
+  fn draws_added_meshes_without_respecifying_attributes() {
+    let mut render_loop = create_loop(640, 480);
+    let vertices = Float32View::create(&[0.0, 0.0, 0.0, 10.0, 0.0, 0.0, 0.0, 10.0, 0.0]).unwrap();
+    let position = MeshAttribute::packed(VertexAttributes::Position, 3);
+    let mesh = Mesh::create(render_loop.context(), DrawArrayKind::Triangles, &vertices, &[position]).unwrap();
+    render_loop.add_mesh(mesh);
+    render_loop.context.take_commands();
+    render_loop.draw();
+
+    assert_eq!(&render_loop.context.take_commands()[5..], &[
+      Command::BindVertexArray(Some(RecordingVertexArray(1))),
+      Command::DrawArrays { mode: DrawArrayKind::Triangles, first: 0, count: 3 },
+      Command::BindVertexArray(None),
+    ]);
+  }
+
+  #[test]
+  fn grid_cells_are_row_and_column_of_each_square() {
+    let cells = grid_cells(ROW_LEN, COL_LEN);
+    assert_eq!(cells.len(), 12 * 3 * 2);
+    assert_eq!(&cells[..8], &[0.0, 0.0, 0.0, 1.0, 0.0, 2.0, 1.0, 0.0]);
+  }
+
+  #[test]
+  fn grid_layout_centers_grid_vertically() {
+    let layout = grid_layout(640, 480, ROW_LEN, COL_LEN);
+    let border = (640.0 / 12.0) * 0.075;
+    assert_eq!(layout.spacing, (640.0 - border) / 12.0);
+    assert_eq!(layout.size, layout.spacing - border);
+    assert_eq!(layout.origin, [border, border + (240.0 - (3.0 * layout.spacing) / 2.0)]);
+  }
+
+  #[test]
+  fn update_viewport_only_sets_uniforms() {
+    let mut render_loop = create_loop(640, 480);
+    render_loop.context.take_commands();
+    render_loop.update_viewport(800, 300).unwrap();
+
+    let mut expected = vec![Command::SetViewport { x: 0, y: 0, width: 800, height: 300 }];
+    expected.extend(grid_uniforms(800, 300));
+    assert_eq!(render_loop.context.take_commands(), expected);
+  }
+
+  /**
+   * Covers wide, tall and odd sized viewports, as the border
+   * and `height_offset` are derived from both dimensions.
+   */
+  const GOLDEN_SIZES: [(i32, i32); 6] = [
+    (160, 120),
+    (120, 160),
+    (97, 61),
+    (255, 43),
+    (64, 64),
+    (33, 200),
+  ];
+
+  #[test]
+  fn grid_matches_goldens() {
+    for &(width, height) in GOLDEN_SIZES.iter() {
+      let api = SoftwareRenderAPI::create(GridShader, width as usize, height as usize);
+      let render_loop = RenderLoop::create(api, width, height).unwrap();
+      render_loop.draw();
+
+      let name = format!("grid_{}x{}", width, height);
+      let pixels = render_loop.context.canvas_pixels();
+      assert_matches_golden(&name, width as u32, height as u32, &pixels, 2);
+    }
+  }
+
+  #[test]
+  fn resized_grid_matches_goldens() {
+    let api = SoftwareRenderAPI::create(GridShader, 97, 61);
+    let mut render_loop = RenderLoop::create(api, 64, 64).unwrap();
+    render_loop.update_viewport(97, 61).unwrap();
+    render_loop.draw();
+
+    let pixels = render_loop.context.canvas_pixels();
+    assert_matches_golden("grid_97x61", 97, 61, &pixels, 2);
+  }
+
+  /**
+   * Draws a frame with the native backend into a framebuffer,
+   * which should come out like the software backend's frame.
+   */
+  #[cfg(feature = "egl")]
+  #[test]
+  fn native_grid_matches_golden() {
+    use super::super::render::constants::{FramebufferAttachment, RenderbufferFormat};
+    use super::super::render::egl::{EglContext};
+    use super::super::render::native::{NativeRenderAPI};
+    use super::super::render::preprocessor::{ShaderPreprocessor};
+
+    let preprocessor = ShaderPreprocessor::create()
+      .with_chunk("clip_space", include_str!("../../shaders/chunks/clip_space.glsl"));
+    let vert = preprocessor.process("vertex", include_str!("../../shaders/vert_shader.glsl")).unwrap();
+    let frag = preprocessor.process("fragment", include_str!("../../shaders/frag_shader.glsl")).unwrap();
+
+    let egl = EglContext::create().expect("surfaceless EGL context");
+    let api = NativeRenderAPI::build(egl.load(), &vert.source, &frag.source)
+      .map_err(|e| e.to_string()).unwrap();
+    let (width, height) = (64, 64);
+    let framebuffer = api.create_framebuffer().unwrap();
+    let renderbuffer = api.create_renderbuffer().unwrap();
+    api.renderbuffer_storage(&renderbuffer, RenderbufferFormat::Rgba8, width, height);
+    api.framebuffer_renderbuffer(&framebuffer, FramebufferAttachment::Color(0), &renderbuffer);
+    api.check_framebuffer_status(&framebuffer).unwrap();
+    api.set_viewport(0, 0, width, height);
+
+    let render_loop = RenderLoop::create(api, width, height).unwrap();
+    render_loop.draw();
+
+    // read back bottom row first, where the golden is top first.
+    let pixels = render_loop.context.read_pixels(0, 0, width, height).unwrap();
+    let pixels = pixels.chunks(width as usize * 4).rev().flatten().copied().collect::<Vec<_>>();
+    assert_matches_golden("grid_64x64", width as u32, height as u32, &pixels, 2);
+  }
+}
@@ -0,0 +1,200 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{JsCast};
+use web_sys::{WebGl2RenderingContext};
+use super::render::builder::{BuildError, RenderBuilder};
+use super::render::cache::{CachedRenderAPI};
+use super::render::preprocessor::{ShaderPreprocessor};
+use super::render::web::{WebRenderAPI};
+use super::render_loop::{self, RenderLoop};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+}
+
+macro_rules! console_log {
+    ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
+}
+
+type WebRenderLoop = RenderLoop<CachedRenderAPI<WebRenderAPI>>;
+
+#[derive(Debug, Copy, Clone)]
+struct Dimensions {
+  width: i32,
+  height: i32,
+}
+
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct Runtime {
+  render_loop: WebRenderLoop,
+  dimensions: Dimensions,
+}
+
+#[wasm_bindgen]
+impl Runtime {
+  fn new(render_loop: WebRenderLoop, dimensions: Dimensions) -> Self {
+    Runtime { render_loop, dimensions }
+  }
+
+  #[wasm_bindgen]
+  pub fn tick(&self) {
+    self.render_loop.draw();
+  }
+
+  #[wasm_bindgen(js_name = "debugState")]
+  pub fn debug_state(&self) {
+    console_log!("Debug: {:#?}", self);
+  }
+
+  #[wasm_bindgen(js_name = "setDimensions")]
+  pub fn set_dimensions(&mut self, width: i32, height: i32) -> Result<(), JsValue> {
+    self.dimensions = Dimensions { width, height };
+    self.render_loop.update_viewport(width, height).map_err(error_to_string)
+  }
+}
+
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct RuntimeBuilder {
+  dimensions: Option<Dimensions>,
+  render_builder: RenderBuilder,
+  preprocessor: ShaderPreprocessor,
+}
+
+fn error_to_string<E>(error: E) -> JsValue where E: ToString {
+  return JsValue::from_str(error.to_string().as_ref())
+}
+
+/**
+ * A JS `Error` with the same message as `error_to_string`,
+ * along with a `kind` & for shaders that fail to compile or
+ * link, the `stage`, `log` & the `errors` on each line.
+ */
+fn build_error_to_js(error: BuildError) -> JsValue {
+  let js_error: JsValue = js_sys::Error::new(error.to_string().as_ref()).into();
+  set_property(&js_error, "kind", &error.kind().into());
+
+  match &error {
+    BuildError::FailedToCompileShader(compile_error) => {
+      set_property(&js_error, "stage", &compile_error.stage.name().into());
+      set_property(&js_error, "log", &compile_error.log.clone().into());
+
+      let errors = js_sys::Array::new();
+      for source_error in compile_error.errors.iter() {
+        let context = js_sys::Array::new();
+        for line in source_error.context.iter() {
+          let js_line: JsValue = js_sys::Object::new().into();
+          set_property(&js_line, "number", &(line.number as u32).into());
+          set_property(&js_line, "text", &line.text.as_str().into());
+          context.push(&js_line);
+        }
+
+        let js_source_error: JsValue = js_sys::Object::new().into();
+        set_property(&js_source_error, "file", &source_error.file.clone().into());
+        set_property(&js_source_error, "line", &(source_error.line as u32).into());
+        set_property(&js_source_error, "message", &source_error.message.as_str().into());
+        set_property(&js_source_error, "context", &context);
+        errors.push(&js_source_error);
+      }
+      set_property(&js_error, "errors", &errors);
+    },
+    BuildError::FailedToLinkProgram(log) => {
+      set_property(&js_error, "log", &log.clone().into());
+    },
+    _ => {},
+  }
+  js_error
+}
+
+fn set_property(target: &JsValue, key: &str, value: &JsValue) {
+  // this only fails on frozen objects or proxies, and every
+  // target here is a new plain object.
+  let _ = js_sys::Reflect::set(target, &key.into(), value);
+}
+
+#[wasm_bindgen]
+impl RuntimeBuilder {
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> Result<RuntimeBuilder, JsValue> {
+    let mut render_builder = RenderBuilder::new();
+    render_builder.set_program_keys(render_loop::program_keys());
+    let dimensions = None;
+    let preprocessor = ShaderPreprocessor::create();
+    Ok(RuntimeBuilder { render_builder, dimensions, preprocessor })
+  }
+
+  #[wasm_bindgen(js_name = "linkWebglContext")]
+  pub fn link_webgl_context(&mut self, maybe_context: JsValue) -> Result<(), JsValue> {
+    return maybe_context.dyn_into::<WebGl2RenderingContext>()
+      .map(|context| self.render_builder.set_context(context))
+      .map_err(|value| {
+        let message = format!("expected web gl context, instead got {:?}", value);
+        return JsValue::from_str(message.as_ref())
+      });
+  }
+
+  #[wasm_bindgen(js_name = "linkFragShader")]
+  pub fn link_frag_shader(&mut self, shader_source: &str) -> Result<(), JsValue> {
+    self.render_builder.set_preprocessor(self.preprocessor.clone());
+    return self.render_builder.set_frag_shader(shader_source).map_err(build_error_to_js)
+  }
+
+  #[wasm_bindgen(js_name = "linkVertShader")]
+  pub fn link_vert_shader(&mut self, shader_source: &str) -> Result<(), JsValue> {
+    self.render_builder.set_preprocessor(self.preprocessor.clone());
+    return self.render_builder.set_vert_shader(shader_source).map_err(build_error_to_js)
+  }
+
+  /**
+   * Makes `#include "name"` available to shaders linked
+   * after this.
+   */
+  #[wasm_bindgen(js_name = "addShaderChunk")]
+  pub fn add_shader_chunk(&mut self, name: &str, source: &str) {
+    self.preprocessor = std::mem::take(&mut self.preprocessor).with_chunk(name, source);
+  }
+
+  #[wasm_bindgen(js_name = "addShaderDefine")]
+  pub fn add_shader_define(&mut self, name: &str, value: &str) {
+    self.preprocessor = std::mem::take(&mut self.preprocessor).with_define(name, value);
+  }
+
+  #[wasm_bindgen(js_name = "addShaderFeature")]
+  pub fn add_shader_feature(&mut self, name: &str) {
+    self.preprocessor = std::mem::take(&mut self.preprocessor).with_features(&[name]);
+  }
+
+  #[wasm_bindgen(js_name = "createRuntime")]
+  pub fn create_runtime(&mut self) -> Result<Runtime, JsValue> {
+    let dimensions = self.dimensions.ok_or("need dimensions before building runtime")?;
+    let render_loop = self.render_builder.build_render_api()
+      .map_err(build_error_to_js)
+      .and_then(|render_api|
+          RenderLoop::create(
+            CachedRenderAPI::create(render_api),
+            dimensions.width,
+            dimensions.height,
+          ).map_err(error_to_string))?;
+
+    Ok(Runtime::new(render_loop, dimensions))
+  }
+
+  #[wasm_bindgen(js_name = "setDimensions")]
+  pub fn set_dimensions(&mut self, width: i32, height: i32) {
+    self.dimensions = Some(Dimensions { width, height });
+  }
+
+  #[wasm_bindgen(js_name = "debugState")]
+  pub fn debug_state(&self) {
+    console_log!("Debug: {:#?}", self);
+  }
+}
+
+
+#[wasm_bindgen(js_name = "setupPanicHook")]
+pub fn setup_panic_hook() {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+}
@@ -0,0 +1,14 @@
+// converts a position in pixels, where (0, 0) is the top
+// left, to clip space.
+vec4 pixelsToClipSpace(vec2 pixels, vec2 resolution) {
+  // convert the position from pixels to 0.0 to 1.0
+  vec2 zeroToOne = pixels / resolution;
+
+  // convert from 0->1 to 0->2 to -1->+1
+  vec2 clipSpace = (zeroToOne * 2.0) - 1.0;
+
+  // flip the y axis, so (0, 0) is the top left
+  vec2 clipSpace2d = clipSpace * vec2(1, -1);
+
+  return vec4(clipSpace2d, 0, 1);
+}
@@ -1,21 +1,28 @@
 #version 300 es
 
+// a corner of the unit square
 in vec2 position;
 
+// the row & column of the square, once per instance
+in vec2 cell;
+
 out vec4 v_color;
 
 uniform vec2 resolution;
 
-void main() {
-  // convert the position from pixels to 0.0 to 1.0
-  vec2 zeroToOne = position / resolution;
+// the top left of the first square in pixels
+uniform vec2 origin;
 
-  // convert from 0->1 to 0->2 to -1->+1
-  vec2 clipSpace = (zeroToOne * 2.0) - 1.0;
+// the distance between squares in pixels
+uniform float spacing;
 
-  // flip the y axis, so (0, 0) is the top left
-  vec2 clipSpace2d = clipSpace * vec2(1, -1);
+// the length of a square in pixels
+uniform float size;
 
-  gl_Position = vec4(clipSpace2d, 0, 1);
+#include "clip_space"
+
+void main() {
+  vec2 pixels = (origin + (cell * spacing)) + (position * size);
+  gl_Position = pixelsToClipSpace(pixels, resolution);
   v_color = gl_Position * 0.5 + 0.5;
 }
@@ -4,11 +4,15 @@ import "./style.css";
 import { useRuntime } from 'runtime/use_runtime';
 import { loadRuntime } from 'runtime/load';
 import { RuntimeModule } from 'runtime/types';
+import clipSpaceChunk from 'shaders/chunks/clip_space.glsl';
 import fragShader from 'shaders/frag_shader.glsl';
 import vertShader from 'shaders/vert_shader.glsl';
 import { Canvas } from 'ui/canvas/canvas';
 import { Viewport } from 'ui/viewport/viewport';
 
+// defined once, as the runtime is rebuilt when these change
+const shaderChunks = { clip_space: clipSpaceChunk };
+
 function getRootNode() {
   const applicationRoot = document.createElement('div');
   applicationRoot.id = "application-root";
@@ -38,6 +42,7 @@ function main() {
           onError,
           vertShader,
           fragShader,
+          shaderChunks,
       )
   );
 
@@ -3,3 +3,19 @@ import { Runtime } from '../../runtime/pkg/webpack_demo.d';
 export type RuntimeModule = typeof import("../../runtime/pkg");
 
 export { Runtime }
+
+/**
+ * Thrown by `RuntimeBuilder` when the shaders fail to build,
+ * where `kind` is the name of the `BuildError` variant.
+ */
+export type BuildError = Error & {
+  kind: string,
+  stage?: 'vertex' | 'fragment',
+  log?: string | null,
+  errors?: Array<{
+    file: string | null,
+    line: number,
+    message: string,
+    context: Array<{ number: number, text: string }>,
+  }>,
+};
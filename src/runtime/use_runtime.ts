@@ -11,6 +11,7 @@ export function useRuntime(
     onError: (e: Error) => void,
     vertShader: string,
     fragShader: string,
+    shaderChunks: Record<string, string>,
 ) {
   const [runtime, setRuntime] = React.useState<Runtime | undefined>();
   const getContext = createContextFactory(canvas);
@@ -29,6 +30,9 @@ export function useRuntime(
 
     try {
       builder.linkWebglContext(context);
+      for (const [name, source] of Object.entries(shaderChunks)) {
+        builder.addShaderChunk(name, source);
+      }
       builder.linkVertShader(vertShader);
       builder.linkFragShader(fragShader);
       builder.setDimensions(width, height);
@@ -46,7 +50,7 @@ export function useRuntime(
     } catch (e) {
       onError(e);
     }
-  }, [fragShader, vertShader, canvas, module]);
+  }, [fragShader, vertShader, shaderChunks, canvas, module]);
 
   React.useEffect(function onResize() {
     if (runtime == null) return;